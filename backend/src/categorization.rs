@@ -0,0 +1,130 @@
+//! Matching of items against a user's categorization rules.
+
+use chrono::NaiveDate;
+use regex::Regex;
+use sqlx::SqlitePool;
+
+use crate::models::{CategorizationRule, Money};
+
+pub const RULE_COLUMNS: &str = "id, user_id, category_id, priority, description_contains, description_regex, min_amount, max_amount, start_date, end_date";
+
+/// A user's rules in the order they are tried, with patterns compiled once.
+pub struct RuleSet {
+    rules: Vec<(CategorizationRule, Option<Regex>)>,
+}
+
+impl RuleSet {
+    pub async fn load(pool: &SqlitePool, user_id: i64) -> Result<Self, sqlx::Error> {
+        let rules: Vec<CategorizationRule> = sqlx::query_as(&format!(
+            "SELECT {RULE_COLUMNS} FROM categorization_rules WHERE user_id = ? ORDER BY priority, id"
+        ))
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Self::new(rules))
+    }
+
+    /// Builds a rule set from rules already in priority order. Rules whose
+    /// pattern does not compile never match.
+    pub fn new(rules: Vec<CategorizationRule>) -> Self {
+        let rules = rules
+            .into_iter()
+            .filter_map(
+                |rule| match rule.description_regex.as_deref().map(Regex::new) {
+                    None => Some((rule, None)),
+                    Some(Ok(regex)) => Some((rule, Some(regex))),
+                    Some(Err(_)) => None,
+                },
+            )
+            .collect();
+        Self { rules }
+    }
+
+    /// Category of the first rule matching the item, if any.
+    pub fn category_for(&self, description: &str, amount: Money, date: NaiveDate) -> Option<i64> {
+        let lowercase = description.to_lowercase();
+        self.rules
+            .iter()
+            .find(|(rule, regex)| {
+                rule.description_contains
+                    .as_ref()
+                    .is_none_or(|needle| lowercase.contains(&needle.to_lowercase()))
+                    && regex.as_ref().is_none_or(|r| r.is_match(description))
+                    && rule.min_amount.is_none_or(|min| amount >= min)
+                    && rule.max_amount.is_none_or(|max| amount <= max)
+                    && rule.start_date.is_none_or(|start| date >= start)
+                    && rule.end_date.is_none_or(|end| date <= end)
+            })
+            .map(|(rule, _)| rule.category_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, category_id: i64) -> CategorizationRule {
+        CategorizationRule {
+            id,
+            user_id: 1,
+            category_id,
+            priority: 0,
+            description_contains: None,
+            description_regex: None,
+            min_amount: None,
+            max_amount: None,
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut coffee = rule(1, 10);
+        coffee.description_contains = Some("COFFEE".to_string());
+        coffee.max_amount = Some(Money::from_minor(1000));
+        let mut shop = rule(2, 20);
+        shop.description_regex = Some(r"(?i)^(coffee|tea) shop".to_string());
+        let rules = RuleSet::new(vec![coffee, shop]);
+
+        let small = Money::from_minor(450);
+        let large = Money::from_minor(4500);
+        assert_eq!(rules.category_for("Coffee Shop", small, date(1)), Some(10));
+        assert_eq!(rules.category_for("Coffee Shop", large, date(1)), Some(20));
+        assert_eq!(rules.category_for("Tea shop", small, date(1)), Some(20));
+        assert_eq!(rules.category_for("Bakery", small, date(1)), None);
+    }
+
+    #[test]
+    fn test_date_and_amount_bounds_are_inclusive() {
+        let mut rent = rule(1, 30);
+        rent.min_amount = Some(Money::from_minor(100000));
+        rent.start_date = Some(date(1));
+        rent.end_date = Some(date(5));
+        let rules = RuleSet::new(vec![rent]);
+
+        let amount = Money::from_minor(100000);
+        assert_eq!(rules.category_for("Transfer", amount, date(5)), Some(30));
+        assert_eq!(rules.category_for("Transfer", amount, date(6)), None);
+        assert_eq!(
+            rules.category_for("Transfer", Money::from_minor(99999), date(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_never_matches() {
+        let mut broken = rule(1, 10);
+        broken.description_regex = Some("(".to_string());
+        let rules = RuleSet::new(vec![broken, rule(2, 20)]);
+        assert_eq!(
+            rules.category_for("anything", Money::ZERO, date(1)),
+            Some(20)
+        );
+    }
+}
@@ -0,0 +1,826 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use sqlx::{SqliteConnection, SqlitePool};
+use thiserror::Error;
+
+type MigrationFuture<'a> = Pin<Box<dyn Future<Output = Result<(), sqlx::Error>> + Send + 'a>>;
+
+enum Step {
+    /// One or more SQL statements executed as a batch.
+    Sql(&'static str),
+    /// Schema changes that depend on the current shape of the database.
+    Code(for<'a> fn(&'a mut SqliteConnection) -> MigrationFuture<'a>),
+}
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    step: Step,
+}
+
+/// Every schema change, in the order it must be applied. Versions are never
+/// reused or reordered; new changes are appended with the next version.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        step: Step::Sql(INITIAL_SCHEMA),
+    },
+    Migration {
+        version: 2,
+        name: "legacy_user_columns",
+        step: Step::Code(legacy_user_columns),
+    },
+    Migration {
+        version: 3,
+        name: "money_minor_units",
+        step: Step::Code(money_minor_units),
+    },
+    Migration {
+        version: 4,
+        name: "csv_import_profiles",
+        step: Step::Sql(CSV_IMPORT_PROFILES),
+    },
+    Migration {
+        version: 5,
+        name: "transaction_fitids",
+        step: Step::Sql(TRANSACTION_FITIDS),
+    },
+    Migration {
+        version: 6,
+        name: "categorization_rules",
+        step: Step::Sql(CATEGORIZATION_RULES),
+    },
+    Migration {
+        version: 7,
+        name: "budget_rollover",
+        step: Step::Sql(BUDGET_ROLLOVER),
+    },
+    Migration {
+        version: 8,
+        name: "snapshot_versions",
+        step: Step::Sql(SNAPSHOT_VERSIONS),
+    },
+    Migration {
+        version: 9,
+        name: "monthly_fixed_expenses",
+        step: Step::Sql(MONTHLY_FIXED_EXPENSES),
+    },
+    Migration {
+        version: 10,
+        name: "income_templates",
+        step: Step::Sql(INCOME_TEMPLATES),
+    },
+    Migration {
+        version: 11,
+        name: "recurring_transactions",
+        step: Step::Sql(RECURRING_TRANSACTIONS),
+    },
+    Migration {
+        version: 12,
+        name: "accounts",
+        step: Step::Sql(ACCOUNTS),
+    },
+    Migration {
+        version: 13,
+        name: "transfers",
+        step: Step::Sql(TRANSFERS),
+    },
+    Migration {
+        version: 14,
+        name: "ledger_entries",
+        step: Step::Sql(LEDGER_ENTRIES),
+    },
+    Migration {
+        version: 15,
+        name: "savings_goals",
+        step: Step::Sql(SAVINGS_GOALS),
+    },
+    Migration {
+        version: 16,
+        name: "item_splits",
+        step: Step::Sql(ITEM_SPLITS),
+    },
+    Migration {
+        version: 17,
+        name: "tags",
+        step: Step::Sql(TAGS),
+    },
+    Migration {
+        version: 18,
+        name: "attachments",
+        step: Step::Sql(ATTACHMENTS),
+    },
+    Migration {
+        version: 19,
+        name: "item_search",
+        step: Step::Sql(ITEM_SEARCH),
+    },
+    Migration {
+        version: 20,
+        name: "sessions",
+        step: Step::Sql(SESSIONS),
+    },
+    Migration {
+        version: 21,
+        name: "api_tokens",
+        step: Step::Sql(API_TOKENS),
+    },
+    Migration {
+        version: 22,
+        name: "two_factor",
+        step: Step::Sql(TWO_FACTOR),
+    },
+    Migration {
+        version: 23,
+        name: "two_factor_lockout",
+        step: Step::Sql(TWO_FACTOR_LOCKOUT),
+    },
+    Migration {
+        version: 24,
+        name: "fitid_accounts",
+        step: Step::Sql(FITID_ACCOUNTS),
+    },
+    Migration {
+        version: 25,
+        name: "user_currency",
+        step: Step::Sql(USER_CURRENCY),
+    },
+];
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Database schema version {database} is newer than this binary supports ({binary}); upgrade payme before starting it against this database")]
+    DatabaseNewer { database: i64, binary: i64 },
+
+    #[error("Invalid export file: {0}")]
+    InvalidExport(String),
+
+    #[error("Migration {version} ({name}) failed: {source}")]
+    Failed {
+        version: i64,
+        name: &'static str,
+        source: sqlx::Error,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub applied_at: Option<String>,
+}
+
+impl MigrationStatus {
+    pub fn is_applied(&self) -> bool {
+        self.applied_at.is_some()
+    }
+}
+
+/// Highest schema version known to this binary.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+/// Applies every pending migration, each inside its own transaction.
+pub async fn run_migrations(pool: &SqlitePool) -> Result<(), MigrationError> {
+    ensure_migrations_table(pool).await?;
+    let applied = applied_versions(pool).await?;
+
+    if let Some(&newest) = applied.iter().max() {
+        if newest > latest_version() {
+            return Err(MigrationError::DatabaseNewer {
+                database: newest,
+                binary: latest_version(),
+            });
+        }
+    }
+
+    for migration in MIGRATIONS {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+
+        let failed = |source| MigrationError::Failed {
+            version: migration.version,
+            name: migration.name,
+            source,
+        };
+
+        let mut tx = pool.begin().await?;
+        match migration.step {
+            Step::Sql(sql) => sqlx::query(sql)
+                .execute(&mut *tx)
+                .await
+                .map(|_| ())
+                .map_err(failed)?,
+            Step::Code(apply) => apply(&mut tx).await.map_err(failed)?,
+        }
+        sqlx::query("INSERT INTO schema_migrations (version, name) VALUES (?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            "Applied migration {} ({})",
+            migration.version,
+            migration.name
+        );
+    }
+
+    Ok(())
+}
+
+/// Lists every known migration along with when it was applied, if it has been.
+pub async fn migration_status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>, MigrationError> {
+    ensure_migrations_table(pool).await?;
+    let applied: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?;
+
+    let mut status: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|m| MigrationStatus {
+            version: m.version,
+            name: m.name.to_string(),
+            applied_at: applied
+                .iter()
+                .find(|(version, _, _)| *version == m.version)
+                .map(|(_, _, applied_at)| applied_at.clone()),
+        })
+        .collect();
+
+    // Versions recorded by a newer binary are still reported so the mismatch is visible.
+    for (version, name, applied_at) in applied {
+        if version > latest_version() {
+            status.push(MigrationStatus {
+                version,
+                name,
+                applied_at: Some(applied_at),
+            });
+        }
+    }
+
+    Ok(status)
+}
+
+async fn ensure_migrations_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT version FROM schema_migrations")
+        .fetch_all(pool)
+        .await
+}
+
+async fn column_type(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT type FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_optional(conn)
+        .await
+}
+
+const INITIAL_SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    savings INTEGER NOT NULL DEFAULT 0,
+    savings_goal INTEGER NOT NULL DEFAULT 0,
+    retirement_savings INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS fixed_expenses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS budget_categories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    default_amount INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS months (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    year INTEGER NOT NULL,
+    month INTEGER NOT NULL,
+    is_closed INTEGER NOT NULL DEFAULT 0,
+    closed_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE(user_id, year, month)
+);
+
+CREATE TABLE IF NOT EXISTS income_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS monthly_budgets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    allocated_amount INTEGER NOT NULL,
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE,
+    UNIQUE(month_id, category_id)
+);
+
+CREATE TABLE IF NOT EXISTS items (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    spent_on TEXT NOT NULL,
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS monthly_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL UNIQUE,
+    pdf_data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
+);
+"#;
+
+const CSV_IMPORT_PROFILES: &str = r#"
+CREATE TABLE csv_import_profiles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    delimiter TEXT NOT NULL DEFAULT ',',
+    has_header INTEGER NOT NULL DEFAULT 1,
+    date_column INTEGER NOT NULL,
+    description_column INTEGER NOT NULL,
+    amount_column INTEGER NOT NULL,
+    sign_convention TEXT NOT NULL DEFAULT 'expenses_negative',
+    decimal_separator TEXT NOT NULL DEFAULT 'dot',
+    date_format TEXT NOT NULL DEFAULT '%Y-%m-%d',
+    category_id INTEGER,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE SET NULL,
+    UNIQUE(user_id, name)
+);
+"#;
+
+/// Bank transaction ids kept from OFX imports so a statement is only imported once.
+const TRANSACTION_FITIDS: &str = r#"
+ALTER TABLE items ADD COLUMN fitid TEXT;
+ALTER TABLE income_entries ADD COLUMN fitid TEXT;
+CREATE INDEX idx_items_fitid ON items(fitid) WHERE fitid IS NOT NULL;
+CREATE INDEX idx_income_entries_fitid ON income_entries(fitid) WHERE fitid IS NOT NULL;
+"#;
+
+const CATEGORIZATION_RULES: &str = r#"
+CREATE TABLE categorization_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    description_contains TEXT,
+    description_regex TEXT,
+    min_amount INTEGER,
+    max_amount INTEGER,
+    start_date TEXT,
+    end_date TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
+);
+"#;
+
+const BUDGET_ROLLOVER: &str = r#"
+ALTER TABLE budget_categories ADD COLUMN rollover_mode TEXT NOT NULL DEFAULT 'none';
+ALTER TABLE monthly_budgets ADD COLUMN carried_amount INTEGER NOT NULL DEFAULT 0;
+"#;
+
+/// Months can be reopened and closed again, so each close adds a numbered
+/// snapshot instead of replacing the only one.
+const SNAPSHOT_VERSIONS: &str = r#"
+CREATE TABLE monthly_snapshots_versioned (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    pdf_data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
+    UNIQUE(month_id, version)
+);
+INSERT INTO monthly_snapshots_versioned (id, month_id, version, pdf_data, created_at)
+    SELECT id, month_id, 1, pdf_data, created_at FROM monthly_snapshots;
+DROP TABLE monthly_snapshots;
+ALTER TABLE monthly_snapshots_versioned RENAME TO monthly_snapshots;
+"#;
+
+/// Fixed expenses as they applied to each month. Existing months get a copy of
+/// the current fixed expenses, the closest record of what they used to be.
+const MONTHLY_FIXED_EXPENSES: &str = r#"
+CREATE TABLE monthly_fixed_expenses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    fixed_expense_id INTEGER,
+    label TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
+    FOREIGN KEY (fixed_expense_id) REFERENCES fixed_expenses(id) ON DELETE SET NULL
+);
+CREATE INDEX idx_monthly_fixed_expenses_month ON monthly_fixed_expenses(month_id);
+INSERT INTO monthly_fixed_expenses (month_id, fixed_expense_id, label, amount)
+    SELECT m.id, fe.id, fe.label, fe.amount
+    FROM months m
+    JOIN fixed_expenses fe ON fe.user_id = m.user_id
+    ORDER BY m.id, fe.id;
+"#;
+
+const INCOME_TEMPLATES: &str = r#"
+CREATE TABLE income_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    schedule TEXT NOT NULL,
+    anchor_date TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+"#;
+
+const RECURRING_TRANSACTIONS: &str = r#"
+CREATE TABLE recurring_transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    frequency TEXT NOT NULL,
+    interval INTEGER NOT NULL DEFAULT 1,
+    start_date TEXT NOT NULL,
+    day_of_month INTEGER,
+    end_date TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
+);
+CREATE TABLE recurring_overrides (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recurring_id INTEGER NOT NULL,
+    occurs_on TEXT NOT NULL,
+    skipped INTEGER NOT NULL DEFAULT 0,
+    amount INTEGER,
+    description TEXT,
+    FOREIGN KEY (recurring_id) REFERENCES recurring_transactions(id) ON DELETE CASCADE,
+    UNIQUE(recurring_id, occurs_on)
+);
+ALTER TABLE items ADD COLUMN recurring_id INTEGER REFERENCES recurring_transactions(id) ON DELETE SET NULL;
+CREATE INDEX idx_items_recurring ON items(recurring_id) WHERE recurring_id IS NOT NULL;
+"#;
+
+/// Savings and retirement balances kept on `users` become the opening balances
+/// of a savings and a retirement account; the `users` columns are no longer read.
+const ACCOUNTS: &str = r#"
+CREATE TABLE accounts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    opening_balance INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+ALTER TABLE items ADD COLUMN account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL;
+ALTER TABLE income_entries ADD COLUMN account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL;
+CREATE INDEX idx_items_account ON items(account_id) WHERE account_id IS NOT NULL;
+CREATE INDEX idx_income_entries_account ON income_entries(account_id) WHERE account_id IS NOT NULL;
+INSERT INTO accounts (user_id, name, kind, opening_balance)
+SELECT id, 'Savings', 'savings', savings FROM users WHERE savings != 0;
+INSERT INTO accounts (user_id, name, kind, opening_balance)
+SELECT id, 'Retirement', 'retirement', retirement_savings FROM users WHERE retirement_savings != 0;
+"#;
+
+const TRANSFERS: &str = r#"
+CREATE TABLE transfers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    month_id INTEGER NOT NULL,
+    from_account_id INTEGER NOT NULL,
+    to_account_id INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    transferred_on TEXT NOT NULL,
+    FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
+    FOREIGN KEY (from_account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    FOREIGN KEY (to_account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_transfers_month ON transfers(month_id);
+"#;
+
+/// Dated changes to an account balance made by hand rather than through
+/// items, income or transfers. `amount` is signed.
+const LEDGER_ENTRIES: &str = r#"
+CREATE TABLE ledger_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    entry_date TEXT NOT NULL,
+    note TEXT,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_ledger_entries_account ON ledger_entries(account_id, entry_date);
+"#;
+
+const SAVINGS_GOALS: &str = r#"
+CREATE TABLE savings_goals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    target_amount INTEGER NOT NULL,
+    target_date TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    account_id INTEGER REFERENCES accounts(id) ON DELETE SET NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE TABLE goal_allocations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    goal_id INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    allocated_on TEXT NOT NULL,
+    FOREIGN KEY (goal_id) REFERENCES savings_goals(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_goal_allocations_goal ON goal_allocations(goal_id);
+"#;
+
+/// Items with splits count towards their split categories instead of their own.
+const ITEM_SPLITS: &str = r#"
+CREATE TABLE item_splits (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    category_id INTEGER NOT NULL,
+    amount INTEGER NOT NULL,
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE,
+    FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_item_splits_item ON item_splits(item_id);
+"#;
+
+const TAGS: &str = r#"
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE(user_id, name)
+);
+CREATE TABLE item_tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    UNIQUE(item_id, tag_id),
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_item_tags_tag ON item_tags(tag_id);
+"#;
+
+/// Receipts and other documents uploaded for an item, stored inline like
+/// month snapshots so they follow the item through deletes and exports.
+const ATTACHMENTS: &str = r#"
+CREATE TABLE attachments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    filename TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (item_id) REFERENCES items(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_attachments_item ON attachments(item_id);
+"#;
+
+/// Full-text index over item descriptions, kept in step with `items` by
+/// triggers.
+const ITEM_SEARCH: &str = r#"
+CREATE VIRTUAL TABLE items_fts USING fts5(description, content='items', content_rowid='id');
+INSERT INTO items_fts (rowid, description) SELECT id, description FROM items;
+CREATE TRIGGER items_fts_insert AFTER INSERT ON items BEGIN
+    INSERT INTO items_fts (rowid, description) VALUES (new.id, new.description);
+END;
+CREATE TRIGGER items_fts_delete AFTER DELETE ON items BEGIN
+    INSERT INTO items_fts (items_fts, rowid, description) VALUES ('delete', old.id, old.description);
+END;
+CREATE TRIGGER items_fts_update AFTER UPDATE OF description ON items BEGIN
+    INSERT INTO items_fts (items_fts, rowid, description) VALUES ('delete', old.id, old.description);
+    INSERT INTO items_fts (rowid, description) VALUES (new.id, new.description);
+END;
+CREATE INDEX idx_items_spent_on ON items(spent_on);
+"#;
+
+/// Login sessions behind the short-lived access tokens. Only hashes of the
+/// refresh tokens are kept; the previous one is remembered to detect reuse.
+const SESSIONS: &str = r#"
+CREATE TABLE sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    previous_token_hash TEXT,
+    user_agent TEXT,
+    ip TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    last_seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+    rotated_at TEXT,
+    expires_at TEXT NOT NULL,
+    revoked_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_sessions_user ON sessions(user_id);
+CREATE INDEX idx_sessions_previous_token ON sessions(previous_token_hash);
+"#;
+
+/// Personal access tokens for scripts, stored as hashes like refresh tokens.
+const API_TOKENS: &str = r#"
+CREATE TABLE api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    prefix TEXT NOT NULL,
+    scopes TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at TEXT,
+    last_used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_api_tokens_user ON api_tokens(user_id);
+"#;
+
+/// TOTP second factor. `totp_secret` is set when enrollment starts and
+/// `totp_enabled_at` once a code confirms it; `totp_last_step` stops a code
+/// being used twice. Recovery codes and login challenges are stored as hashes.
+const TWO_FACTOR: &str = r#"
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_enabled_at TEXT;
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
+CREATE TABLE recovery_codes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    code_hash TEXT NOT NULL,
+    used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_recovery_codes_user ON recovery_codes(user_id);
+CREATE TABLE login_challenges (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+CREATE INDEX idx_login_challenges_user ON login_challenges(user_id);
+"#;
+
+/// Second-factor attempts since the last success, across all login
+/// challenges, and when a lockout they triggered ends.
+const TWO_FACTOR_LOCKOUT: &str = r#"
+ALTER TABLE users ADD COLUMN totp_failures INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN totp_locked_until TEXT;
+"#;
+
+/// FITIDs are only unique within one bank account, so they are stored with
+/// the statement's ACCTID. A transaction's date fixes its month, so keying
+/// the index on the month stops one being imported twice. The import checks
+/// for duplicates in the same per-month scope.
+const FITID_ACCOUNTS: &str = r#"
+ALTER TABLE items ADD COLUMN fitid_account TEXT;
+ALTER TABLE income_entries ADD COLUMN fitid_account TEXT;
+DROP INDEX idx_items_fitid;
+DROP INDEX idx_income_entries_fitid;
+CREATE UNIQUE INDEX idx_items_fitid ON items(month_id, COALESCE(fitid_account, ''), fitid) WHERE fitid IS NOT NULL;
+CREATE UNIQUE INDEX idx_income_entries_fitid ON income_entries(month_id, COALESCE(fitid_account, ''), fitid) WHERE fitid IS NOT NULL;
+"#;
+
+/// The currency all of a user's amounts are in.
+const USER_CURRENCY: &str = r#"
+ALTER TABLE users ADD COLUMN currency TEXT NOT NULL DEFAULT 'USD'
+    CHECK (currency IN ('USD', 'EUR', 'GBP', 'CAD', 'AUD'));
+"#;
+
+/// Databases created before savings tracking lack some `users` columns, and
+/// the earliest ones kept retirement savings in a `roth_ira` column.
+fn legacy_user_columns(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for column in ["savings", "savings_goal", "retirement_savings"] {
+            if column_type(conn, "users", column).await?.is_none() {
+                sqlx::query(&format!(
+                    "ALTER TABLE users ADD COLUMN {column} REAL NOT NULL DEFAULT 0"
+                ))
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+
+        if column_type(conn, "users", "roth_ira").await?.is_some() {
+            sqlx::query(
+                "UPDATE users SET retirement_savings = roth_ira WHERE retirement_savings = 0 AND roth_ira IS NOT NULL AND roth_ira > 0",
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    })
+}
+
+/// Every money column, stored as integer minor units (cents).
+const MONEY_COLUMNS: &[(&str, &str)] = &[
+    ("users", "savings"),
+    ("users", "savings_goal"),
+    ("users", "retirement_savings"),
+    ("fixed_expenses", "amount"),
+    ("budget_categories", "default_amount"),
+    ("income_entries", "amount"),
+    ("monthly_budgets", "allocated_amount"),
+    ("items", "amount"),
+];
+
+/// Rewrites money columns created as `REAL` dollars by older versions into
+/// `INTEGER` cents. Rounding to the nearest cent is lossless for values that
+/// were entered with at most two decimal places.
+fn money_minor_units(conn: &mut SqliteConnection) -> MigrationFuture<'_> {
+    Box::pin(async move {
+        for (table, column) in MONEY_COLUMNS {
+            let is_real = column_type(conn, table, column)
+                .await?
+                .is_some_and(|t| t.eq_ignore_ascii_case("REAL"));
+            if !is_real {
+                continue;
+            }
+
+            let statements = [
+                format!("ALTER TABLE {table} ADD COLUMN {column}_minor INTEGER NOT NULL DEFAULT 0"),
+                format!(
+                    "UPDATE {table} SET {column}_minor = CAST(ROUND({column} * 100) AS INTEGER)"
+                ),
+                format!("ALTER TABLE {table} DROP COLUMN {column}"),
+                format!("ALTER TABLE {table} RENAME COLUMN {column}_minor TO {column}"),
+            ];
+            for statement in &statements {
+                sqlx::query(statement).execute(&mut *conn).await?;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_strictly_increasing() {
+        let versions: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert!(versions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(versions.first(), Some(&1));
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        let mut names: Vec<&str> = MIGRATIONS.iter().map(|m| m.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), MIGRATIONS.len());
+    }
+}
@@ -1,5 +1,13 @@
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 
+mod migrations;
+mod user_data;
+
+pub use migrations::{
+    latest_version, migration_status, run_migrations, MigrationError, MigrationStatus,
+};
+pub use user_data::{export_user_database, import_user_database};
+
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     let pool = SqlitePoolOptions::new()
         .max_connections(5)
@@ -7,148 +15,3 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error>
         .await?;
     Ok(pool)
 }
-
-pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL,
-            savings REAL NOT NULL DEFAULT 0,
-            savings_goal REAL NOT NULL DEFAULT 0,
-            created_at TEXT NOT NULL DEFAULT (datetime('now'))
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query("ALTER TABLE users ADD COLUMN savings REAL NOT NULL DEFAULT 0")
-        .execute(pool)
-        .await
-        .ok();
-
-    sqlx::query("ALTER TABLE users ADD COLUMN retirement_savings REAL NOT NULL DEFAULT 0")
-        .execute(pool)
-        .await
-        .ok();
-
-    sqlx::query("ALTER TABLE users ADD COLUMN savings_goal REAL NOT NULL DEFAULT 0")
-        .execute(pool)
-        .await
-        .ok();
-
-    sqlx::query("UPDATE users SET retirement_savings = roth_ira WHERE retirement_savings = 0 AND roth_ira IS NOT NULL AND roth_ira > 0")
-        .execute(pool)
-        .await
-        .ok();
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS fixed_expenses (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            amount REAL NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS budget_categories (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            default_amount REAL NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS months (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            year INTEGER NOT NULL,
-            month INTEGER NOT NULL,
-            is_closed INTEGER NOT NULL DEFAULT 0,
-            closed_at TEXT,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-            UNIQUE(user_id, year, month)
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS income_entries (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            amount REAL NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS monthly_budgets (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            category_id INTEGER NOT NULL,
-            allocated_amount REAL NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
-            FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE,
-            UNIQUE(month_id, category_id)
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS items (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            category_id INTEGER NOT NULL,
-            description TEXT NOT NULL,
-            amount REAL NOT NULL,
-            spent_on TEXT NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
-            FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS monthly_snapshots (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL UNIQUE,
-            pdf_data BLOB NOT NULL,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await?;
-
-    Ok(())
-}
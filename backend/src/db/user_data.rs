@@ -0,0 +1,393 @@
+use std::path::Path;
+
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Connection, SqliteConnection, SqlitePool};
+
+use super::migrations::{run_migrations, MigrationError};
+
+/// A table whose rows belong to a single user, either directly through a
+/// user id column or transitively through a foreign key to another such table.
+struct UserTable {
+    name: &'static str,
+    user_column: Option<&'static str>,
+    /// Foreign keys to other user tables as `(column, referenced table)`. For
+    /// tables without a `user_column`, the first entry determines ownership.
+    references: &'static [(&'static str, &'static str)],
+}
+
+/// Per-user tables in dependency order: parents always precede their children.
+const USER_TABLES: &[UserTable] = &[
+    UserTable {
+        name: "fixed_expenses",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "budget_categories",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "csv_import_profiles",
+        user_column: Some("user_id"),
+        references: &[("category_id", "budget_categories")],
+    },
+    UserTable {
+        name: "categorization_rules",
+        user_column: Some("user_id"),
+        references: &[("category_id", "budget_categories")],
+    },
+    UserTable {
+        name: "recurring_transactions",
+        user_column: Some("user_id"),
+        references: &[("category_id", "budget_categories")],
+    },
+    UserTable {
+        name: "recurring_overrides",
+        user_column: None,
+        references: &[("recurring_id", "recurring_transactions")],
+    },
+    UserTable {
+        name: "income_templates",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "accounts",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "ledger_entries",
+        user_column: None,
+        references: &[("account_id", "accounts")],
+    },
+    UserTable {
+        name: "savings_goals",
+        user_column: Some("user_id"),
+        references: &[("account_id", "accounts")],
+    },
+    UserTable {
+        name: "goal_allocations",
+        user_column: None,
+        references: &[("goal_id", "savings_goals")],
+    },
+    UserTable {
+        name: "tags",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "months",
+        user_column: Some("user_id"),
+        references: &[],
+    },
+    UserTable {
+        name: "monthly_fixed_expenses",
+        user_column: None,
+        references: &[
+            ("month_id", "months"),
+            ("fixed_expense_id", "fixed_expenses"),
+        ],
+    },
+    UserTable {
+        name: "income_entries",
+        user_column: None,
+        references: &[("month_id", "months"), ("account_id", "accounts")],
+    },
+    UserTable {
+        name: "monthly_budgets",
+        user_column: None,
+        references: &[("month_id", "months"), ("category_id", "budget_categories")],
+    },
+    UserTable {
+        name: "items",
+        user_column: None,
+        references: &[
+            ("month_id", "months"),
+            ("category_id", "budget_categories"),
+            ("recurring_id", "recurring_transactions"),
+            ("account_id", "accounts"),
+        ],
+    },
+    UserTable {
+        name: "item_splits",
+        user_column: None,
+        references: &[("item_id", "items"), ("category_id", "budget_categories")],
+    },
+    UserTable {
+        name: "item_tags",
+        user_column: None,
+        references: &[("item_id", "items"), ("tag_id", "tags")],
+    },
+    UserTable {
+        name: "attachments",
+        user_column: None,
+        references: &[("item_id", "items")],
+    },
+    UserTable {
+        name: "transfers",
+        user_column: None,
+        references: &[
+            ("month_id", "months"),
+            ("from_account_id", "accounts"),
+            ("to_account_id", "accounts"),
+        ],
+    },
+    UserTable {
+        name: "monthly_snapshots",
+        user_column: None,
+        references: &[("month_id", "months")],
+    },
+];
+
+/// Columns of `users` that are exported and restored. Credentials never leave
+/// the server and are never overwritten by an import. Savings balances live in
+/// `accounts` instead.
+const USER_PROFILE_COLUMNS: &[&str] = &["savings_goal", "currency"];
+
+const ATTACHED: &str = "user_export";
+
+/// Writes a standalone SQLite database at `path`, with the full current
+/// schema, containing only the rows owned by `user_id`.
+pub async fn export_user_database(
+    pool: &SqlitePool,
+    user_id: i64,
+    path: &Path,
+) -> Result<(), MigrationError> {
+    let export = open_file_pool(path).await?;
+    let result = run_migrations(&export).await;
+    export.close().await;
+    result?;
+
+    let mut conn = pool.acquire().await?;
+    attach(&mut conn, path).await?;
+    let result = copy_user_rows(&mut conn, user_id).await;
+    detach(&mut conn).await?;
+    result?;
+
+    Ok(())
+}
+
+/// Replaces all of `user_id`'s data with the contents of a database produced
+/// by [`export_user_database`]. Row ids are renumbered so they never collide
+/// with rows belonging to other users.
+pub async fn import_user_database(
+    pool: &SqlitePool,
+    user_id: i64,
+    path: &Path,
+) -> Result<(), MigrationError> {
+    // Bring exports from older versions up to the current schema first.
+    let import = open_file_pool(path).await?;
+    let result = run_migrations(&import).await;
+    import.close().await;
+    result?;
+
+    let mut conn = pool.acquire().await?;
+    attach(&mut conn, path).await?;
+    let result = restore_user_rows(&mut conn, user_id).await;
+    detach(&mut conn).await?;
+    result?;
+
+    Ok(())
+}
+
+async fn open_file_pool(path: &Path) -> Result<SqlitePool, sqlx::Error> {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+}
+
+async fn attach(conn: &mut SqliteConnection, path: &Path) -> Result<(), sqlx::Error> {
+    // An explicit URI mode keeps the attachment on disk even when the main
+    // database is in memory, whose open flags would otherwise be inherited.
+    let uri = path
+        .display()
+        .to_string()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    sqlx::query(&format!("ATTACH DATABASE ? AS {ATTACHED}"))
+        .bind(format!("file:{uri}?mode=rw"))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+async fn detach(conn: &mut SqliteConnection) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!("DETACH DATABASE {ATTACHED}"))
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Columns of `table` in the server's own schema. Never read from an
+/// uploaded file, whose column names could carry SQL.
+async fn columns(conn: &mut SqliteConnection, table: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT name FROM pragma_table_info(?, 'main') ORDER BY cid")
+        .bind(table)
+        .fetch_all(&mut *conn)
+        .await
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Fails unless every reference in the attached file points at a row of the
+/// same file, so renumbered ids can only land on the rows being imported.
+async fn check_references(conn: &mut SqliteConnection) -> Result<(), MigrationError> {
+    for table in USER_TABLES {
+        for (column, parent) in table.references {
+            let dangling: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {ATTACHED}.{name} WHERE {column} IS NOT NULL AND {column} NOT IN (SELECT id FROM {ATTACHED}.{parent})",
+                name = table.name,
+            ))
+            .fetch_one(&mut *conn)
+            .await?;
+            if dangling > 0 {
+                return Err(MigrationError::InvalidExport(format!(
+                    "{}.{column} refers to missing {parent} rows",
+                    table.name
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// SQL condition selecting the rows of `table` in `schema` owned by `user_id`.
+fn ownership_filter(table: &UserTable, schema: &str, user_id: i64) -> String {
+    match table.user_column {
+        Some(column) => format!("{column} = {user_id}"),
+        None => {
+            let (column, parent) = table.references[0];
+            let parent_table = USER_TABLES
+                .iter()
+                .find(|t| t.name == parent)
+                .expect("referenced table must be a user table");
+            format!(
+                "{column} IN (SELECT id FROM {schema}.{parent} WHERE {})",
+                ownership_filter(parent_table, schema, user_id)
+            )
+        }
+    }
+}
+
+async fn copy_user_rows(conn: &mut SqliteConnection, user_id: i64) -> Result<(), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+
+    let mut user_columns = vec!["id", "username", "created_at"];
+    user_columns.extend_from_slice(USER_PROFILE_COLUMNS);
+    let user_columns = user_columns.join(", ");
+    sqlx::query(&format!(
+        "INSERT INTO {ATTACHED}.users ({user_columns}, password_hash) SELECT {user_columns}, '' FROM main.users WHERE id = ?"
+    ))
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    for table in USER_TABLES {
+        let columns = columns(&mut tx, table.name)
+            .await?
+            .iter()
+            .map(|c| quote(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO {ATTACHED}.{name} ({columns}) SELECT {columns} FROM main.{name} WHERE {filter}",
+            name = table.name,
+            filter = ownership_filter(table, "main", user_id),
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await
+}
+
+async fn restore_user_rows(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+) -> Result<(), MigrationError> {
+    check_references(conn).await?;
+    let mut tx = conn.begin().await?;
+
+    // Deleting the directly owned rows cascades to everything below them.
+    for table in USER_TABLES {
+        if let Some(column) = table.user_column {
+            sqlx::query(&format!(
+                "DELETE FROM main.{} WHERE {column} = ?",
+                table.name
+            ))
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    let assignments = USER_PROFILE_COLUMNS
+        .iter()
+        .map(|c| format!("{c} = (SELECT {c} FROM {ATTACHED}.users ORDER BY id LIMIT 1)"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    sqlx::query(&format!(
+        "UPDATE main.users SET {assignments} WHERE id = ? AND EXISTS (SELECT 1 FROM {ATTACHED}.users)"
+    ))
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let mut offsets: Vec<(&str, i64)> = Vec::new();
+    for table in USER_TABLES {
+        let offset: i64 = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(MAX(id), 0) FROM main.{}",
+            table.name
+        ))
+        .fetch_one(&mut *tx)
+        .await?;
+        offsets.push((table.name, offset));
+    }
+    let offset_of = |name: &str| {
+        offsets
+            .iter()
+            .find(|(table, _)| *table == name)
+            .map(|(_, offset)| *offset)
+            .unwrap_or(0)
+    };
+
+    for table in USER_TABLES {
+        let columns = columns(&mut tx, table.name).await?;
+        let values = columns
+            .iter()
+            .map(|column| {
+                let quoted = quote(column);
+                if column == "id" {
+                    format!("{quoted} + {}", offset_of(table.name))
+                } else if Some(column.as_str()) == table.user_column {
+                    user_id.to_string()
+                } else if let Some((_, parent)) = table.references.iter().find(|(c, _)| c == column)
+                {
+                    format!("{quoted} + {}", offset_of(parent))
+                } else {
+                    quoted
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        sqlx::query(&format!(
+            "INSERT INTO main.{name} ({columns}) SELECT {values} FROM {ATTACHED}.{name} ORDER BY id",
+            name = table.name,
+            columns = columns
+                .iter()
+                .map(|c| quote(c))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    Ok(tx.commit().await?)
+}
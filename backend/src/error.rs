@@ -1,9 +1,14 @@
+use std::collections::{BTreeMap, HashMap};
+
 use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
+    Json,
 };
+use serde::Serialize;
 use thiserror::Error;
-use validator::ValidationErrors;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
 #[derive(Error, Debug)]
 pub enum PaymeError {
@@ -19,25 +24,167 @@ pub enum PaymeError {
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Month is closed")]
+    MonthClosed,
+
+    /// A referenced category, account or tag that is missing or belongs to
+    /// another user.
+    #[error("Invalid {0}")]
+    InvalidReference(&'static str),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// A total of amounts too large to represent.
+    #[error("Amount out of range")]
+    AmountOutOfRange,
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
+/// Stable, machine-readable reason for an error response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    ValidationFailed,
+    BadRequest,
+    MonthClosed,
+    InvalidReference,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    TooManyRequests,
+    InternalError,
+}
+
+/// Body of every error response.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Validation failures keyed by field path, such as `name` or
+    /// `splits[1].amount`. Only present when `code` is `validation_failed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<BTreeMap<String, Vec<FieldError>>>,
+}
+
+/// One rule a field failed, such as `length` or `range`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FieldError {
+    pub code: String,
+    pub message: Option<String>,
+    /// The rule's limits, such as `min` and `max`.
+    #[schema(value_type = Object)]
+    pub params: HashMap<String, serde_json::Value>,
+}
+
+impl PaymeError {
+    fn status_and_code(&self) -> (StatusCode, ErrorCode) {
+        match self {
+            PaymeError::Database(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                (StatusCode::CONFLICT, ErrorCode::Conflict)
+            }
+            PaymeError::Database(_) | PaymeError::Internal(_) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, ErrorCode::InternalError)
+            }
+            PaymeError::Validation(_) => (StatusCode::BAD_REQUEST, ErrorCode::ValidationFailed),
+            PaymeError::NotFound => (StatusCode::NOT_FOUND, ErrorCode::NotFound),
+            PaymeError::Unauthorized => (StatusCode::UNAUTHORIZED, ErrorCode::Unauthorized),
+            PaymeError::Forbidden(_) => (StatusCode::FORBIDDEN, ErrorCode::Forbidden),
+            PaymeError::BadRequest(_) | PaymeError::AmountOutOfRange => {
+                (StatusCode::BAD_REQUEST, ErrorCode::BadRequest)
+            }
+            PaymeError::MonthClosed => (StatusCode::BAD_REQUEST, ErrorCode::MonthClosed),
+            PaymeError::InvalidReference(_) => {
+                (StatusCode::BAD_REQUEST, ErrorCode::InvalidReference)
+            }
+            PaymeError::Conflict(_) => (StatusCode::CONFLICT, ErrorCode::Conflict),
+            PaymeError::TooManyRequests(_) => {
+                (StatusCode::TOO_MANY_REQUESTS, ErrorCode::TooManyRequests)
+            }
+        }
+    }
+
+    fn body(&self) -> ErrorBody {
+        let (_, code) = self.status_and_code();
+        let message = match self {
+            PaymeError::BadRequest(message)
+            | PaymeError::Conflict(message)
+            | PaymeError::Forbidden(message)
+            | PaymeError::TooManyRequests(message) => message.clone(),
+            PaymeError::Validation(_) => "Validation failed".to_string(),
+            _ if code == ErrorCode::Conflict => "Already exists".to_string(),
+            _ if code == ErrorCode::InternalError => "Internal server error".to_string(),
+            e => e.to_string(),
+        };
+        let fields = match self {
+            PaymeError::Validation(errors) => {
+                let mut fields = BTreeMap::new();
+                collect_field_errors(errors, "", &mut fields);
+                Some(fields)
+            }
+            _ => None,
+        };
+        ErrorBody {
+            code,
+            message,
+            fields,
+        }
+    }
+}
+
+/// Flattens nested validation errors into `parent.child` and `list[i]` paths.
+fn collect_field_errors(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut BTreeMap<String, Vec<FieldError>>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{prefix}.{field}")
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                let errors = errors.iter().map(|e| FieldError {
+                    code: e.code.to_string(),
+                    message: e.message.as_ref().map(|m| m.to_string()),
+                    params: e
+                        .params
+                        .iter()
+                        .filter(|(name, _)| *name != "value")
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect(),
+                });
+                fields.entry(path).or_default().extend(errors);
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(errors, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(errors, &format!("{path}[{index}]"), fields);
+                }
+            }
+        }
+    }
+}
+
 impl IntoResponse for PaymeError {
     fn into_response(self) -> Response {
-        let status = match &self {
-            PaymeError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            PaymeError::Validation(_) => StatusCode::BAD_REQUEST,
-            PaymeError::NotFound => StatusCode::NOT_FOUND,
-            PaymeError::Unauthorized => StatusCode::UNAUTHORIZED,
-            PaymeError::BadRequest(_) => StatusCode::BAD_REQUEST,
-            PaymeError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let (status, _) = self.status_and_code();
         tracing::error!("{self}");
-        status.into_response()
+        (status, Json(self.body())).into_response()
     }
 }
 
@@ -67,6 +214,13 @@ mod tests {
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[test]
+    fn test_conflict_status() {
+        let error = PaymeError::Conflict("test".to_string());
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
     #[test]
     fn test_internal_status() {
         let error = PaymeError::Internal("test".to_string());
@@ -74,6 +228,26 @@ mod tests {
         assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[test]
+    fn test_month_closed_status() {
+        let response = PaymeError::MonthClosed.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_error_bodies() {
+        let body = serde_json::to_value(PaymeError::MonthClosed.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({ "code": "month_closed", "message": "Month is closed" })
+        );
+        let body = serde_json::to_value(PaymeError::InvalidReference("category").body()).unwrap();
+        assert_eq!(body["code"], "invalid_reference");
+        assert_eq!(body["message"], "Invalid category");
+        let body = serde_json::to_value(PaymeError::Internal("secret".to_string()).body()).unwrap();
+        assert_eq!(body["message"], "Internal server error");
+    }
+
     #[test]
     fn test_error_display() {
         assert_eq!(PaymeError::NotFound.to_string(), "Not found");
@@ -0,0 +1,257 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{Account, AccountKind, LedgerEntryKind, Money};
+
+/// Selects [`Account`] rows with their derived balance; append a `WHERE`.
+pub(crate) const ACCOUNT_SELECT: &str = r#"
+    SELECT a.id, a.user_id, a.name, a.kind, a.opening_balance,
+        a.opening_balance
+            + COALESCE((SELECT SUM(amount) FROM income_entries WHERE account_id = a.id), 0)
+            - COALESCE((SELECT SUM(amount) FROM items WHERE account_id = a.id), 0)
+            + COALESCE((SELECT SUM(amount) FROM transfers WHERE to_account_id = a.id), 0)
+            - COALESCE((SELECT SUM(amount) FROM transfers WHERE from_account_id = a.id), 0)
+            + COALESCE((SELECT SUM(amount) FROM ledger_entries WHERE account_id = a.id), 0)
+            AS balance
+    FROM accounts a
+"#;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveAccount {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    pub kind: AccountKind,
+    #[serde(default)]
+    pub opening_balance: Money,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/accounts",
+    responses(
+        (status = 200, body = [Account]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "List accounts",
+    description = "Retrieves the user's accounts with their current balances."
+)]
+pub async fn list_accounts(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<Account>>, PaymeError> {
+    let accounts: Vec<Account> = sqlx::query_as(&format!(
+        "{ACCOUNT_SELECT} WHERE a.user_id = ? ORDER BY a.id"
+    ))
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(accounts))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/accounts",
+    request_body = SaveAccount,
+    responses(
+        (status = 200, body = Account),
+        (status = 400, description = "Invalid account"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Create account",
+    description = "Adds an account. Items and income entries can then be linked to it."
+)]
+pub async fn create_account(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveAccount>,
+) -> Result<Json<Account>, PaymeError> {
+    payload.validate()?;
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO accounts (user_id, name, kind, opening_balance) VALUES (?, ?, ?, ?) RETURNING id",
+    )
+    .bind(claims.sub)
+    .bind(&payload.name)
+    .bind(payload.kind)
+    .bind(payload.opening_balance)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(Account {
+        id,
+        user_id: claims.sub,
+        name: payload.name,
+        kind: payload.kind,
+        opening_balance: payload.opening_balance,
+        balance: payload.opening_balance,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/accounts/{id}",
+    params(("id" = i64, Path, description = "Account ID")),
+    request_body = SaveAccount,
+    responses(
+        (status = 200, body = Account),
+        (status = 400, description = "Invalid account"),
+        (status = 404, description = "Account not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Update account",
+    description = "Replaces an account's name, type and opening balance."
+)]
+pub async fn update_account(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveAccount>,
+) -> Result<Json<Account>, PaymeError> {
+    payload.validate()?;
+
+    let result = sqlx::query(
+        "UPDATE accounts SET name = ?, kind = ?, opening_balance = ? WHERE id = ? AND user_id = ?",
+    )
+    .bind(&payload.name)
+    .bind(payload.kind)
+    .bind(payload.opening_balance)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    let account: Account = sqlx::query_as(&format!("{ACCOUNT_SELECT} WHERE a.id = ?"))
+        .bind(id)
+        .fetch_one(&pool)
+        .await?;
+
+    Ok(Json(account))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/accounts/{id}",
+    params(("id" = i64, Path, description = "Account ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Wealth",
+    summary = "Delete account",
+    description = "Removes an account with its transfers and ledger entries. Items and income entries linked to it are kept and unlinked."
+)]
+pub async fn delete_account(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM accounts WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks that an optional account link points at one of the user's accounts.
+pub(crate) async fn verify_account(
+    pool: &SqlitePool,
+    user_id: i64,
+    account_id: Option<i64>,
+) -> Result<(), PaymeError> {
+    let Some(account_id) = account_id else {
+        return Ok(());
+    };
+    let _account: (i64,) = sqlx::query_as("SELECT id FROM accounts WHERE id = ? AND user_id = ?")
+        .bind(account_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(PaymeError::InvalidReference("account"))?;
+    Ok(())
+}
+
+/// The combined balance of the user's accounts of one kind.
+pub(crate) async fn total_balance(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    kind: AccountKind,
+) -> Result<Money, sqlx::Error> {
+    sqlx::query_scalar(&format!(
+        "SELECT COALESCE(SUM(balance), 0) FROM ({ACCOUNT_SELECT} WHERE a.user_id = ? AND a.kind = ?)"
+    ))
+    .bind(user_id)
+    .bind(kind)
+    .fetch_one(conn)
+    .await
+}
+
+/// The oldest of the user's accounts of one kind, creating an empty one
+/// called `name` if there is none.
+pub(crate) async fn primary_account(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    kind: AccountKind,
+    name: &str,
+) -> Result<i64, sqlx::Error> {
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT MIN(id) FROM accounts WHERE user_id = ? AND kind = ?")
+            .bind(user_id)
+            .bind(kind)
+            .fetch_one(&mut *conn)
+            .await?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    sqlx::query_scalar("INSERT INTO accounts (user_id, name, kind) VALUES (?, ?, ?) RETURNING id")
+        .bind(user_id)
+        .bind(name)
+        .bind(kind)
+        .fetch_one(conn)
+        .await
+}
+
+/// Makes the combined balance of the user's accounts of one kind equal
+/// `total` by recording a correction on [`primary_account`], dated `on`.
+pub(crate) async fn set_total_balance(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    kind: AccountKind,
+    name: &str,
+    total: Money,
+    on: NaiveDate,
+) -> Result<(), sqlx::Error> {
+    let account_id = primary_account(conn, user_id, kind, name).await?;
+    let change = total - total_balance(conn, user_id, kind).await?;
+    if change.is_zero() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "INSERT INTO ledger_entries (account_id, kind, amount, entry_date) VALUES (?, ?, ?, ?)",
+    )
+    .bind(account_id)
+    .bind(LedgerEntryKind::Correction)
+    .bind(change)
+    .bind(on)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, State},
+    http::{Method, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::handlers::sessions::{hash_token, random_token};
+use crate::middleware::auth::Claims;
+use crate::models::{ApiToken, TokenScope, TokenScopes};
+
+/// Marks a bearer token as a personal API token rather than a session JWT.
+pub const API_TOKEN_PREFIX: &str = "pm_";
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateApiToken {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<TokenScope>,
+    /// Days until the token stops working; it never expires when omitted.
+    #[validate(range(min = 1, max = 3650))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    /// The secret to send as `Authorization: Bearer <token>`. It cannot be
+    /// retrieved again.
+    pub secret: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/tokens",
+    responses(
+        (status = 200, body = [ApiToken]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "List API tokens",
+    description = "Lists the user's personal API tokens, newest first. Secrets are never returned."
+)]
+pub async fn list_api_tokens(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<ApiToken>>, PaymeError> {
+    let tokens: Vec<ApiToken> = sqlx::query_as(
+        "SELECT id, name, prefix, scopes, created_at, expires_at, last_used_at FROM api_tokens WHERE user_id = ? ORDER BY id DESC",
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/tokens",
+    request_body = CreateApiToken,
+    responses(
+        (status = 200, description = "Token created; the secret is only shown now", body = CreatedApiToken),
+        (status = 400, description = "Invalid name, scopes or expiry"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Create API token",
+    description = "Creates a personal access token for scripts. `read` allows GET requests, `write` also allows changes, and `import_export` allows the import and export endpoints."
+)]
+pub async fn create_api_token(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateApiToken>,
+) -> Result<Json<CreatedApiToken>, PaymeError> {
+    payload.validate()?;
+
+    let mut scopes = payload.scopes;
+    scopes.sort();
+    scopes.dedup();
+    let scopes = TokenScopes(scopes);
+    let secret = format!("{API_TOKEN_PREFIX}{}", random_token());
+
+    let token: ApiToken = sqlx::query_as(
+        r#"
+        INSERT INTO api_tokens (user_id, name, token_hash, prefix, scopes, expires_at)
+        VALUES (?, ?, ?, ?, ?, CASE WHEN ? IS NULL THEN NULL ELSE datetime('now', '+' || ? || ' days') END)
+        RETURNING id, name, prefix, scopes, created_at, expires_at, last_used_at
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(&payload.name)
+    .bind(hash_token(&secret))
+    .bind(&secret[..API_TOKEN_PREFIX.len() + 8])
+    .bind(scopes.to_db())
+    .bind(payload.expires_in_days)
+    .bind(payload.expires_in_days)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(CreatedApiToken { token, secret }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/tokens/{id}",
+    params(("id" = i64, Path, description = "API token ID")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 404, description = "Token not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Revoke API token",
+    description = "Deletes a personal API token; requests using it are rejected from then on."
+)]
+pub async fn delete_api_token(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    let result = sqlx::query("DELETE FROM api_tokens WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolves an API token to its owner, checking that it has not expired and
+/// grants the scope the route needs. Records when it was last used, at most
+/// once a minute.
+pub async fn authenticate_api_token(
+    pool: &SqlitePool,
+    token: &str,
+    method: &Method,
+    path: &str,
+) -> Result<Claims, PaymeError> {
+    let (id, user_id, username, scopes, stale): (i64, i64, String, String, bool) = sqlx::query_as(
+        r#"
+        SELECT t.id, t.user_id, u.username, t.scopes,
+               COALESCE(t.last_used_at < datetime('now', '-1 minute'), 1)
+        FROM api_tokens t
+        JOIN users u ON t.user_id = u.id
+        WHERE t.token_hash = ? AND (t.expires_at IS NULL OR t.expires_at > datetime('now'))
+        "#,
+    )
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::Unauthorized)?;
+
+    let scopes = TokenScopes::try_from(scopes).map_err(|e| PaymeError::Internal(e.to_string()))?;
+    match required_scope(method, path) {
+        Some(scope) if scopes.contains(scope) => {}
+        Some(scope) => {
+            let name = match scope {
+                TokenScope::Read => "read",
+                TokenScope::Write => "write",
+                TokenScope::ImportExport => "import_export",
+            };
+            return Err(PaymeError::Forbidden(format!(
+                "This token lacks the {name} scope"
+            )));
+        }
+        None => {
+            return Err(PaymeError::Forbidden(
+                "API tokens cannot manage the account".to_string(),
+            ))
+        }
+    }
+
+    if stale {
+        sqlx::query("UPDATE api_tokens SET last_used_at = datetime('now') WHERE id = ?")
+            .bind(id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(Claims {
+        sub: user_id,
+        username,
+        exp: usize::MAX,
+        sid: None,
+    })
+}
+
+/// The scope a request needs, or `None` for account management routes that
+/// only a signed-in user may call.
+fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    if path.starts_with("/api/auth/") {
+        return (path == "/api/auth/me" && method == Method::GET).then_some(TokenScope::Read);
+    }
+    if path.starts_with("/api/import") || path.starts_with("/api/export") {
+        return Some(TokenScope::ImportExport);
+    }
+    if method == Method::GET || method == Method::HEAD {
+        Some(TokenScope::Read)
+    } else {
+        Some(TokenScope::Write)
+    }
+}
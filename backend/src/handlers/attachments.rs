@@ -0,0 +1,275 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use sqlx::SqlitePool;
+
+use crate::error::PaymeError;
+use crate::handlers::items::{verify_month_access, verify_month_not_closed};
+use crate::middleware::auth::Claims;
+use crate::models::Attachment;
+
+/// Largest file accepted as an attachment.
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Receipts are photos or PDFs.
+const ALLOWED_TYPES: &[&str] = &[
+    "application/pdf",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "image/heic",
+];
+
+/// ISO base media brands used by HEIC and HEIF photos.
+const HEIC_BRANDS: &[&[u8; 4]] = &[
+    b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+];
+
+#[utoipa::path(
+    get,
+    path = "/api/months/{month_id}/items/{id}/attachments",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("id" = i64, Path, description = "Item (Transaction) ID")
+    ),
+    responses(
+        (status = 200, body = [Attachment]),
+        (status = 404, description = "Item not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Items",
+    summary = "List attachments",
+    description = "Lists the receipts and documents attached to a transaction, oldest first."
+)]
+pub async fn list_attachments(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, item_id)): Path<(i64, i64)>,
+) -> Result<Json<Vec<Attachment>>, PaymeError> {
+    verify_month_access(&pool, claims.sub, month_id).await?;
+    verify_item(&pool, month_id, item_id).await?;
+
+    let attachments: Vec<Attachment> = sqlx::query_as(
+        "SELECT id, item_id, filename, content_type, size, created_at FROM attachments WHERE item_id = ? ORDER BY id",
+    )
+    .bind(item_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(attachments))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/months/{month_id}/items/{id}/attachments",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("id" = i64, Path, description = "Item (Transaction) ID")
+    ),
+    request_body(content_type = "multipart/form-data", description = "A `file` field holding a PDF, JPEG, PNG, WebP or HEIC file of at most 10 MB"),
+    responses(
+        (status = 200, body = Attachment),
+        (status = 400, description = "Missing, empty, oversized or unsupported file, content that does not match its type, or the month is closed"),
+        (status = 404, description = "Item not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Items",
+    summary = "Attach file",
+    description = "Uploads a receipt or document for a transaction."
+)]
+pub async fn upload_attachment(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, item_id)): Path<(i64, i64)>,
+    mut multipart: Multipart,
+) -> Result<Json<Attachment>, PaymeError> {
+    verify_month_not_closed(&pool, claims.sub, month_id).await?;
+    verify_item(&pool, month_id, item_id).await?;
+
+    let mut upload = None;
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let filename = sanitize_filename(field.file_name().unwrap_or_default());
+        let content_type = field.content_type().unwrap_or_default().to_string();
+        let data = field.bytes().await.map_err(multipart_error)?;
+        upload = Some((filename, content_type, data));
+        break;
+    }
+    let (filename, content_type, data) =
+        upload.ok_or(PaymeError::BadRequest("A file is required".to_string()))?;
+
+    if !ALLOWED_TYPES.contains(&content_type.as_str()) {
+        return Err(PaymeError::BadRequest(format!(
+            "Unsupported file type: {content_type}"
+        )));
+    }
+    if data.is_empty() {
+        return Err(PaymeError::BadRequest("File is empty".to_string()));
+    }
+    if data.len() > MAX_ATTACHMENT_SIZE {
+        return Err(PaymeError::BadRequest(format!(
+            "File is larger than {} MB",
+            MAX_ATTACHMENT_SIZE / (1024 * 1024)
+        )));
+    }
+    // The declared type is what downloads are served as, so the content
+    // has to back it up.
+    if detect_type(&data) != Some(content_type.as_str()) {
+        return Err(PaymeError::BadRequest(format!(
+            "File content is not {content_type}"
+        )));
+    }
+
+    let attachment: Attachment = sqlx::query_as(
+        "INSERT INTO attachments (item_id, filename, content_type, size, data) VALUES (?, ?, ?, ?, ?) RETURNING id, item_id, filename, content_type, size, created_at",
+    )
+    .bind(item_id)
+    .bind(&filename)
+    .bind(&content_type)
+    .bind(data.len() as i64)
+    .bind(data.as_ref())
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(attachment))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/months/{month_id}/items/{item_id}/attachments/{id}",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("item_id" = i64, Path, description = "Item (Transaction) ID"),
+        ("id" = i64, Path, description = "Attachment ID")
+    ),
+    responses(
+        (status = 200, description = "Download the file with its original type"),
+        (status = 404, description = "Attachment not found")
+    ),
+    tag = "Items",
+    summary = "Download attachment",
+    description = "Retrieves an attached file."
+)]
+pub async fn get_attachment(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, item_id, id)): Path<(i64, i64, i64)>,
+) -> Result<impl IntoResponse, PaymeError> {
+    let (filename, content_type, data): (String, String, Vec<u8>) = sqlx::query_as(
+        r#"
+        SELECT a.filename, a.content_type, a.data
+        FROM attachments a
+        JOIN items i ON a.item_id = i.id
+        JOIN months m ON i.month_id = m.id
+        WHERE a.id = ? AND a.item_id = ? AND i.month_id = ? AND m.user_id = ?
+        "#,
+    )
+    .bind(id)
+    .bind(item_id)
+    .bind(month_id)
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    Ok((
+        [
+            ("Content-Type", content_type),
+            (
+                "Content-Disposition",
+                format!("attachment; filename=\"{filename}\""),
+            ),
+            ("X-Content-Type-Options", "nosniff".to_string()),
+        ],
+        data,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/months/{month_id}/items/{item_id}/attachments/{id}",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("item_id" = i64, Path, description = "Item (Transaction) ID"),
+        ("id" = i64, Path, description = "Attachment ID")
+    ),
+    responses(
+        (status = 204, description = "Attachment deleted successfully"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Items",
+    summary = "Delete attachment",
+    description = "Removes a file from a transaction."
+)]
+pub async fn delete_attachment(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, item_id, id)): Path<(i64, i64, i64)>,
+) -> Result<StatusCode, PaymeError> {
+    verify_month_not_closed(&pool, claims.sub, month_id).await?;
+
+    sqlx::query(
+        "DELETE FROM attachments WHERE id = ? AND item_id IN (SELECT id FROM items WHERE id = ? AND month_id = ?)",
+    )
+    .bind(id)
+    .bind(item_id)
+    .bind(month_id)
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks that the item belongs to the month, whose ownership the caller
+/// has already verified.
+async fn verify_item(pool: &SqlitePool, month_id: i64, item_id: i64) -> Result<(), PaymeError> {
+    let _item: (i64,) = sqlx::query_as("SELECT id FROM items WHERE id = ? AND month_id = ?")
+        .bind(item_id)
+        .bind(month_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(PaymeError::NotFound)?;
+    Ok(())
+}
+
+/// Recognises the allowed file types by their leading bytes.
+fn detect_type(data: &[u8]) -> Option<&'static str> {
+    match data {
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n', ..] => Some("image/png"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..]
+            if HEIC_BRANDS.iter().any(|b| brand.starts_with(*b)) =>
+        {
+            Some("image/heic")
+        }
+        _ => None,
+    }
+}
+
+/// Keeps the name safe to echo back in a `Content-Disposition` header.
+fn sanitize_filename(name: &str) -> String {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(200)
+        .collect();
+    if name.trim().is_empty() {
+        "attachment".to_string()
+    } else {
+        name
+    }
+}
+
+fn multipart_error(e: axum::extract::multipart::MultipartError) -> PaymeError {
+    PaymeError::BadRequest(e.body_text())
+}
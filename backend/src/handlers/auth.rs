@@ -2,18 +2,29 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
-use axum::{extract::State, response::IntoResponse, Json};
-use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::extract::cookie::CookieJar;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use url::Url;
 use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::sessions::{
+    clear_cookies, revoke_all_sessions, revoke_session, rotate_session, start_session, ClientInfo,
+    SessionTokens, ACCESS_TOKEN_MINUTES,
+};
+use crate::handlers::two_factor::{
+    complete_challenge, start_challenge, LoginChallenge, VerifyLoginRequest,
+};
 use crate::middleware::auth::Claims;
+use crate::models::Currency;
+use crate::totp::Clock;
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct AuthRequest {
@@ -27,6 +38,54 @@ pub struct AuthRequest {
 pub struct AuthResponse {
     pub id: i64,
     pub username: String,
+    /// The currency all of the user's amounts are in.
+    pub currency: Currency,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct LoginRequest {
+    #[validate(length(min = 3, max = 32))]
+    pub username: String,
+    #[validate(length(min = 6, max = 128))]
+    pub password: String,
+    /// Also return the refresh token in the response body, for clients that
+    /// do not keep cookies. Browsers leave this off and get it only as the
+    /// httpOnly `refresh_token` cookie.
+    #[serde(default)]
+    pub include_refresh_token: bool,
+}
+
+/// Returned on login and refresh. Both tokens are also set as cookies.
+#[derive(Serialize, ToSchema)]
+pub struct TokenResponse {
+    pub id: i64,
+    pub username: String,
+    pub access_token: String,
+    /// Only present when the client asked for it with `include_refresh_token`
+    /// or refreshed with a token in the request body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Seconds until the access token expires.
+    pub expires_in: i64,
+}
+
+impl TokenResponse {
+    fn new(id: i64, username: String, tokens: &SessionTokens, include_refresh_token: bool) -> Self {
+        Self {
+            id,
+            username,
+            access_token: tokens.access_token.clone(),
+            refresh_token: include_refresh_token.then(|| tokens.refresh_token.clone()),
+            expires_in: ACCESS_TOKEN_MINUTES * 60,
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    /// Falls back to the `refresh_token` cookie when omitted. The new refresh
+    /// token is returned in the body only when this is given.
+    pub refresh_token: Option<String>,
 }
 
 #[utoipa::path(
@@ -65,34 +124,38 @@ pub async fn register(
     Ok(Json(AuthResponse {
         id: result,
         username: payload.username,
+        currency: Currency::default(),
     }))
 }
 
 #[utoipa::path(
     post,
     path = "/api/auth/login",
-    request_body = AuthRequest,
+    request_body = LoginRequest,
     responses(
-        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 200, description = "Login successful", body = TokenResponse),
+        (status = 202, description = "Password accepted; a second factor is required", body = LoginChallenge),
         (status = 401, description = "Invalid credentials"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Auth",
     summary = "Authenticate user",
-    description = "Verifies credentials and issues a JWT token."
+    description = "Verifies credentials and starts a session. Issues a 15-minute access token and a refresh token as httpOnly cookies. The access token is also returned in the body, and the refresh token too when `include_refresh_token` is set. Accounts with two-factor authentication get a challenge instead, answered at `/api/auth/login/verify`."
 )]
 pub async fn login(
     State(pool): State<SqlitePool>,
     jar: CookieJar,
-    Json(payload): Json<AuthRequest>,
-) -> Result<impl IntoResponse, PaymeError> {
+    client: ClientInfo,
+    Json(payload): Json<LoginRequest>,
+) -> Result<Response, PaymeError> {
     payload.validate()?;
-    let user: (i64, String, String) =
-        sqlx::query_as("SELECT id, username, password_hash FROM users WHERE username = ?")
-            .bind(&payload.username)
-            .fetch_optional(&pool)
-            .await?
-            .ok_or(PaymeError::Unauthorized)?;
+    let user: (i64, String, String, bool) = sqlx::query_as(
+        "SELECT id, username, password_hash, totp_enabled_at IS NOT NULL FROM users WHERE username = ?",
+    )
+    .bind(&payload.username)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::Unauthorized)?;
 
     let parsed_hash =
         PasswordHash::new(&user.2).map_err(|e| PaymeError::Internal(e.to_string()))?;
@@ -100,35 +163,98 @@ pub async fn login(
         .verify_password(payload.password.as_bytes(), &parsed_hash)
         .map_err(|_| PaymeError::Unauthorized)?;
 
-    let secret = std::env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string());
-
-    let claims = Claims {
-        sub: user.0,
-        username: user.1.clone(),
-        exp: (Utc::now() + Duration::days(30)).timestamp() as usize,
-    };
+    if user.3 {
+        let challenge = start_challenge(&pool, user.0).await?;
+        return Ok((StatusCode::ACCEPTED, Json(challenge)).into_response());
+    }
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
+    let tokens = start_session(&pool, user.0, &user.1, &client).await?;
+    Ok((
+        tokens.set_cookies(jar),
+        Json(TokenResponse::new(
+            user.0,
+            user.1,
+            &tokens,
+            payload.include_refresh_token,
+        )),
     )
-    .map_err(|e| PaymeError::Internal(e.to_string()))?;
+        .into_response())
+}
 
-    let cookie = Cookie::build(("token", token))
-        .path("/")
-        .http_only(true)
-        .same_site(SameSite::Lax)
-        .max_age(time::Duration::days(30))
-        .build();
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/verify",
+    request_body = VerifyLoginRequest,
+    responses(
+        (status = 200, description = "Login successful", body = TokenResponse),
+        (status = 401, description = "Wrong code, or the challenge is invalid, expired or used up"),
+        (status = 429, description = "Too many wrong codes in a row; the second step is locked for a while"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Complete two-factor login",
+    description = "Answers a login challenge with a code from the authenticator app or a recovery code, then starts a session like a normal login. Each code works once, and a challenge allows five wrong codes."
+)]
+pub async fn verify_login(
+    State(pool): State<SqlitePool>,
+    axum::Extension(clock): axum::Extension<Clock>,
+    jar: CookieJar,
+    client: ClientInfo,
+    Json(payload): Json<VerifyLoginRequest>,
+) -> Result<impl IntoResponse, PaymeError> {
+    payload.validate()?;
+    let (id, username) =
+        complete_challenge(&pool, &payload.challenge_token, &payload.code, clock.now()).await?;
 
+    let tokens = start_session(&pool, id, &username, &client).await?;
+    Ok((
+        tokens.set_cookies(jar),
+        Json(TokenResponse::new(
+            id,
+            username,
+            &tokens,
+            payload.include_refresh_token,
+        )),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    request_body(content = RefreshRequest, description = "Optional when the refresh token cookie is sent"),
+    responses(
+        (status = 200, description = "New token pair issued", body = TokenResponse),
+        (status = 401, description = "Refresh token is invalid, expired, revoked or already used"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Refresh tokens",
+    description = "Exchanges a refresh token for a new access token and refresh token. Each refresh token works once; reusing an old one revokes its session."
+)]
+pub async fn refresh(
+    State(pool): State<SqlitePool>,
+    jar: CookieJar,
+    client: ClientInfo,
+    payload: Option<Json<RefreshRequest>>,
+) -> Result<impl IntoResponse, PaymeError> {
+    // A token sent in the body means the client is not relying on cookies,
+    // so it gets the new one back the same way.
+    let from_body = payload.and_then(|Json(p)| p.refresh_token);
+    let include_refresh_token = from_body.is_some();
+    let refresh_token = from_body
+        .or_else(|| jar.get("refresh_token").map(|c| c.value().to_string()))
+        .filter(|t| !t.is_empty())
+        .ok_or(PaymeError::Unauthorized)?;
+
+    let (id, username, tokens) = rotate_session(&pool, &refresh_token, &client).await?;
     Ok((
-        jar.add(cookie),
-        Json(AuthResponse {
-            id: user.0,
-            username: user.1,
-        }),
+        tokens.set_cookies(jar),
+        Json(TokenResponse::new(
+            id,
+            username,
+            &tokens,
+            include_refresh_token,
+        )),
     ))
 }
 
@@ -141,16 +267,17 @@ pub async fn login(
     ),
     tag = "Auth",
     summary = "Log out user",
-    description = "Clears the authentication token by setting the session cookie to expire immediately."
+    description = "Revokes the current session and expires the token cookies."
 )]
-pub async fn logout(jar: CookieJar) -> impl IntoResponse {
-    let cookie = Cookie::build(("token", ""))
-        .path("/")
-        .http_only(true)
-        .max_age(time::Duration::seconds(0))
-        .build();
-
-    jar.add(cookie)
+pub async fn logout(
+    State(pool): State<SqlitePool>,
+    jar: CookieJar,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<impl IntoResponse, PaymeError> {
+    if let Some(sid) = claims.sid {
+        revoke_session(&pool, claims.sub, sid).await?;
+    }
+    Ok(clear_cookies(jar))
 }
 
 #[utoipa::path(
@@ -169,45 +296,20 @@ pub async fn me(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
 ) -> Result<Json<AuthResponse>, PaymeError> {
-    let user: (i64, String) = sqlx::query_as("SELECT id, username FROM users WHERE id = ?")
-        .bind(claims.sub)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or(PaymeError::NotFound)?;
+    let user: (i64, String, Currency) =
+        sqlx::query_as("SELECT id, username, currency FROM users WHERE id = ?")
+            .bind(claims.sub)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(PaymeError::NotFound)?;
 
     Ok(Json(AuthResponse {
         id: user.0,
         username: user.1,
+        currency: user.2,
     }))
 }
 
-pub async fn export_db(
-    axum::Extension(claims): axum::Extension<Claims>,
-) -> Result<impl IntoResponse, PaymeError> {
-    let db_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:payme.db".to_string());
-
-    let db_path = Url::parse(&db_url)
-        .map_err(|e| PaymeError::Internal(e.to_string()))?
-        .path()
-        .to_string();
-
-    let data = tokio::fs::read(&db_path)
-        .await
-        .map_err(|e| PaymeError::Internal(e.to_string()))?;
-
-    let filename = format!("attachment; filename=\"payme-{}.db\"", claims.username);
-    Ok((
-        [
-            (
-                "Content-Type".to_string(),
-                "application/octet-stream".to_string(),
-            ),
-            ("Content-Disposition".to_string(), filename),
-        ],
-        data,
-    ))
-}
-
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct ChangeUsernameRequest {
     #[validate(length(min = 3, max = 32))]
@@ -234,15 +336,53 @@ pub async fn change_username(
 ) -> Result<Json<AuthResponse>, PaymeError> {
     payload.validate()?;
 
-    sqlx::query("UPDATE users SET username = ? WHERE id = ?")
-        .bind(&payload.new_username)
-        .bind(claims.sub)
-        .execute(&pool)
-        .await?;
+    let currency: Currency =
+        sqlx::query_scalar("UPDATE users SET username = ? WHERE id = ? RETURNING currency")
+            .bind(&payload.new_username)
+            .bind(claims.sub)
+            .fetch_one(&pool)
+            .await?;
 
     Ok(Json(AuthResponse {
         id: claims.sub,
         username: payload.new_username,
+        currency,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChangeCurrencyRequest {
+    pub currency: Currency,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/auth/change-currency",
+    request_body = ChangeCurrencyRequest,
+    responses(
+        (status = 200, description = "Currency changed successfully", body = AuthResponse),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Change currency",
+    description = "Sets the currency the authenticated user's amounts are in. Amounts are relabelled, not converted."
+)]
+pub async fn change_currency(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<ChangeCurrencyRequest>,
+) -> Result<Json<AuthResponse>, PaymeError> {
+    let username: String =
+        sqlx::query_scalar("UPDATE users SET currency = ? WHERE id = ? RETURNING username")
+            .bind(payload.currency)
+            .bind(claims.sub)
+            .fetch_one(&pool)
+            .await?;
+
+    Ok(Json(AuthResponse {
+        id: claims.sub,
+        username,
+        currency: payload.currency,
     }))
 }
 
@@ -265,20 +405,23 @@ pub struct ChangePasswordRequest {
     ),
     tag = "Auth",
     summary = "Change password",
-    description = "Updates the authenticated user's password."
+    description = "Updates the authenticated user's password and revokes every session. The caller gets a new session through fresh token cookies."
 )]
 pub async fn change_password(
     State(pool): State<SqlitePool>,
+    jar: CookieJar,
+    client: ClientInfo,
     axum::Extension(claims): axum::Extension<Claims>,
     Json(payload): Json<ChangePasswordRequest>,
 ) -> Result<impl IntoResponse, PaymeError> {
     payload.validate()?;
 
-    let user: (String,) = sqlx::query_as("SELECT password_hash FROM users WHERE id = ?")
-        .bind(claims.sub)
-        .fetch_optional(&pool)
-        .await?
-        .ok_or(PaymeError::NotFound)?;
+    let user: (String, String) =
+        sqlx::query_as("SELECT password_hash, username FROM users WHERE id = ?")
+            .bind(claims.sub)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(PaymeError::NotFound)?;
 
     let parsed_hash =
         PasswordHash::new(&user.0).map_err(|e| PaymeError::Internal(e.to_string()))?;
@@ -299,8 +442,12 @@ pub async fn change_password(
         .execute(&pool)
         .await?;
 
-    Ok(Json(
-        serde_json::json!({"message": "Password changed successfully"}),
+    revoke_all_sessions(&pool, claims.sub).await?;
+    let tokens = start_session(&pool, claims.sub, &user.1, &client).await?;
+
+    Ok((
+        tokens.set_cookies(jar),
+        Json(serde_json::json!({"message": "Password changed successfully"})),
     ))
 }
 
@@ -348,14 +495,8 @@ pub async fn clear_all_data(
         .execute(&pool)
         .await?;
 
-    let cookie = Cookie::build(("token", ""))
-        .path("/")
-        .http_only(true)
-        .max_age(time::Duration::seconds(0))
-        .build();
-
     Ok((
-        jar.add(cookie),
+        clear_cookies(jar),
         Json(serde_json::json!({"message": "All data cleared"})),
     ))
 }
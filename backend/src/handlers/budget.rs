@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
@@ -9,54 +9,96 @@ use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::pagination::{AmountFilter, ListQuery, Paginated, SortDirection};
 use crate::middleware::auth::Claims;
-use crate::models::{BudgetCategory, MonthlyBudget};
+use crate::models::{BudgetCategory, Money, MonthlyBudget, RolloverMode};
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateCategory {
     #[validate(length(min = 1, max = 100))]
     pub label: String,
-    #[validate(range(min = 0.0))]
-    pub default_amount: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub default_amount: Money,
+    #[serde(default)]
+    pub rollover_mode: RolloverMode,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateCategory {
     #[validate(length(min = 1, max = 100))]
     pub label: Option<String>,
-    #[validate(range(min = 0.0))]
-    pub default_amount: Option<f64>,
+    #[validate(range(min = Money::ZERO))]
+    pub default_amount: Option<Money>,
+    pub rollover_mode: Option<RolloverMode>,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateMonthlyBudget {
-    #[validate(range(min = 0.0))]
-    pub allocated_amount: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub allocated_amount: Money,
 }
 
 #[utoipa::path(
     get,
     path = "/api/categories",
+    params(ListQuery, AmountFilter),
     responses(
-        (status = 200, body = [BudgetCategory]),
+        (status = 200, body = [BudgetCategory], headers(
+            ("X-Total-Count" = i64, description = "Number of matching rows"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Configuration",
     summary = "List all categories",
-    description = "Retrieves all budget categories used as templates for new months."
+    description = "Retrieves the budget categories used as templates for new months, in the order they were added, optionally filtered by default amount. Also sorts by `label` or `default_amount`."
 )]
 pub async fn list_categories(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
-) -> Result<Json<Vec<BudgetCategory>>, PaymeError> {
-    let categories: Vec<BudgetCategory> = sqlx::query_as(
-        "SELECT id, user_id, label, default_amount FROM budget_categories WHERE user_id = ?",
-    )
+    list: ListQuery,
+    Query(amounts): Query<AmountFilter>,
+) -> Result<Paginated<BudgetCategory>, PaymeError> {
+    let page = list.page()?;
+    let order_by = list.order_by(
+        &[
+            ("id", &[]),
+            ("label", &["label COLLATE NOCASE"]),
+            ("default_amount", &["default_amount"]),
+        ],
+        SortDirection::Asc,
+        "id",
+    )?;
+    let filter = r#"
+        FROM budget_categories
+        WHERE user_id = ?
+            AND (? IS NULL OR default_amount >= ?)
+            AND (? IS NULL OR default_amount <= ?)
+    "#;
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {filter}"))
+        .bind(claims.sub)
+        .bind(amounts.min_amount)
+        .bind(amounts.min_amount)
+        .bind(amounts.max_amount)
+        .bind(amounts.max_amount)
+        .fetch_one(&pool)
+        .await?;
+    let categories: Vec<BudgetCategory> = sqlx::query_as(&format!(
+        "SELECT id, user_id, label, default_amount, rollover_mode {filter} {order_by} LIMIT ? OFFSET ?"
+    ))
     .bind(claims.sub)
+    .bind(amounts.min_amount)
+    .bind(amounts.min_amount)
+    .bind(amounts.max_amount)
+    .bind(amounts.max_amount)
+    .bind(page.sql_limit())
+    .bind(page.offset)
     .fetch_all(&pool)
     .await?;
 
-    Ok(Json(categories))
+    Ok(list.paginate(categories, total, page))
 }
 
 #[utoipa::path(
@@ -78,11 +120,12 @@ pub async fn create_category(
 ) -> Result<Json<BudgetCategory>, PaymeError> {
     payload.validate()?;
     let id: i64 = sqlx::query_scalar(
-        "INSERT INTO budget_categories (user_id, label, default_amount) VALUES (?, ?, ?) RETURNING id",
+        "INSERT INTO budget_categories (user_id, label, default_amount, rollover_mode) VALUES (?, ?, ?, ?) RETURNING id",
     )
     .bind(claims.sub)
     .bind(&payload.label)
     .bind(payload.default_amount)
+    .bind(payload.rollover_mode)
     .fetch_one(&pool)
     .await?;
 
@@ -109,6 +152,7 @@ pub async fn create_category(
         user_id: claims.sub,
         label: payload.label,
         default_amount: payload.default_amount,
+        rollover_mode: payload.rollover_mode,
     }))
 }
 
@@ -123,7 +167,7 @@ pub async fn create_category(
     ),
     tag = "Configuration",
     summary = "Update a category",
-    description = "Updates the label or default amount for a category template."
+    description = "Updates the label, default amount or rollover mode of a category template."
 )]
 pub async fn update_category(
     State(pool): State<SqlitePool>,
@@ -133,7 +177,7 @@ pub async fn update_category(
 ) -> Result<Json<BudgetCategory>, PaymeError> {
     payload.validate()?;
     let existing: BudgetCategory = sqlx::query_as(
-        "SELECT id, user_id, label, default_amount FROM budget_categories WHERE id = ? AND user_id = ?",
+        "SELECT id, user_id, label, default_amount, rollover_mode FROM budget_categories WHERE id = ? AND user_id = ?",
     )
     .bind(category_id)
     .bind(claims.sub)
@@ -143,19 +187,24 @@ pub async fn update_category(
 
     let label = payload.label.unwrap_or(existing.label);
     let default_amount = payload.default_amount.unwrap_or(existing.default_amount);
+    let rollover_mode = payload.rollover_mode.unwrap_or(existing.rollover_mode);
 
-    sqlx::query("UPDATE budget_categories SET label = ?, default_amount = ? WHERE id = ?")
-        .bind(&label)
-        .bind(default_amount)
-        .bind(category_id)
-        .execute(&pool)
-        .await?;
+    sqlx::query(
+        "UPDATE budget_categories SET label = ?, default_amount = ?, rollover_mode = ? WHERE id = ?",
+    )
+    .bind(&label)
+    .bind(default_amount)
+    .bind(rollover_mode)
+    .bind(category_id)
+    .execute(&pool)
+    .await?;
 
     Ok(Json(BudgetCategory {
         id: category_id,
         user_id: claims.sub,
         label,
         default_amount,
+        rollover_mode,
     }))
 }
 
@@ -247,7 +296,7 @@ pub async fn update_monthly_budget(
             .ok_or(PaymeError::NotFound)?;
 
     if month.0 {
-        return Err(PaymeError::BadRequest("Month is closed".to_string()));
+        return Err(PaymeError::MonthClosed);
     }
 
     let existing: MonthlyBudget = sqlx::query_as(
@@ -1,17 +1,43 @@
-use axum::{extract::State, http::StatusCode, Json};
+use std::path::{Path, PathBuf};
+
+use axum::{body::Bytes, extract::State, http::StatusCode, response::IntoResponse, Json};
+use chrono::{NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
+use crate::db::{self, MigrationError};
 use crate::error::PaymeError;
+use crate::handlers::accounts::{set_total_balance, total_balance};
+use crate::handlers::items::attach_details;
+use crate::handlers::transfers::TRANSFERS_IN_MONTH;
 use crate::middleware::auth::Claims;
-use crate::models::{BudgetCategory, FixedExpense, IncomeEntry, Item, Month};
+use crate::models::{
+    AccountKind, BudgetCategory, Currency, FixedExpense, GoalAllocation, IncomeEntry,
+    ItemWithCategory, LedgerEntry, LedgerEntryKind, Money, Month, RolloverMode,
+    TransferWithAccounts,
+};
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserExport {
     pub version: u32,
-    pub savings: Option<f64>,
-    pub retirement_savings: Option<f64>,
+    /// The currency every amount in the export is in. Left unchanged on
+    /// import when missing, as in exports made before it was stored.
+    #[serde(default)]
+    pub currency: Option<Currency>,
+    /// Combined balance of the savings accounts. Only read on import when
+    /// `accounts` is empty, as in exports made before accounts existed.
+    pub savings: Option<Money>,
+    /// Combined balance of the retirement accounts, read like `savings`.
+    pub retirement_savings: Option<Money>,
+    #[serde(default)]
+    pub accounts: Vec<AccountExport>,
+    #[serde(default)]
+    pub goals: Vec<GoalExport>,
+    /// Tag names, including tags no item carries.
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub fixed_expenses: Vec<FixedExpenseExport>,
     pub categories: Vec<CategoryExport>,
     pub months: Vec<MonthExport>,
@@ -20,13 +46,50 @@ pub struct UserExport {
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct FixedExpenseExport {
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AccountExport {
+    pub name: String,
+    pub kind: AccountKind,
+    pub opening_balance: Money,
+    #[serde(default)]
+    pub ledger_entries: Vec<LedgerEntryExport>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LedgerEntryExport {
+    pub kind: LedgerEntryKind,
+    pub amount: Money,
+    pub entry_date: NaiveDate,
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GoalExport {
+    pub name: String,
+    pub target_amount: Money,
+    pub target_date: NaiveDate,
+    pub priority: i32,
+    /// Name of the linked account.
+    pub account: Option<String>,
+    #[serde(default)]
+    pub allocations: Vec<AllocationExport>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AllocationExport {
+    pub amount: Money,
+    pub allocated_on: NaiveDate,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct CategoryExport {
     pub label: String,
-    pub default_amount: f64,
+    pub default_amount: Money,
+    #[serde(default)]
+    pub rollover_mode: RolloverMode,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -34,29 +97,64 @@ pub struct MonthExport {
     pub year: i32,
     pub month: i32,
     pub is_closed: bool,
+    /// Absent in exports made before fixed expenses were tracked per month;
+    /// such months get a copy of the current fixed expenses on import.
+    #[serde(default)]
+    pub fixed_expenses: Option<Vec<FixedExpenseExport>>,
     pub income_entries: Vec<IncomeExport>,
     pub budgets: Vec<BudgetExport>,
     pub items: Vec<ItemExport>,
+    #[serde(default)]
+    pub transfers: Vec<TransferExport>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TransferExport {
+    /// Name of the account the money left.
+    pub from_account: String,
+    /// Name of the account the money went to.
+    pub to_account: String,
+    pub amount: Money,
+    pub transferred_on: String,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct IncomeExport {
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    /// Name of the linked account.
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct BudgetExport {
     pub category_label: String,
-    pub allocated_amount: f64,
+    pub allocated_amount: Money,
+    #[serde(default)]
+    pub carried_amount: Money,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct ItemExport {
     pub category_label: String,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
     pub spent_on: String,
+    /// Name of the linked account.
+    #[serde(default)]
+    pub account: Option<String>,
+    #[serde(default)]
+    pub splits: Vec<SplitExport>,
+    /// Tag names.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SplitExport {
+    pub category_label: String,
+    pub amount: Money,
 }
 
 #[utoipa::path(
@@ -75,18 +173,75 @@ pub async fn export_json(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
 ) -> Result<Json<UserExport>, PaymeError> {
-    let savings: f64 = sqlx::query_scalar("SELECT savings FROM users WHERE id = ?")
+    let mut conn = pool.acquire().await?;
+    let savings = total_balance(&mut conn, claims.sub, AccountKind::Savings).await?;
+    let retirement_savings = total_balance(&mut conn, claims.sub, AccountKind::Retirement).await?;
+    let currency: Currency = sqlx::query_scalar("SELECT currency FROM users WHERE id = ?")
         .bind(claims.sub)
-        .fetch_one(&pool)
-        .await
-        .unwrap_or(0.0);
+        .fetch_one(&mut *conn)
+        .await?;
+    drop(conn);
 
-    let retirement_savings: f64 =
-        sqlx::query_scalar("SELECT retirement_savings FROM users WHERE id = ?")
+    let accounts: Vec<(i64, String, AccountKind, Money)> = sqlx::query_as(
+        "SELECT id, name, kind, opening_balance FROM accounts WHERE user_id = ? ORDER BY id",
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+    let ledger_entries: Vec<LedgerEntry> = sqlx::query_as(
+        r#"
+        SELECT e.id, e.account_id, e.kind, e.amount, e.entry_date, e.note
+        FROM ledger_entries e
+        JOIN accounts a ON e.account_id = a.id
+        WHERE a.user_id = ?
+        ORDER BY e.entry_date, e.id
+        "#,
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+    let account_name = |id: Option<i64>| {
+        accounts
+            .iter()
+            .find(|(account_id, ..)| Some(*account_id) == id)
+            .map(|(_, name, ..)| name.clone())
+    };
+
+    let goals: Vec<(i64, String, Money, NaiveDate, i32, Option<i64>)> = sqlx::query_as(
+        "SELECT id, name, target_amount, target_date, priority, account_id FROM savings_goals WHERE user_id = ? ORDER BY id",
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+    let mut goal_exports = Vec::new();
+    for (id, name, target_amount, target_date, priority, account_id) in goals {
+        let allocations: Vec<GoalAllocation> = sqlx::query_as(
+            "SELECT id, goal_id, amount, allocated_on FROM goal_allocations WHERE goal_id = ? ORDER BY allocated_on, id",
+        )
+        .bind(id)
+        .fetch_all(&pool)
+        .await?;
+        goal_exports.push(GoalExport {
+            name,
+            target_amount,
+            target_date,
+            priority,
+            account: account_name(account_id),
+            allocations: allocations
+                .into_iter()
+                .map(|a| AllocationExport {
+                    amount: a.amount,
+                    allocated_on: a.allocated_on,
+                })
+                .collect(),
+        });
+    }
+
+    let tags: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM tags WHERE user_id = ? ORDER BY name")
             .bind(claims.sub)
-            .fetch_one(&pool)
-            .await
-            .unwrap_or(0.0);
+            .fetch_all(&pool)
+            .await?;
 
     let fixed_expenses: Vec<FixedExpense> =
         sqlx::query_as("SELECT id, user_id, label, amount FROM fixed_expenses WHERE user_id = ?")
@@ -95,7 +250,7 @@ pub async fn export_json(
             .await?;
 
     let categories: Vec<BudgetCategory> = sqlx::query_as(
-        "SELECT id, user_id, label, default_amount FROM budget_categories WHERE user_id = ?",
+        "SELECT id, user_id, label, default_amount, rollover_mode FROM budget_categories WHERE user_id = ?",
     )
     .bind(claims.sub)
     .fetch_all(&pool)
@@ -112,15 +267,22 @@ pub async fn export_json(
 
     for m in &months {
         let income_entries: Vec<IncomeEntry> = sqlx::query_as(
-            "SELECT id, month_id, label, amount FROM income_entries WHERE month_id = ?",
+            "SELECT id, month_id, label, amount, account_id FROM income_entries WHERE month_id = ?",
         )
         .bind(m.id)
         .fetch_all(&pool)
         .await?;
 
-        let budgets: Vec<(String, f64)> = sqlx::query_as(
+        let month_fixed: Vec<(String, Money)> = sqlx::query_as(
+            "SELECT label, amount FROM monthly_fixed_expenses WHERE month_id = ? ORDER BY id",
+        )
+        .bind(m.id)
+        .fetch_all(&pool)
+        .await?;
+
+        let budgets: Vec<(String, Money, Money)> = sqlx::query_as(
             r#"
-            SELECT bc.label, mb.allocated_amount
+            SELECT bc.label, mb.allocated_amount, mb.carried_amount
             FROM monthly_budgets mb
             JOIN budget_categories bc ON mb.category_id = bc.id
             WHERE mb.month_id = ?
@@ -130,52 +292,108 @@ pub async fn export_json(
         .fetch_all(&pool)
         .await?;
 
-        let items: Vec<Item> = sqlx::query_as(
-            "SELECT id, month_id, category_id, description, amount, spent_on FROM items WHERE month_id = ?",
+        let mut items: Vec<ItemWithCategory> = sqlx::query_as(
+            r#"
+            SELECT i.id, i.month_id, i.category_id, bc.label AS category_label, i.description, i.amount, i.spent_on, i.account_id
+            FROM items i
+            JOIN budget_categories bc ON i.category_id = bc.id
+            WHERE i.month_id = ?
+            "#,
         )
         .bind(m.id)
         .fetch_all(&pool)
         .await?;
+        attach_details(&pool, &mut items).await?;
 
-        let mut item_exports = Vec::new();
-        for item in items {
-            let cat = categories.iter().find(|c| c.id == item.category_id);
-            if let Some(cat) = cat {
-                item_exports.push(ItemExport {
-                    category_label: cat.label.clone(),
-                    description: item.description,
-                    amount: item.amount,
-                    spent_on: item.spent_on.to_string(),
-                });
-            }
-        }
+        let item_exports: Vec<ItemExport> = items
+            .into_iter()
+            .map(|item| ItemExport {
+                category_label: item.category_label,
+                description: item.description,
+                amount: item.amount,
+                spent_on: item.spent_on.to_string(),
+                account: account_name(item.account_id),
+                splits: item
+                    .splits
+                    .into_iter()
+                    .map(|s| SplitExport {
+                        category_label: s.category_label,
+                        amount: s.amount,
+                    })
+                    .collect(),
+                tags: item.tags.into_iter().map(|t| t.name).collect(),
+            })
+            .collect();
+
+        let transfers: Vec<TransferWithAccounts> = sqlx::query_as(TRANSFERS_IN_MONTH)
+            .bind(m.id)
+            .fetch_all(&pool)
+            .await?;
 
         month_exports.push(MonthExport {
             year: m.year,
             month: m.month,
             is_closed: m.is_closed,
+            fixed_expenses: Some(
+                month_fixed
+                    .into_iter()
+                    .map(|(label, amount)| FixedExpenseExport { label, amount })
+                    .collect(),
+            ),
             income_entries: income_entries
                 .into_iter()
                 .map(|i| IncomeExport {
+                    account: account_name(i.account_id),
                     label: i.label,
                     amount: i.amount,
                 })
                 .collect(),
             budgets: budgets
                 .into_iter()
-                .map(|(label, amount)| BudgetExport {
+                .map(|(label, allocated, carried)| BudgetExport {
                     category_label: label,
-                    allocated_amount: amount,
+                    allocated_amount: allocated,
+                    carried_amount: carried,
                 })
                 .collect(),
             items: item_exports,
+            transfers: transfers
+                .into_iter()
+                .map(|t| TransferExport {
+                    from_account: t.from_account_name,
+                    to_account: t.to_account_name,
+                    amount: t.amount,
+                    transferred_on: t.transferred_on.to_string(),
+                })
+                .collect(),
         });
     }
 
     Ok(Json(UserExport {
         version: 1,
+        currency: Some(currency),
         savings: Some(savings),
         retirement_savings: Some(retirement_savings),
+        accounts: accounts
+            .into_iter()
+            .map(|(id, name, kind, opening_balance)| AccountExport {
+                name,
+                kind,
+                opening_balance,
+                ledger_entries: ledger_entries
+                    .iter()
+                    .filter(|e| e.account_id == id)
+                    .map(|e| LedgerEntryExport {
+                        kind: e.kind,
+                        amount: e.amount,
+                        entry_date: e.entry_date,
+                        note: e.note.clone(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+        goals: goal_exports,
+        tags,
         fixed_expenses: fixed_expenses
             .into_iter()
             .map(|e| FixedExpenseExport {
@@ -188,6 +406,7 @@ pub async fn export_json(
             .map(|c| CategoryExport {
                 label: c.label,
                 default_amount: c.default_amount,
+                rollover_mode: c.rollover_mode,
             })
             .collect(),
         months: month_exports,
@@ -235,6 +454,10 @@ pub async fn import_json(
             .bind(month_id)
             .execute(&mut *tx)
             .await?;
+        sqlx::query("DELETE FROM monthly_fixed_expenses WHERE month_id = ?")
+            .bind(month_id)
+            .execute(&mut *tx)
+            .await?;
     }
 
     sqlx::query("DELETE FROM months WHERE user_id = ?")
@@ -250,39 +473,146 @@ pub async fn import_json(
         .execute(&mut *tx)
         .await?;
 
-    if let Some(savings) = data.savings {
-        sqlx::query("UPDATE users SET savings = ? WHERE id = ?")
-            .bind(savings)
+    sqlx::query("DELETE FROM tags WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM savings_goals WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM accounts WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+
+    if let Some(currency) = data.currency {
+        sqlx::query("UPDATE users SET currency = ? WHERE id = ?")
+            .bind(currency)
             .bind(claims.sub)
             .execute(&mut *tx)
             .await?;
     }
 
-    if let Some(retirement_savings) = data.retirement_savings {
-        sqlx::query("UPDATE users SET retirement_savings = ? WHERE id = ?")
-            .bind(retirement_savings)
-            .bind(claims.sub)
+    let mut account_map: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for account in &data.accounts {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO accounts (user_id, name, kind, opening_balance) VALUES (?, ?, ?, ?) RETURNING id",
+        )
+        .bind(claims.sub)
+        .bind(&account.name)
+        .bind(account.kind)
+        .bind(account.opening_balance)
+        .fetch_one(&mut *tx)
+        .await?;
+        account_map.entry(account.name.clone()).or_insert(id);
+
+        for entry in &account.ledger_entries {
+            sqlx::query(
+                "INSERT INTO ledger_entries (account_id, kind, amount, entry_date, note) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(entry.kind)
+            .bind(entry.amount)
+            .bind(entry.entry_date)
+            .bind(&entry.note)
             .execute(&mut *tx)
             .await?;
+        }
     }
 
-    for expense in &data.fixed_expenses {
-        sqlx::query("INSERT INTO fixed_expenses (user_id, label, amount) VALUES (?, ?, ?)")
-            .bind(claims.sub)
-            .bind(&expense.label)
-            .bind(expense.amount)
+    if data.accounts.is_empty() {
+        if let Some(savings) = data.savings {
+            set_total_balance(
+                &mut tx,
+                claims.sub,
+                AccountKind::Savings,
+                "Savings",
+                savings,
+                Utc::now().date_naive(),
+            )
+            .await?;
+        }
+        if let Some(retirement_savings) = data.retirement_savings {
+            set_total_balance(
+                &mut tx,
+                claims.sub,
+                AccountKind::Retirement,
+                "Retirement",
+                retirement_savings,
+                Utc::now().date_naive(),
+            )
+            .await?;
+        }
+    }
+    let account_id = |name: &Option<String>| name.as_ref().and_then(|n| account_map.get(n));
+
+    let mut tag_map: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    let item_tags = data
+        .months
+        .iter()
+        .flat_map(|m| &m.items)
+        .flat_map(|i| &i.tags);
+    for name in data.tags.iter().chain(item_tags) {
+        if tag_map.contains_key(name) {
+            continue;
+        }
+        let id: i64 =
+            sqlx::query_scalar("INSERT INTO tags (user_id, name) VALUES (?, ?) RETURNING id")
+                .bind(claims.sub)
+                .bind(name)
+                .fetch_one(&mut *tx)
+                .await?;
+        tag_map.insert(name.clone(), id);
+    }
+
+    for goal in &data.goals {
+        let goal_id: i64 = sqlx::query_scalar(
+            "INSERT INTO savings_goals (user_id, name, target_amount, target_date, priority, account_id) VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
+        )
+        .bind(claims.sub)
+        .bind(&goal.name)
+        .bind(goal.target_amount)
+        .bind(goal.target_date)
+        .bind(goal.priority)
+        .bind(account_id(&goal.account))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for allocation in &goal.allocations {
+            sqlx::query(
+                "INSERT INTO goal_allocations (goal_id, amount, allocated_on) VALUES (?, ?, ?)",
+            )
+            .bind(goal_id)
+            .bind(allocation.amount)
+            .bind(allocation.allocated_on)
             .execute(&mut *tx)
             .await?;
+        }
+    }
+
+    let mut expense_map: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    for expense in &data.fixed_expenses {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO fixed_expenses (user_id, label, amount) VALUES (?, ?, ?) RETURNING id",
+        )
+        .bind(claims.sub)
+        .bind(&expense.label)
+        .bind(expense.amount)
+        .fetch_one(&mut *tx)
+        .await?;
+        expense_map.insert(expense.label.clone(), id);
     }
 
     let mut category_map: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
     for cat in &data.categories {
         let id: i64 = sqlx::query_scalar(
-            "INSERT INTO budget_categories (user_id, label, default_amount) VALUES (?, ?, ?) RETURNING id",
+            "INSERT INTO budget_categories (user_id, label, default_amount, rollover_mode) VALUES (?, ?, ?, ?) RETURNING id",
         )
         .bind(claims.sub)
         .bind(&cat.label)
         .bind(cat.default_amount)
+        .bind(cat.rollover_mode)
         .fetch_one(&mut *tx)
         .await?;
         category_map.insert(cat.label.clone(), id);
@@ -299,23 +629,43 @@ pub async fn import_json(
         .fetch_one(&mut *tx)
         .await?;
 
+        let month_fixed = month_data
+            .fixed_expenses
+            .as_ref()
+            .unwrap_or(&data.fixed_expenses);
+        for expense in month_fixed {
+            sqlx::query(
+                "INSERT INTO monthly_fixed_expenses (month_id, fixed_expense_id, label, amount) VALUES (?, ?, ?, ?)",
+            )
+            .bind(month_id)
+            .bind(expense_map.get(&expense.label))
+            .bind(&expense.label)
+            .bind(expense.amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+
         for income in &month_data.income_entries {
-            sqlx::query("INSERT INTO income_entries (month_id, label, amount) VALUES (?, ?, ?)")
-                .bind(month_id)
-                .bind(&income.label)
-                .bind(income.amount)
-                .execute(&mut *tx)
-                .await?;
+            sqlx::query(
+                "INSERT INTO income_entries (month_id, label, amount, account_id) VALUES (?, ?, ?, ?)",
+            )
+            .bind(month_id)
+            .bind(&income.label)
+            .bind(income.amount)
+            .bind(account_id(&income.account))
+            .execute(&mut *tx)
+            .await?;
         }
 
         for budget in &month_data.budgets {
             if let Some(&cat_id) = category_map.get(&budget.category_label) {
                 sqlx::query(
-                    "INSERT INTO monthly_budgets (month_id, category_id, allocated_amount) VALUES (?, ?, ?)",
+                    "INSERT INTO monthly_budgets (month_id, category_id, allocated_amount, carried_amount) VALUES (?, ?, ?, ?)",
                 )
                 .bind(month_id)
                 .bind(cat_id)
                 .bind(budget.allocated_amount)
+                .bind(budget.carried_amount)
                 .execute(&mut *tx)
                 .await?;
             }
@@ -323,14 +673,54 @@ pub async fn import_json(
 
         for item in &month_data.items {
             if let Some(&cat_id) = category_map.get(&item.category_label) {
-                sqlx::query(
-                    "INSERT INTO items (month_id, category_id, description, amount, spent_on) VALUES (?, ?, ?, ?, ?)",
+                let item_id: i64 = sqlx::query_scalar(
+                    "INSERT INTO items (month_id, category_id, description, amount, spent_on, account_id) VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
                 )
                 .bind(month_id)
                 .bind(cat_id)
                 .bind(&item.description)
                 .bind(item.amount)
                 .bind(&item.spent_on)
+                .bind(account_id(&item.account))
+                .fetch_one(&mut *tx)
+                .await?;
+
+                for split in &item.splits {
+                    let Some(&split_cat_id) = category_map.get(&split.category_label) else {
+                        continue;
+                    };
+                    sqlx::query(
+                        "INSERT INTO item_splits (item_id, category_id, amount) VALUES (?, ?, ?)",
+                    )
+                    .bind(item_id)
+                    .bind(split_cat_id)
+                    .bind(split.amount)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+
+                for name in &item.tags {
+                    sqlx::query("INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?, ?)")
+                        .bind(item_id)
+                        .bind(tag_map[name])
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+        }
+
+        for transfer in &month_data.transfers {
+            let from = account_map.get(&transfer.from_account);
+            let to = account_map.get(&transfer.to_account);
+            if let (Some(from), Some(to)) = (from, to) {
+                sqlx::query(
+                    "INSERT INTO transfers (month_id, from_account_id, to_account_id, amount, transferred_on) VALUES (?, ?, ?, ?, ?)",
+                )
+                .bind(month_id)
+                .bind(from)
+                .bind(to)
+                .bind(transfer.amount)
+                .bind(&transfer.transferred_on)
                 .execute(&mut *tx)
                 .await?;
             }
@@ -340,3 +730,101 @@ pub async fn import_json(
     tx.commit().await?;
     Ok(StatusCode::OK)
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    responses(
+        (status = 200, description = "A SQLite database containing only the caller's data", content_type = "application/octet-stream"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Data Management",
+    summary = "Export all data as a SQLite database",
+    description = "Builds a fresh SQLite database with the current schema holding only the authenticated user's rows. Password hashes are never included."
+)]
+pub async fn export_sqlite(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<impl IntoResponse, PaymeError> {
+    let path = temp_database_path();
+    let result = match db::export_user_database(&pool, claims.sub, &path).await {
+        Ok(()) => tokio::fs::read(&path)
+            .await
+            .map_err(|e| PaymeError::Internal(e.to_string())),
+        Err(e) => Err(migration_error(e)),
+    };
+    remove_temp_database(&path).await;
+    let data = result?;
+
+    let filename = format!("attachment; filename=\"payme-{}.db\"", claims.username);
+    Ok((
+        [
+            (
+                "Content-Type".to_string(),
+                "application/octet-stream".to_string(),
+            ),
+            ("Content-Disposition".to_string(), filename),
+        ],
+        data,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    request_body(content = Vec<u8>, description = "A SQLite database produced by /api/export", content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Data imported successfully. Note: This overwrites existing user data."),
+        (status = 400, description = "The upload is not a payme export or was made by a newer version"),
+        (status = 500, description = "Internal server error during database restoration")
+    ),
+    tag = "Data Management",
+    summary = "Import data from a SQLite export",
+    description = "Overwrites the current user's records with the contents of a SQLite export. Exports from older versions are migrated first. This action is destructive and irreversible."
+)]
+pub async fn import_sqlite(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    body: Bytes,
+) -> Result<StatusCode, PaymeError> {
+    if !body.starts_with(b"SQLite format 3\0") {
+        return Err(PaymeError::BadRequest(
+            "Upload is not a SQLite database".to_string(),
+        ));
+    }
+
+    let path = temp_database_path();
+    let result = match tokio::fs::write(&path, &body).await {
+        Ok(()) => db::import_user_database(&pool, claims.sub, &path)
+            .await
+            .map_err(|e| match e {
+                MigrationError::Database(sqlx::Error::Database(e)) => {
+                    PaymeError::BadRequest(format!("Invalid export file: {e}"))
+                }
+                e => migration_error(e),
+            }),
+        Err(e) => Err(PaymeError::Internal(e.to_string())),
+    };
+    remove_temp_database(&path).await;
+    result?;
+
+    Ok(StatusCode::OK)
+}
+
+fn temp_database_path() -> PathBuf {
+    std::env::temp_dir().join(format!("payme-{}.db", Uuid::new_v4()))
+}
+
+async fn remove_temp_database(path: &Path) {
+    tokio::fs::remove_file(path).await.ok();
+}
+
+fn migration_error(e: MigrationError) -> PaymeError {
+    match e {
+        MigrationError::Database(e) => PaymeError::Database(e),
+        MigrationError::DatabaseNewer { .. } => PaymeError::BadRequest(e.to_string()),
+        MigrationError::Failed { .. } => PaymeError::BadRequest(e.to_string()),
+        MigrationError::InvalidExport(_) => PaymeError::BadRequest(e.to_string()),
+    }
+}
@@ -1,84 +1,180 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use serde::Deserialize;
 use sqlx::SqlitePool;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::pagination::{AmountFilter, ListQuery, Paginated, SortDirection};
 use crate::middleware::auth::Claims;
-use crate::models::FixedExpense;
+use crate::models::{FixedExpense, Money};
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateFixedExpense {
     #[validate(length(min = 1, max = 100))]
     pub label: String,
-    #[validate(range(min = 0.0))]
-    pub amount: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateFixedExpense {
     #[validate(length(min = 1, max = 100))]
     pub label: Option<String>,
-    #[validate(range(min = 0.0))]
-    pub amount: Option<f64>,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Option<Money>,
 }
 
+/// Which months a change to a fixed expense is copied into. Only open months
+/// are ever changed, so closed months keep the amounts they were closed with.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonthRange {
+    /// First month to change, as `YYYY-MM`. Defaults to the earliest open month.
+    pub from: Option<String>,
+    /// Last month to change, as `YYYY-MM`. Defaults to the latest open month.
+    pub to: Option<String>,
+}
+
+impl MonthRange {
+    /// The range as inclusive bounds on `year * 12 + month - 1`. Years are
+    /// limited to those a month can be created for.
+    fn bounds(&self) -> Result<(i32, i32), PaymeError> {
+        let index = |raw: &Option<String>, default| match raw {
+            None => Ok(default),
+            Some(raw) => raw
+                .split_once('-')
+                .and_then(|(year, month)| {
+                    Some((year.parse::<i32>().ok()?, month.parse::<i32>().ok()?))
+                })
+                .filter(|(year, month)| (1900..=9999).contains(year) && (1..=12).contains(month))
+                .map(|(year, month)| year * 12 + month - 1)
+                .ok_or_else(|| PaymeError::BadRequest(format!("Invalid month '{raw}'"))),
+        };
+        Ok((index(&self.from, i32::MIN)?, index(&self.to, i32::MAX)?))
+    }
+}
+
+/// Selects the ids of a user's open months within a [`MonthRange`]; binds
+/// the user id and both bounds.
+const OPEN_MONTHS_IN_RANGE: &str = "SELECT id FROM months WHERE user_id = ? AND is_closed = 0 AND year * 12 + month - 1 BETWEEN ? AND ?";
+
 #[utoipa::path(
     get,
     path = "/api/fixed-expenses",
+    params(ListQuery, AmountFilter),
     responses(
-        (status = 200, body = [FixedExpense]),
+        (status = 200, body = [FixedExpense], headers(
+            ("X-Total-Count" = i64, description = "Number of matching rows"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Configuration",
     summary = "List fixed expenses",
-    description = "Retrieves all fixed expenses associated with the authenticated user."
+    description = "Retrieves the fixed expenses associated with the authenticated user, in the order they were added. Also sorts by `label` or `amount`."
 )]
 pub async fn list_fixed_expenses(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
-) -> Result<Json<Vec<FixedExpense>>, PaymeError> {
-    let expenses: Vec<FixedExpense> =
-        sqlx::query_as("SELECT id, user_id, label, amount FROM fixed_expenses WHERE user_id = ?")
-            .bind(claims.sub)
-            .fetch_all(&pool)
-            .await?;
-
-    Ok(Json(expenses))
+    list: ListQuery,
+    Query(amounts): Query<AmountFilter>,
+) -> Result<Paginated<FixedExpense>, PaymeError> {
+    let page = list.page()?;
+    let order_by = list.order_by(
+        &[
+            ("id", &[]),
+            ("label", &["label COLLATE NOCASE"]),
+            ("amount", &["amount"]),
+        ],
+        SortDirection::Asc,
+        "id",
+    )?;
+    let filter = r#"
+        FROM fixed_expenses
+        WHERE user_id = ?
+            AND (? IS NULL OR amount >= ?)
+            AND (? IS NULL OR amount <= ?)
+    "#;
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {filter}"))
+        .bind(claims.sub)
+        .bind(amounts.min_amount)
+        .bind(amounts.min_amount)
+        .bind(amounts.max_amount)
+        .bind(amounts.max_amount)
+        .fetch_one(&pool)
+        .await?;
+    let expenses: Vec<FixedExpense> = sqlx::query_as(&format!(
+        "SELECT id, user_id, label, amount {filter} {order_by} LIMIT ? OFFSET ?"
+    ))
+    .bind(claims.sub)
+    .bind(amounts.min_amount)
+    .bind(amounts.min_amount)
+    .bind(amounts.max_amount)
+    .bind(amounts.max_amount)
+    .bind(page.sql_limit())
+    .bind(page.offset)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(list.paginate(expenses, total, page))
 }
 
 #[utoipa::path(
     post,
     path = "/api/fixed-expenses",
+    params(MonthRange),
     request_body = CreateFixedExpense,
     responses(
         (status = 201, body = FixedExpense),
+        (status = 400, description = "Invalid month range"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Configuration",
     summary = "Create fixed expense",
-    description = "Adds a new recurring expense (e.g., Rent, Internet) to the user's profile."
+    description = "Adds a new recurring expense (e.g., Rent, Internet) to the user's profile and to their open months in the given range. Months created later include it."
 )]
 pub async fn create_fixed_expense(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
+    Query(range): Query<MonthRange>,
     Json(payload): Json<CreateFixedExpense>,
 ) -> Result<Json<FixedExpense>, PaymeError> {
     payload.validate()?;
+    let (from, to) = range.bounds()?;
+
+    let mut tx = pool.begin().await?;
     let id: i64 = sqlx::query_scalar(
         "INSERT INTO fixed_expenses (user_id, label, amount) VALUES (?, ?, ?) RETURNING id",
     )
     .bind(claims.sub)
     .bind(&payload.label)
     .bind(payload.amount)
-    .fetch_one(&pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO monthly_fixed_expenses (month_id, fixed_expense_id, label, amount)
+        SELECT id, ?, ?, ? FROM months WHERE id IN ({OPEN_MONTHS_IN_RANGE})
+        "#
+    ))
+    .bind(id)
+    .bind(&payload.label)
+    .bind(payload.amount)
+    .bind(claims.sub)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
     Ok(Json(FixedExpense {
         id,
         user_id: claims.sub,
@@ -90,24 +186,27 @@ pub async fn create_fixed_expense(
 #[utoipa::path(
     put,
     path = "/api/fixed-expenses/{id}",
-    params(("id" = i64, Path, description = "Expense ID")),
+    params(("id" = i64, Path, description = "Expense ID"), MonthRange),
     request_body = UpdateFixedExpense,
     responses(
         (status = 200, body = FixedExpense),
+        (status = 400, description = "Invalid month range"),
         (status = 404, description = "Not Found"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Configuration",
     summary = "Update fixed expense",
-    description = "Updates the label or amount of an existing fixed expense by ID."
+    description = "Updates the label or amount of an existing fixed expense by ID, and of its copies in open months within the given range. Closed months are not changed."
 )]
 pub async fn update_fixed_expense(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Path(expense_id): Path<i64>,
+    Query(range): Query<MonthRange>,
     Json(payload): Json<UpdateFixedExpense>,
 ) -> Result<Json<FixedExpense>, PaymeError> {
     payload.validate()?;
+    let (from, to) = range.bounds()?;
     let existing: FixedExpense = sqlx::query_as(
         "SELECT id, user_id, label, amount FROM fixed_expenses WHERE id = ? AND user_id = ?",
     )
@@ -120,13 +219,30 @@ pub async fn update_fixed_expense(
     let label = payload.label.unwrap_or(existing.label);
     let amount = payload.amount.unwrap_or(existing.amount);
 
+    let mut tx = pool.begin().await?;
     sqlx::query("UPDATE fixed_expenses SET label = ?, amount = ? WHERE id = ?")
         .bind(&label)
         .bind(amount)
         .bind(expense_id)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
 
+    sqlx::query(&format!(
+        r#"
+        UPDATE monthly_fixed_expenses SET label = ?, amount = ?
+        WHERE fixed_expense_id = ? AND month_id IN ({OPEN_MONTHS_IN_RANGE})
+        "#
+    ))
+    .bind(&label)
+    .bind(amount)
+    .bind(expense_id)
+    .bind(claims.sub)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
     Ok(Json(FixedExpense {
         id: expense_id,
         user_id: claims.sub,
@@ -138,22 +254,45 @@ pub async fn update_fixed_expense(
 #[utoipa::path(
     delete,
     path = "/api/fixed-expenses/{id}",
-    params(("id" = i64, Path, description = "Expense ID")),
-    responses((status = 204, description = "Deleted")),
+    params(("id" = i64, Path, description = "Expense ID"), MonthRange),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 400, description = "Invalid month range")
+    ),
     tag = "Configuration",
     summary = "Delete fixed expense",
-    description = "Permanently removes a recurring expense template."
+    description = "Permanently removes a recurring expense template, and its copies in open months within the given range. Other months keep their copy."
 )]
 pub async fn delete_fixed_expense(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Path(expense_id): Path<i64>,
+    Query(range): Query<MonthRange>,
 ) -> Result<StatusCode, PaymeError> {
+    let (from, to) = range.bounds()?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(&format!(
+        r#"
+        DELETE FROM monthly_fixed_expenses
+        WHERE fixed_expense_id IN (SELECT id FROM fixed_expenses WHERE id = ? AND user_id = ?)
+            AND month_id IN ({OPEN_MONTHS_IN_RANGE})
+        "#
+    ))
+    .bind(expense_id)
+    .bind(claims.sub)
+    .bind(claims.sub)
+    .bind(from)
+    .bind(to)
+    .execute(&mut *tx)
+    .await?;
+
     sqlx::query("DELETE FROM fixed_expenses WHERE id = ? AND user_id = ?")
         .bind(expense_id)
         .bind(claims.sub)
-        .execute(&pool)
+        .execute(&mut *tx)
         .await?;
+    tx.commit().await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
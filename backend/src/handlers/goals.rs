@@ -0,0 +1,310 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::handlers::accounts::{verify_account, ACCOUNT_SELECT};
+use crate::middleware::auth::Claims;
+use crate::models::{GoalAllocation, Money, SavingsGoal};
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveGoal {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(range(min = Money::ZERO))]
+    pub target_amount: Money,
+    pub target_date: NaiveDate,
+    /// Lower numbers come first.
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub priority: i32,
+    /// Tracks progress by this account's balance instead of allocations.
+    pub account_id: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateAllocation {
+    pub amount: Money,
+    /// Defaults to today.
+    pub allocated_on: Option<NaiveDate>,
+}
+
+/// Selects [`SavingsGoal`] rows with their saved amount; append a `WHERE`.
+fn goal_select() -> String {
+    format!(
+        r#"
+        SELECT g.id, g.user_id, g.name, g.target_amount, g.target_date, g.priority, g.account_id,
+            CASE WHEN g.account_id IS NULL
+                THEN COALESCE((SELECT SUM(amount) FROM goal_allocations WHERE goal_id = g.id), 0)
+                ELSE (SELECT balance FROM ({ACCOUNT_SELECT}) acc WHERE acc.id = g.account_id)
+            END AS saved
+        FROM savings_goals g
+        "#
+    )
+}
+
+/// The user's goals in priority order, with progress as of today.
+pub(crate) async fn list_goal_progress(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<Vec<SavingsGoal>, sqlx::Error> {
+    let today = Utc::now().date_naive();
+    let goals: Vec<SavingsGoal> = sqlx::query_as(&format!(
+        "{} WHERE g.user_id = ? ORDER BY g.priority, g.target_date, g.id",
+        goal_select()
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(goals.into_iter().map(|g| g.with_progress(today)).collect())
+}
+
+async fn fetch_goal(pool: &SqlitePool, user_id: i64, id: i64) -> Result<SavingsGoal, PaymeError> {
+    let goal: SavingsGoal = sqlx::query_as(&format!(
+        "{} WHERE g.id = ? AND g.user_id = ?",
+        goal_select()
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    Ok(goal.with_progress(Utc::now().date_naive()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/goals",
+    responses(
+        (status = 200, body = [SavingsGoal]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "List savings goals",
+    description = "Retrieves the user's savings goals in priority order, with how much is saved and the monthly contribution needed to reach each by its target date."
+)]
+pub async fn list_goals(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<SavingsGoal>>, PaymeError> {
+    Ok(Json(list_goal_progress(&pool, claims.sub).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/goals",
+    request_body = SaveGoal,
+    responses(
+        (status = 200, body = SavingsGoal),
+        (status = 400, description = "Invalid goal or account"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Create savings goal",
+    description = "Adds a savings goal, optionally tracked by one of the user's accounts."
+)]
+pub async fn create_goal(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveGoal>,
+) -> Result<Json<SavingsGoal>, PaymeError> {
+    payload.validate()?;
+    verify_account(&pool, claims.sub, payload.account_id).await?;
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO savings_goals (user_id, name, target_amount, target_date, priority, account_id) VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(claims.sub)
+    .bind(&payload.name)
+    .bind(payload.target_amount)
+    .bind(payload.target_date)
+    .bind(payload.priority)
+    .bind(payload.account_id)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(fetch_goal(&pool, claims.sub, id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/goals/{id}",
+    params(("id" = i64, Path, description = "Goal ID")),
+    request_body = SaveGoal,
+    responses(
+        (status = 200, body = SavingsGoal),
+        (status = 400, description = "Invalid goal or account"),
+        (status = 404, description = "Goal not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Update savings goal",
+    description = "Replaces a goal's name, target, priority and linked account. Allocations are kept."
+)]
+pub async fn update_goal(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveGoal>,
+) -> Result<Json<SavingsGoal>, PaymeError> {
+    payload.validate()?;
+    verify_account(&pool, claims.sub, payload.account_id).await?;
+
+    let result = sqlx::query(
+        "UPDATE savings_goals SET name = ?, target_amount = ?, target_date = ?, priority = ?, account_id = ? WHERE id = ? AND user_id = ?",
+    )
+    .bind(&payload.name)
+    .bind(payload.target_amount)
+    .bind(payload.target_date)
+    .bind(payload.priority)
+    .bind(payload.account_id)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(fetch_goal(&pool, claims.sub, id).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/goals/{id}",
+    params(("id" = i64, Path, description = "Goal ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Wealth",
+    summary = "Delete savings goal",
+    description = "Removes a goal and its allocations. A linked account is left untouched."
+)]
+pub async fn delete_goal(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM savings_goals WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/goals/{id}/allocations",
+    params(("id" = i64, Path, description = "Goal ID")),
+    responses(
+        (status = 200, body = [GoalAllocation]),
+        (status = 404, description = "Goal not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "List goal allocations",
+    description = "Retrieves the money set aside for a goal, newest first."
+)]
+pub async fn list_allocations(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(goal_id): Path<i64>,
+) -> Result<Json<Vec<GoalAllocation>>, PaymeError> {
+    fetch_goal(&pool, claims.sub, goal_id).await?;
+
+    let allocations: Vec<GoalAllocation> = sqlx::query_as(
+        "SELECT id, goal_id, amount, allocated_on FROM goal_allocations WHERE goal_id = ? ORDER BY allocated_on DESC, id DESC",
+    )
+    .bind(goal_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(allocations))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/goals/{id}/allocations",
+    params(("id" = i64, Path, description = "Goal ID")),
+    request_body = CreateAllocation,
+    responses(
+        (status = 200, body = GoalAllocation),
+        (status = 400, description = "Goal is linked to an account"),
+        (status = 404, description = "Goal not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Allocate to goal",
+    description = "Sets money aside for a goal that is not linked to an account. A negative amount takes money back out."
+)]
+pub async fn create_allocation(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(goal_id): Path<i64>,
+    Json(payload): Json<CreateAllocation>,
+) -> Result<Json<GoalAllocation>, PaymeError> {
+    let goal = fetch_goal(&pool, claims.sub, goal_id).await?;
+    if goal.account_id.is_some() {
+        return Err(PaymeError::BadRequest(
+            "Goal is linked to an account".to_string(),
+        ));
+    }
+    let allocated_on = payload
+        .allocated_on
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO goal_allocations (goal_id, amount, allocated_on) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(goal_id)
+    .bind(payload.amount)
+    .bind(allocated_on)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(GoalAllocation {
+        id,
+        goal_id,
+        amount: payload.amount,
+        allocated_on,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/goals/{goal_id}/allocations/{id}",
+    params(
+        ("goal_id" = i64, Path, description = "Goal ID"),
+        ("id" = i64, Path, description = "Allocation ID")
+    ),
+    responses((status = 204, description = "Deleted")),
+    tag = "Wealth",
+    summary = "Delete goal allocation",
+    description = "Removes an allocation from a goal."
+)]
+pub async fn delete_allocation(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((goal_id, id)): Path<(i64, i64)>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query(
+        "DELETE FROM goal_allocations WHERE id = ? AND goal_id IN (SELECT id FROM savings_goals WHERE id = ? AND user_id = ?)",
+    )
+    .bind(id)
+    .bind(goal_id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,770 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteConnection, SqlitePool};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use super::months::insert_month;
+use crate::categorization::RuleSet;
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{CsvImportProfile, CsvMapping, Money};
+use crate::statements::{self, RowError, StatementRow};
+
+const PROFILE_COLUMNS: &str = "id, user_id, name, delimiter, has_header, date_column, description_column, amount_column, sign_convention, decimal_separator, date_format, category_id";
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveCsvProfile {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub mapping: CsvMapping,
+    pub category_id: Option<i64>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CsvImportRequest {
+    /// Raw contents of the CSV file.
+    pub csv: String,
+    /// Saved profile supplying the mapping and default category.
+    pub profile_id: Option<i64>,
+    /// Mapping to use instead of the profile's.
+    #[validate(nested)]
+    pub mapping: Option<CsvMapping>,
+    /// Category for rows without an override or matching rule; defaults to the profile's.
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub category_overrides: Vec<CategoryOverride>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct OfxImportRequest {
+    /// Raw contents of the OFX or QFX file.
+    pub ofx: String,
+    /// Category for debits without an override or matching rule.
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub category_overrides: Vec<CategoryOverride>,
+}
+
+/// Places the row on `line` of the file into a specific category.
+#[derive(Deserialize, ToSchema)]
+pub struct CategoryOverride {
+    pub line: usize,
+    pub category_id: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRow {
+    pub line: usize,
+    pub spent_on: NaiveDate,
+    pub description: String,
+    pub amount: Money,
+    pub category_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fitid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fitid_account: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportIncome {
+    pub line: usize,
+    pub received_on: NaiveDate,
+    pub label: String,
+    pub amount: Money,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fitid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fitid_account: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SkippedRow {
+    pub line: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+pub struct YearMonth {
+    pub year: i32,
+    pub month: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportPreview {
+    /// Rows that would become items.
+    pub rows: Vec<ImportRow>,
+    /// Credits that would become income entries.
+    pub income: Vec<ImportIncome>,
+    /// Rows that would be left out, with the reason.
+    pub skipped: Vec<SkippedRow>,
+    /// Months that do not exist yet and would be created.
+    pub new_months: Vec<YearMonth>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportResult {
+    /// Number of items created.
+    pub imported: usize,
+    pub income_imported: usize,
+    pub months_created: usize,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/import/csv/profiles",
+    responses(
+        (status = 200, body = [CsvImportProfile]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "List CSV mapping profiles",
+    description = "Retrieves the saved column mappings used to import each bank's CSV export."
+)]
+pub async fn list_csv_profiles(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<CsvImportProfile>>, PaymeError> {
+    let profiles: Vec<CsvImportProfile> = sqlx::query_as(&format!(
+        "SELECT {PROFILE_COLUMNS} FROM csv_import_profiles WHERE user_id = ? ORDER BY name"
+    ))
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(profiles))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/csv/profiles",
+    request_body = SaveCsvProfile,
+    responses(
+        (status = 200, body = CsvImportProfile),
+        (status = 400, description = "Invalid mapping or category"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Create CSV mapping profile",
+    description = "Saves a column mapping under a name so the same bank export can be imported again in one step."
+)]
+pub async fn create_csv_profile(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveCsvProfile>,
+) -> Result<Json<CsvImportProfile>, PaymeError> {
+    payload.validate()?;
+    if let Some(category_id) = payload.category_id {
+        verify_categories(&pool, claims.sub, [category_id]).await?;
+    }
+
+    let mapping = &payload.mapping;
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO csv_import_profiles
+            (user_id, name, delimiter, has_header, date_column, description_column, amount_column,
+             sign_convention, decimal_separator, date_format, category_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(&payload.name)
+    .bind(&mapping.delimiter)
+    .bind(mapping.has_header)
+    .bind(mapping.date_column)
+    .bind(mapping.description_column)
+    .bind(mapping.amount_column)
+    .bind(mapping.sign_convention)
+    .bind(mapping.decimal_separator)
+    .bind(&mapping.date_format)
+    .bind(payload.category_id)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(CsvImportProfile {
+        id,
+        user_id: claims.sub,
+        name: payload.name,
+        mapping: payload.mapping,
+        category_id: payload.category_id,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/import/csv/profiles/{id}",
+    params(("id" = i64, Path, description = "Profile ID")),
+    request_body = SaveCsvProfile,
+    responses(
+        (status = 200, body = CsvImportProfile),
+        (status = 400, description = "Invalid mapping or category"),
+        (status = 404, description = "Profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Update CSV mapping profile",
+    description = "Replaces the name, mapping and default category of a saved profile."
+)]
+pub async fn update_csv_profile(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveCsvProfile>,
+) -> Result<Json<CsvImportProfile>, PaymeError> {
+    payload.validate()?;
+    fetch_profile(&pool, claims.sub, id).await?;
+    if let Some(category_id) = payload.category_id {
+        verify_categories(&pool, claims.sub, [category_id]).await?;
+    }
+
+    let mapping = &payload.mapping;
+    sqlx::query(
+        r#"
+        UPDATE csv_import_profiles
+        SET name = ?, delimiter = ?, has_header = ?, date_column = ?, description_column = ?,
+            amount_column = ?, sign_convention = ?, decimal_separator = ?, date_format = ?,
+            category_id = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+    )
+    .bind(&payload.name)
+    .bind(&mapping.delimiter)
+    .bind(mapping.has_header)
+    .bind(mapping.date_column)
+    .bind(mapping.description_column)
+    .bind(mapping.amount_column)
+    .bind(mapping.sign_convention)
+    .bind(mapping.decimal_separator)
+    .bind(&mapping.date_format)
+    .bind(payload.category_id)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    Ok(Json(CsvImportProfile {
+        id,
+        user_id: claims.sub,
+        name: payload.name,
+        mapping: payload.mapping,
+        category_id: payload.category_id,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/import/csv/profiles/{id}",
+    params(("id" = i64, Path, description = "Profile ID")),
+    responses(
+        (status = 204, description = "Profile deleted successfully"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Delete CSV mapping profile",
+    description = "Removes a saved mapping profile. Previously imported items are not affected."
+)]
+pub async fn delete_csv_profile(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM csv_import_profiles WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/csv/preview",
+    request_body = CsvImportRequest,
+    responses(
+        (status = 200, body = ImportPreview),
+        (status = 400, description = "Missing mapping or invalid category"),
+        (status = 404, description = "Profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Preview CSV import",
+    description = "Parses a bank statement and reports which rows would become items, which would be skipped and which months would be created. Nothing is saved."
+)]
+pub async fn preview_csv_import(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CsvImportRequest>,
+) -> Result<Json<ImportPreview>, PaymeError> {
+    let rows = read_csv(&pool, claims.sub, &payload).await?;
+    Ok(Json(
+        plan_import(&pool, claims.sub, rows, Credits::Skip).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/csv",
+    request_body = CsvImportRequest,
+    responses(
+        (status = 200, body = ImportResult),
+        (status = 400, description = "Missing mapping, invalid category, or a target month was closed during the import"),
+        (status = 404, description = "Profile not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Import CSV statement",
+    description = "Creates an item for every importable row of a bank statement, creating missing months along the way. Rows reported as skipped by the preview are left out."
+)]
+pub async fn import_csv(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CsvImportRequest>,
+) -> Result<Json<ImportResult>, PaymeError> {
+    let rows = read_csv(&pool, claims.sub, &payload).await?;
+    let preview = plan_import(&pool, claims.sub, rows, Credits::Skip).await?;
+    Ok(Json(commit_import(&pool, claims.sub, preview).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/ofx/preview",
+    request_body = OfxImportRequest,
+    responses(
+        (status = 200, body = ImportPreview),
+        (status = 400, description = "Not an OFX file or invalid category"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Preview OFX import",
+    description = "Parses an OFX or QFX statement and reports which debits would become items, which credits would become income, and which transactions would be skipped, including those imported before. Nothing is saved."
+)]
+pub async fn preview_ofx_import(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<OfxImportRequest>,
+) -> Result<Json<ImportPreview>, PaymeError> {
+    let rows = read_ofx(&pool, claims.sub, &payload).await?;
+    Ok(Json(
+        plan_import(&pool, claims.sub, rows, Credits::AsIncome).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/import/ofx",
+    request_body = OfxImportRequest,
+    responses(
+        (status = 200, body = ImportResult),
+        (status = 400, description = "Not an OFX file, invalid category, or a target month was closed during the import"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Import",
+    summary = "Import OFX statement",
+    description = "Creates items for debits and income entries for credits, creating missing months along the way. Each transaction's FITID is stored, so importing the same statement again adds nothing."
+)]
+pub async fn import_ofx(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<OfxImportRequest>,
+) -> Result<Json<ImportResult>, PaymeError> {
+    let rows = read_ofx(&pool, claims.sub, &payload).await?;
+    let preview = plan_import(&pool, claims.sub, rows, Credits::AsIncome).await?;
+    Ok(Json(commit_import(&pool, claims.sub, preview).await?))
+}
+
+/// A parsed statement row together with the category it will be filed under.
+type CategorisedRow = Result<(StatementRow, Option<i64>), RowError>;
+
+async fn read_csv(
+    pool: &SqlitePool,
+    user_id: i64,
+    payload: &CsvImportRequest,
+) -> Result<Vec<CategorisedRow>, PaymeError> {
+    payload.validate()?;
+
+    let profile = match payload.profile_id {
+        Some(id) => Some(fetch_profile(pool, user_id, id).await?),
+        None => None,
+    };
+    let mapping = payload
+        .mapping
+        .as_ref()
+        .or(profile.as_ref().map(|p| &p.mapping))
+        .ok_or_else(|| PaymeError::BadRequest("A mapping or profile_id is required".to_string()))?;
+    let default_category = payload
+        .category_id
+        .or(profile.as_ref().and_then(|p| p.category_id));
+
+    let rows = statements::csv::parse_statement(&payload.csv, mapping);
+    categorise(
+        pool,
+        user_id,
+        rows,
+        default_category,
+        &payload.category_overrides,
+    )
+    .await
+}
+
+async fn read_ofx(
+    pool: &SqlitePool,
+    user_id: i64,
+    payload: &OfxImportRequest,
+) -> Result<Vec<CategorisedRow>, PaymeError> {
+    let rows = statements::ofx::parse_statement(&payload.ofx).map_err(PaymeError::BadRequest)?;
+    categorise(
+        pool,
+        user_id,
+        rows,
+        payload.category_id,
+        &payload.category_overrides,
+    )
+    .await
+}
+
+/// Pairs each row with its category: the override for its line, else the
+/// first matching rule, else the default. Every category passed in must
+/// belong to `user_id`.
+async fn categorise(
+    pool: &SqlitePool,
+    user_id: i64,
+    rows: Vec<Result<StatementRow, RowError>>,
+    default_category: Option<i64>,
+    overrides: &[CategoryOverride],
+) -> Result<Vec<CategorisedRow>, PaymeError> {
+    let overrides: HashMap<usize, i64> =
+        overrides.iter().map(|o| (o.line, o.category_id)).collect();
+    verify_categories(
+        pool,
+        user_id,
+        default_category
+            .into_iter()
+            .chain(overrides.values().copied()),
+    )
+    .await?;
+
+    let rules = RuleSet::load(pool, user_id).await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            row.map(|row| {
+                let category = overrides
+                    .get(&row.line)
+                    .copied()
+                    .or_else(|| rules.category_for(&row.description, row.amount, row.spent_on))
+                    .or(default_category);
+                (row, category)
+            })
+        })
+        .collect())
+}
+
+/// What to do with rows that bring money in.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Credits {
+    Skip,
+    AsIncome,
+}
+
+/// Decides what happens to each row without writing anything.
+async fn plan_import(
+    pool: &SqlitePool,
+    user_id: i64,
+    rows: Vec<CategorisedRow>,
+    credits: Credits,
+) -> Result<ImportPreview, PaymeError> {
+    let months: HashMap<YearMonth, bool> = sqlx::query_as::<_, (i32, i32, bool)>(
+        "SELECT year, month, is_closed FROM months WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(year, month, is_closed)| (YearMonth { year, month }, is_closed))
+    .collect();
+
+    // Keyed like the unique FITID indexes, per month. Transactions without an
+    // account id share the empty one.
+    let mut seen_fitids: HashSet<(YearMonth, String, String)> =
+        sqlx::query_as::<_, (i32, i32, String, String)>(
+            r#"
+        SELECT m.year, m.month, COALESCE(i.fitid_account, ''), i.fitid FROM items i JOIN months m ON i.month_id = m.id
+        WHERE m.user_id = ? AND i.fitid IS NOT NULL
+        UNION
+        SELECT m.year, m.month, COALESCE(e.fitid_account, ''), e.fitid FROM income_entries e JOIN months m ON e.month_id = m.id
+        WHERE m.user_id = ? AND e.fitid IS NOT NULL
+        "#,
+        )
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(year, month, account, fitid)| (YearMonth { year, month }, account, fitid))
+        .collect();
+
+    let mut preview = ImportPreview {
+        rows: Vec::new(),
+        income: Vec::new(),
+        skipped: Vec::new(),
+        new_months: Vec::new(),
+    };
+    let mut new_months = BTreeSet::new();
+
+    for row in rows {
+        let (row, category_id) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                preview.skipped.push(SkippedRow {
+                    line: e.line,
+                    reason: e.message,
+                });
+                continue;
+            }
+        };
+        let skip = |reason: String| SkippedRow {
+            line: row.line,
+            reason,
+        };
+
+        let key = YearMonth {
+            year: row.spent_on.year(),
+            month: row.spent_on.month() as i32,
+        };
+        if let Some(fitid) = &row.fitid {
+            let account = row.account.clone().unwrap_or_default();
+            if !seen_fitids.insert((key, account, fitid.clone())) {
+                preview.skipped.push(skip("Already imported".to_string()));
+                continue;
+            }
+        }
+        let is_credit = row.amount.is_negative();
+        if is_credit && credits == Credits::Skip {
+            preview
+                .skipped
+                .push(skip("Credits are not imported as spending".to_string()));
+            continue;
+        }
+        if !is_credit && category_id.is_none() {
+            preview.skipped.push(skip("No category".to_string()));
+            continue;
+        }
+
+        match months.get(&key) {
+            Some(true) => {
+                preview.skipped.push(skip(format!(
+                    "Month {}-{:02} is closed",
+                    key.year, key.month
+                )));
+                continue;
+            }
+            Some(false) => {}
+            None => {
+                new_months.insert(key);
+            }
+        }
+
+        match category_id {
+            Some(category_id) if !is_credit => preview.rows.push(ImportRow {
+                line: row.line,
+                spent_on: row.spent_on,
+                description: row.description,
+                amount: row.amount,
+                category_id,
+                fitid: row.fitid,
+                fitid_account: row.account,
+            }),
+            _ => preview.income.push(ImportIncome {
+                line: row.line,
+                received_on: row.spent_on,
+                label: row.description,
+                amount: -row.amount,
+                fitid: row.fitid,
+                fitid_account: row.account,
+            }),
+        }
+    }
+
+    preview.new_months = new_months.into_iter().collect();
+    Ok(preview)
+}
+
+/// Writes a planned import in a single transaction. Transactions stored
+/// since the plan was made, by an import running alongside, are skipped.
+async fn commit_import(
+    pool: &SqlitePool,
+    user_id: i64,
+    preview: ImportPreview,
+) -> Result<ImportResult, PaymeError> {
+    let mut tx = pool.begin().await?;
+
+    for key in &preview.new_months {
+        insert_month(&mut tx, user_id, key.year, key.month).await?;
+    }
+    let month_ids: HashMap<YearMonth, (i64, bool)> = sqlx::query_as::<_, (i64, i32, i32, bool)>(
+        "SELECT id, year, month, is_closed FROM months WHERE user_id = ?",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(id, year, month, is_closed)| (YearMonth { year, month }, (id, is_closed)))
+    .collect();
+    // A month the plan saw can have been deleted or closed since; the import
+    // is abandoned rather than filed somewhere else.
+    let month_of = |date: NaiveDate| match month_ids.get(&YearMonth {
+        year: date.year(),
+        month: date.month() as i32,
+    }) {
+        Some((_, true)) => Err(PaymeError::MonthClosed),
+        Some((id, false)) => Ok(*id),
+        None => Err(PaymeError::Conflict(format!(
+            "Month {}-{:02} was deleted during the import",
+            date.year(),
+            date.month()
+        ))),
+    };
+
+    let mut skipped = preview.skipped;
+    let mut already_imported = |line: usize| {
+        skipped.push(SkippedRow {
+            line,
+            reason: "Already imported".to_string(),
+        })
+    };
+
+    let mut imported = 0;
+    for row in &preview.rows {
+        let month_id = month_of(row.spent_on)?;
+        if is_imported(&mut tx, month_id, &row.fitid_account, &row.fitid).await? {
+            already_imported(row.line);
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO items (month_id, category_id, description, amount, spent_on, fitid, fitid_account) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(month_id)
+        .bind(row.category_id)
+        .bind(&row.description)
+        .bind(row.amount)
+        .bind(row.spent_on)
+        .bind(&row.fitid)
+        .bind(&row.fitid_account)
+        .execute(&mut *tx)
+        .await?;
+        imported += 1;
+    }
+
+    let mut income_imported = 0;
+    for income in &preview.income {
+        let month_id = month_of(income.received_on)?;
+        if is_imported(&mut tx, month_id, &income.fitid_account, &income.fitid).await? {
+            already_imported(income.line);
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO income_entries (month_id, label, amount, fitid, fitid_account) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(month_id)
+        .bind(&income.label)
+        .bind(income.amount)
+        .bind(&income.fitid)
+        .bind(&income.fitid_account)
+        .execute(&mut *tx)
+        .await?;
+        income_imported += 1;
+    }
+
+    tx.commit().await?;
+
+    Ok(ImportResult {
+        imported,
+        income_imported,
+        months_created: preview.new_months.len(),
+        skipped,
+    })
+}
+
+/// Whether month `month_id` already has the transaction `fitid` from
+/// `account`, the scope of the unique FITID indexes. A transaction's date
+/// fixes its month, so a re-imported statement is always checked against the
+/// month it went into. Rows without a FITID are never considered duplicates.
+async fn is_imported(
+    conn: &mut SqliteConnection,
+    month_id: i64,
+    account: &Option<String>,
+    fitid: &Option<String>,
+) -> Result<bool, PaymeError> {
+    let Some(fitid) = fitid else {
+        return Ok(false);
+    };
+    let account = account.as_deref().unwrap_or_default();
+    Ok(sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM items
+            WHERE month_id = ? AND COALESCE(fitid_account, '') = ? AND fitid = ?
+            UNION ALL
+            SELECT 1 FROM income_entries
+            WHERE month_id = ? AND COALESCE(fitid_account, '') = ? AND fitid = ?
+        )
+        "#,
+    )
+    .bind(month_id)
+    .bind(account)
+    .bind(fitid)
+    .bind(month_id)
+    .bind(account)
+    .bind(fitid)
+    .fetch_one(conn)
+    .await?)
+}
+
+async fn fetch_profile(
+    pool: &SqlitePool,
+    user_id: i64,
+    id: i64,
+) -> Result<CsvImportProfile, PaymeError> {
+    sqlx::query_as(&format!(
+        "SELECT {PROFILE_COLUMNS} FROM csv_import_profiles WHERE id = ? AND user_id = ?"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::NotFound)
+}
+
+/// Rejects any category id that does not belong to `user_id`.
+async fn verify_categories(
+    pool: &SqlitePool,
+    user_id: i64,
+    category_ids: impl IntoIterator<Item = i64>,
+) -> Result<(), PaymeError> {
+    let owned: HashSet<i64> =
+        sqlx::query_scalar("SELECT id FROM budget_categories WHERE user_id = ?")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect();
+
+    if category_ids.into_iter().all(|id| owned.contains(&id)) {
+        Ok(())
+    } else {
+        Err(PaymeError::InvalidReference("category"))
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
@@ -9,50 +9,96 @@ use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::accounts::verify_account;
+use crate::handlers::pagination::{
+    AmountFilter, ListQuery, Paginated, SortDirection, DEFAULT_LIMIT,
+};
 use crate::middleware::auth::Claims;
-use crate::models::IncomeEntry;
+use crate::models::{IncomeEntry, Money};
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct CreateIncome {
     #[validate(length(min = 1, max = 100))]
     pub label: String,
-    #[validate(range(min = 0.0))]
-    pub amount: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
+    pub account_id: Option<i64>,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateIncome {
     #[validate(length(min = 1, max = 100))]
     pub label: Option<String>,
-    #[validate(range(min = 0.0))]
-    pub amount: Option<f64>,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Option<Money>,
+    /// `null` unlinks the entry from its account.
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[schema(value_type = Option<i64>)]
+    pub account_id: Option<Option<i64>>,
 }
 
 #[utoipa::path(
     get, path = "/api/months/{id}/income",
-    params(("id" = i64, Path)),
+    params(("id" = i64, Path), ListQuery, AmountFilter),
     responses(
-        (status = 200, body = [IncomeEntry]),
+        (status = 200, body = [IncomeEntry], headers(
+            ("X-Total-Count" = i64, description = "Number of matching rows"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Income",
     summary = "List monthly income",
-    description = "Retrieves all sources of income (paychecks, gifts, etc.) recorded for a specific month."
+    description = "Retrieves the sources of income (paychecks, gifts, etc.) recorded for a specific month, in the order they were added. Also sorts by `label` or `amount`."
 )]
 pub async fn list_income(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Path(month_id): Path<i64>,
-) -> Result<Json<Vec<IncomeEntry>>, PaymeError> {
+    list: ListQuery,
+    Query(amounts): Query<AmountFilter>,
+) -> Result<Paginated<IncomeEntry>, PaymeError> {
     verify_month_access(&pool, claims.sub, month_id).await?;
+    let page = list.page_or(DEFAULT_LIMIT)?;
+    let order_by = list.order_by(
+        &[
+            ("id", &[]),
+            ("label", &["label COLLATE NOCASE"]),
+            ("amount", &["amount"]),
+        ],
+        SortDirection::Asc,
+        "id",
+    )?;
+    let filter = r#"
+        FROM income_entries
+        WHERE month_id = ?
+            AND (? IS NULL OR amount >= ?)
+            AND (? IS NULL OR amount <= ?)
+    "#;
 
-    let entries: Vec<IncomeEntry> =
-        sqlx::query_as("SELECT id, month_id, label, amount FROM income_entries WHERE month_id = ?")
-            .bind(month_id)
-            .fetch_all(&pool)
-            .await?;
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {filter}"))
+        .bind(month_id)
+        .bind(amounts.min_amount)
+        .bind(amounts.min_amount)
+        .bind(amounts.max_amount)
+        .bind(amounts.max_amount)
+        .fetch_one(&pool)
+        .await?;
+    let entries: Vec<IncomeEntry> = sqlx::query_as(&format!(
+        "SELECT id, month_id, label, amount, account_id {filter} {order_by} LIMIT ? OFFSET ?"
+    ))
+    .bind(month_id)
+    .bind(amounts.min_amount)
+    .bind(amounts.min_amount)
+    .bind(amounts.max_amount)
+    .bind(amounts.max_amount)
+    .bind(page.sql_limit())
+    .bind(page.offset)
+    .fetch_all(&pool)
+    .await?;
 
-    Ok(Json(entries))
+    Ok(list.paginate(entries, total, page))
 }
 
 #[utoipa::path(
@@ -75,13 +121,15 @@ pub async fn create_income(
 ) -> Result<Json<IncomeEntry>, PaymeError> {
     payload.validate()?;
     verify_month_not_closed(&pool, claims.sub, month_id).await?;
+    verify_account(&pool, claims.sub, payload.account_id).await?;
 
     let id: i64 = sqlx::query_scalar(
-        "INSERT INTO income_entries (month_id, label, amount) VALUES (?, ?, ?) RETURNING id",
+        "INSERT INTO income_entries (month_id, label, amount, account_id) VALUES (?, ?, ?, ?) RETURNING id",
     )
     .bind(month_id)
     .bind(&payload.label)
     .bind(payload.amount)
+    .bind(payload.account_id)
     .fetch_one(&pool)
     .await?;
 
@@ -90,6 +138,7 @@ pub async fn create_income(
         month_id,
         label: payload.label,
         amount: payload.amount,
+        account_id: payload.account_id,
     }))
 }
 
@@ -107,7 +156,7 @@ pub async fn create_income(
     ),
     tag = "Income",
     summary = "Update income entry",
-    description = "Modifies an existing income record's label or amount."
+    description = "Modifies an existing income record's label, amount or account."
 )]
 pub async fn update_income(
     State(pool): State<SqlitePool>,
@@ -119,7 +168,7 @@ pub async fn update_income(
     verify_month_not_closed(&pool, claims.sub, month_id).await?;
 
     let existing: IncomeEntry = sqlx::query_as(
-        "SELECT id, month_id, label, amount FROM income_entries WHERE id = ? AND month_id = ?",
+        "SELECT id, month_id, label, amount, account_id FROM income_entries WHERE id = ? AND month_id = ?",
     )
     .bind(income_id)
     .bind(month_id)
@@ -129,10 +178,15 @@ pub async fn update_income(
 
     let label = payload.label.unwrap_or(existing.label);
     let amount = payload.amount.unwrap_or(existing.amount);
+    let account_id = payload.account_id.unwrap_or(existing.account_id);
+    if payload.account_id.is_some() {
+        verify_account(&pool, claims.sub, account_id).await?;
+    }
 
-    sqlx::query("UPDATE income_entries SET label = ?, amount = ? WHERE id = ?")
+    sqlx::query("UPDATE income_entries SET label = ?, amount = ?, account_id = ? WHERE id = ?")
         .bind(&label)
         .bind(amount)
+        .bind(account_id)
         .bind(income_id)
         .execute(&pool)
         .await?;
@@ -142,6 +196,7 @@ pub async fn update_income(
         month_id,
         label,
         amount,
+        account_id,
     }))
 }
 
@@ -204,7 +259,7 @@ async fn verify_month_not_closed(
             .await?;
 
     match month {
-        Some((true,)) => Err(PaymeError::BadRequest("Month is closed".to_string())),
+        Some((true,)) => Err(PaymeError::MonthClosed),
         Some((false,)) => Ok(()),
         None => Err(PaymeError::NotFound),
     }
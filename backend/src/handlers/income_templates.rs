@@ -0,0 +1,176 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{IncomeTemplate, Money, Schedule};
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveIncomeTemplate {
+    #[validate(length(min = 1, max = 100))]
+    pub label: String,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
+    pub schedule: Schedule,
+    pub anchor_date: Option<NaiveDate>,
+}
+
+impl SaveIncomeTemplate {
+    fn check(&self) -> Result<(), PaymeError> {
+        self.validate()?;
+        if self.anchor_date.is_none()
+            && matches!(self.schedule, Schedule::Biweekly | Schedule::Annual)
+        {
+            return Err(PaymeError::BadRequest(
+                "Biweekly and annual schedules need an anchor_date".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/income-templates",
+    responses(
+        (status = 200, body = [IncomeTemplate]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "List recurring income",
+    description = "Retrieves the income templates that are entered into new months automatically."
+)]
+pub async fn list_income_templates(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<IncomeTemplate>>, PaymeError> {
+    let templates: Vec<IncomeTemplate> = sqlx::query_as(
+        "SELECT id, user_id, label, amount, schedule, anchor_date FROM income_templates WHERE user_id = ? ORDER BY id",
+    )
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(templates))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/income-templates",
+    request_body = SaveIncomeTemplate,
+    responses(
+        (status = 200, body = IncomeTemplate),
+        (status = 400, description = "Invalid template or missing anchor date"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Create recurring income",
+    description = "Adds an income template. Every month created afterwards gets one income entry per scheduled payday; existing months are not changed."
+)]
+pub async fn create_income_template(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveIncomeTemplate>,
+) -> Result<Json<IncomeTemplate>, PaymeError> {
+    payload.check()?;
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO income_templates (user_id, label, amount, schedule, anchor_date) VALUES (?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(claims.sub)
+    .bind(&payload.label)
+    .bind(payload.amount)
+    .bind(payload.schedule)
+    .bind(payload.anchor_date)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(IncomeTemplate {
+        id,
+        user_id: claims.sub,
+        label: payload.label,
+        amount: payload.amount,
+        schedule: payload.schedule,
+        anchor_date: payload.anchor_date,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/income-templates/{id}",
+    params(("id" = i64, Path, description = "Template ID")),
+    request_body = SaveIncomeTemplate,
+    responses(
+        (status = 200, body = IncomeTemplate),
+        (status = 400, description = "Invalid template or missing anchor date"),
+        (status = 404, description = "Template not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Update recurring income",
+    description = "Replaces an income template. Income already entered into existing months is not changed."
+)]
+pub async fn update_income_template(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveIncomeTemplate>,
+) -> Result<Json<IncomeTemplate>, PaymeError> {
+    payload.check()?;
+
+    let result = sqlx::query(
+        "UPDATE income_templates SET label = ?, amount = ?, schedule = ?, anchor_date = ? WHERE id = ? AND user_id = ?",
+    )
+    .bind(&payload.label)
+    .bind(payload.amount)
+    .bind(payload.schedule)
+    .bind(payload.anchor_date)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(IncomeTemplate {
+        id,
+        user_id: claims.sub,
+        label: payload.label,
+        amount: payload.amount,
+        schedule: payload.schedule,
+        anchor_date: payload.anchor_date,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/income-templates/{id}",
+    params(("id" = i64, Path, description = "Template ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Configuration",
+    summary = "Delete recurring income",
+    description = "Stops entering this income into new months. Income already entered is kept."
+)]
+pub async fn delete_income_template(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM income_templates WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
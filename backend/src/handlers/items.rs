@@ -1,26 +1,67 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use chrono::NaiveDate;
 use serde::Deserialize;
-use sqlx::SqlitePool;
-use utoipa::ToSchema;
+use sqlx::query::QueryAs;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
+use crate::categorization::RuleSet;
 use crate::error::PaymeError;
+use crate::handlers::accounts::verify_account;
+use crate::handlers::pagination::{
+    AmountFilter, DateFilter, ListQuery, Page, Paginated, SortDirection, DEFAULT_LIMIT,
+};
 use crate::middleware::auth::Claims;
-use crate::models::{Item, ItemWithCategory};
+use crate::models::{Item, ItemSearchResult, ItemSplit, ItemWithCategory, Money, Tag};
+
+/// One row per split, and per item without splits, with the `month_id`,
+/// `category_id` and `amount` counted towards that category.
+pub(crate) const CATEGORY_SPENDING: &str = r#"
+    SELECT i.month_id, s.category_id, s.amount
+    FROM item_splits s JOIN items i ON s.item_id = i.id
+    UNION ALL
+    SELECT i.month_id, i.category_id, i.amount
+    FROM items i
+    WHERE NOT EXISTS (SELECT 1 FROM item_splits s WHERE s.item_id = i.id)
+"#;
+
+/// Selects [`ItemSplit`] rows; append a `WHERE`.
+const SPLIT_SELECT: &str = r#"
+    SELECT s.id, s.item_id, s.category_id, bc.label AS category_label, s.amount
+    FROM item_splits s
+    JOIN budget_categories bc ON s.category_id = bc.id
+"#;
 
 #[derive(Deserialize, ToSchema, Validate)]
-pub struct CreateItem {
+pub struct SaveSplit {
     pub category_id: i64,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateItem {
+    /// Defaults to the first split's category, or is chosen by the
+    /// categorization rules.
+    pub category_id: Option<i64>,
     #[validate(length(min = 1, max = 200))]
     pub description: String,
-    #[validate(range(min = 0.0))]
-    pub amount: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
     pub spent_on: NaiveDate,
+    pub account_id: Option<i64>,
+    /// Spreads the amount over several categories; must add up to `amount`.
+    #[serde(default)]
+    #[validate(nested)]
+    pub splits: Vec<SaveSplit>,
+    #[serde(default)]
+    pub tag_ids: Vec<i64>,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
@@ -28,43 +69,230 @@ pub struct UpdateItem {
     pub category_id: Option<i64>,
     #[validate(length(min = 1, max = 200))]
     pub description: Option<String>,
-    #[validate(range(min = 0.0))]
-    pub amount: Option<f64>,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Option<Money>,
     pub spent_on: Option<NaiveDate>,
+    /// `null` unlinks the item from its account.
+    #[serde(default, deserialize_with = "crate::models::nullable")]
+    #[schema(value_type = Option<i64>)]
+    pub account_id: Option<Option<i64>>,
+    /// Replaces the splits; an empty list removes them.
+    #[validate(nested)]
+    pub splits: Option<Vec<SaveSplit>>,
+    /// Replaces the tags; an empty list removes them.
+    pub tag_ids: Option<Vec<i64>>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ItemFilter {
+    /// Only items carrying this tag.
+    pub tag: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words that must all start a word of the description.
+    pub q: Option<String>,
+    /// Items in this category, including split items with a share in it.
+    pub category_id: Option<i64>,
+    /// Items carrying this tag.
+    pub tag: Option<i64>,
+}
+
+/// `sort` values accepted by the item lists.
+const ITEM_SORTS: &[(&str, &[&str])] = &[
+    ("spent_on", &["i.spent_on"]),
+    ("amount", &["i.amount"]),
+    ("description", &["i.description COLLATE NOCASE"]),
+];
+
+/// Conditions shared by the item lists and their counts, bound by
+/// [`bind_item_filter`].
+const ITEM_FILTER: &str = r#"
+    FROM items i
+    JOIN months m ON i.month_id = m.id
+    JOIN budget_categories bc ON i.category_id = bc.id
+    WHERE m.user_id = ?
+        AND (? IS NULL OR i.month_id = ?)
+        AND (? IS NULL OR i.id IN (SELECT rowid FROM items_fts WHERE items_fts MATCH ?))
+        AND (? IS NULL OR i.category_id = ? OR i.id IN (SELECT item_id FROM item_splits WHERE category_id = ?))
+        AND (? IS NULL OR i.id IN (SELECT item_id FROM item_tags WHERE tag_id = ?))
+        AND (? IS NULL OR i.amount >= ?)
+        AND (? IS NULL OR i.amount <= ?)
+        AND (? IS NULL OR i.spent_on >= ?)
+        AND (? IS NULL OR i.spent_on <= ?)
+"#;
+
+struct ItemCriteria<'a> {
+    user_id: i64,
+    month_id: Option<i64>,
+    /// An FTS5 query, as built by [`fts_query`].
+    text: Option<String>,
+    category_id: Option<i64>,
+    tag: Option<i64>,
+    amounts: &'a AmountFilter,
+    dates: &'a DateFilter,
+}
+
+fn bind_item_filter<'q, O>(
+    query: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    criteria: &'q ItemCriteria,
+) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>> {
+    query
+        .bind(criteria.user_id)
+        .bind(criteria.month_id)
+        .bind(criteria.month_id)
+        .bind(&criteria.text)
+        .bind(&criteria.text)
+        .bind(criteria.category_id)
+        .bind(criteria.category_id)
+        .bind(criteria.category_id)
+        .bind(criteria.tag)
+        .bind(criteria.tag)
+        .bind(criteria.amounts.min_amount)
+        .bind(criteria.amounts.min_amount)
+        .bind(criteria.amounts.max_amount)
+        .bind(criteria.amounts.max_amount)
+        .bind(criteria.dates.from)
+        .bind(criteria.dates.from)
+        .bind(criteria.dates.to)
+        .bind(criteria.dates.to)
+}
+
+/// One page of matching items with their splits and tags, and the number of
+/// matches over all pages.
+async fn find_items(
+    pool: &SqlitePool,
+    criteria: &ItemCriteria<'_>,
+    order_by: &str,
+    page: Page,
+) -> Result<(Vec<ItemSearchResult>, i64), sqlx::Error> {
+    let count_sql = format!("SELECT COUNT(*) {ITEM_FILTER}");
+    let (total,): (i64,) = bind_item_filter(sqlx::query_as(&count_sql), criteria)
+        .fetch_one(pool)
+        .await?;
+
+    let sql = format!(
+        r#"
+        SELECT m.year, m.month, i.id, i.month_id, i.category_id, bc.label AS category_label,
+            i.description, i.amount, i.spent_on, i.account_id
+        {ITEM_FILTER}
+        {order_by}
+        LIMIT ? OFFSET ?
+        "#
+    );
+    let mut items: Vec<ItemSearchResult> = bind_item_filter(sqlx::query_as(&sql), criteria)
+        .bind(page.sql_limit())
+        .bind(page.offset)
+        .fetch_all(pool)
+        .await?;
+    attach_details(pool, items.iter_mut().map(|r| &mut r.item)).await?;
+
+    Ok((items, total))
+}
+
+#[utoipa::path(
+    get, path = "/api/items/search",
+    params(SearchQuery, ListQuery, AmountFilter, DateFilter),
+    responses(
+        (status = 200, body = [ItemSearchResult], headers(
+            ("X-Total-Count" = i64, description = "Number of matching items"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Items",
+    summary = "Search transactions",
+    description = "Finds items across all of the user's months by description words, category, amount, date and tag. Sorts by `spent_on` (the default, newest first), `amount` or `description`, and returns 50 results per page unless a limit is given."
+)]
+pub async fn search_items(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Query(search): Query<SearchQuery>,
+    list: ListQuery,
+    Query(amounts): Query<AmountFilter>,
+    Query(dates): Query<DateFilter>,
+) -> Result<Paginated<ItemSearchResult>, PaymeError> {
+    let page = list.page_or(DEFAULT_LIMIT)?;
+    let order_by = list.order_by(ITEM_SORTS, SortDirection::Desc, "i.id")?;
+    let text = match search.q.as_deref().map(fts_query) {
+        Some(None) => {
+            return Err(PaymeError::BadRequest(
+                "q must contain a word to search for".to_string(),
+            ))
+        }
+        Some(text) => text,
+        None => None,
+    };
+
+    let criteria = ItemCriteria {
+        user_id: claims.sub,
+        month_id: None,
+        text,
+        category_id: search.category_id,
+        tag: search.tag,
+        amounts: &amounts,
+        dates: &dates,
+    };
+    let (rows, total) = find_items(&pool, &criteria, &order_by, page).await?;
+
+    Ok(list.paginate(rows, total, page))
+}
+
+/// Turns free text into an FTS5 query matching every word as a prefix, with
+/// FTS5 syntax in the text taken literally. `None` when no word remains.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
 }
 
 #[utoipa::path(
     get, path = "/api/months/{id}/items",
-    params(("id" = i64, Path)),
+    params(("id" = i64, Path), ItemFilter, ListQuery, AmountFilter, DateFilter),
     responses(
-        (status = 200, body = [ItemWithCategory]),
+        (status = 200, body = [ItemWithCategory], headers(
+            ("X-Total-Count" = i64, description = "Number of matching items"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Items",
     summary = "List transactions",
-    description = "Retrieves all itemized spending for the month, including category labels."
+    description = "Retrieves the month's itemized spending, including category labels and tags, optionally only the items carrying a tag. Sorts by `spent_on` (the default, newest first), `amount` or `description`."
 )]
 pub async fn list_items(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Path(month_id): Path<i64>,
-) -> Result<Json<Vec<ItemWithCategory>>, PaymeError> {
+    Query(filter): Query<ItemFilter>,
+    list: ListQuery,
+    Query(amounts): Query<AmountFilter>,
+    Query(dates): Query<DateFilter>,
+) -> Result<Paginated<ItemWithCategory>, PaymeError> {
     verify_month_access(&pool, claims.sub, month_id).await?;
+    let page = list.page_or(DEFAULT_LIMIT)?;
+    let order_by = list.order_by(ITEM_SORTS, SortDirection::Desc, "i.id")?;
 
-    let items: Vec<ItemWithCategory> = sqlx::query_as(
-        r#"
-        SELECT i.id, i.month_id, i.category_id, bc.label as category_label, i.description, i.amount, i.spent_on
-        FROM items i
-        JOIN budget_categories bc ON i.category_id = bc.id
-        WHERE i.month_id = ?
-        ORDER BY i.spent_on DESC
-        "#,
-    )
-    .bind(month_id)
-    .fetch_all(&pool)
-    .await?;
+    let criteria = ItemCriteria {
+        user_id: claims.sub,
+        month_id: Some(month_id),
+        text: None,
+        category_id: None,
+        tag: filter.tag,
+        amounts: &amounts,
+        dates: &dates,
+    };
+    let (rows, total) = find_items(&pool, &criteria, &order_by, page).await?;
 
-    Ok(Json(items))
+    Ok(list.paginate(rows.into_iter().map(|r| r.item).collect(), total, page))
 }
 
 #[utoipa::path(
@@ -77,7 +305,7 @@ pub async fn list_items(
     ),
     tag = "Items",
     summary = "Record transaction",
-    description = "Logs a new expense against a specific budget category."
+    description = "Logs a new expense against a budget category, or split across several. When no category is given, the first split's category is used, or else the first matching categorization rule picks one."
 )]
 pub async fn create_item(
     State(pool): State<SqlitePool>,
@@ -88,32 +316,48 @@ pub async fn create_item(
     payload.validate()?;
     verify_month_not_closed(&pool, claims.sub, month_id).await?;
 
-    let _category: (i64,) =
-        sqlx::query_as("SELECT id FROM budget_categories WHERE id = ? AND user_id = ?")
-            .bind(payload.category_id)
-            .bind(claims.sub)
-            .fetch_optional(&pool)
+    let category_id = match (payload.category_id, payload.splits.first()) {
+        (Some(category_id), _) => category_id,
+        (None, Some(split)) => split.category_id,
+        (None, None) => RuleSet::load(&pool, claims.sub)
             .await?
-            .ok_or(PaymeError::BadRequest("Invalid category".to_string()))?;
+            .category_for(&payload.description, payload.amount, payload.spent_on)
+            .ok_or(PaymeError::BadRequest(
+                "No category given and no rule matched".to_string(),
+            ))?,
+    };
 
+    verify_category(&pool, claims.sub, category_id).await?;
+    verify_account(&pool, claims.sub, payload.account_id).await?;
+    verify_splits(&pool, claims.sub, payload.amount, &payload.splits).await?;
+    verify_tags(&pool, claims.sub, &payload.tag_ids).await?;
+
+    let mut tx = pool.begin().await?;
     let id: i64 = sqlx::query_scalar(
-        "INSERT INTO items (month_id, category_id, description, amount, spent_on) VALUES (?, ?, ?, ?, ?) RETURNING id",
+        "INSERT INTO items (month_id, category_id, description, amount, spent_on, account_id) VALUES (?, ?, ?, ?, ?, ?) RETURNING id",
     )
     .bind(month_id)
-    .bind(payload.category_id)
+    .bind(category_id)
     .bind(&payload.description)
     .bind(payload.amount)
     .bind(payload.spent_on)
-    .fetch_one(&pool)
+    .bind(payload.account_id)
+    .fetch_one(&mut *tx)
     .await?;
+    replace_splits(&mut tx, id, &payload.splits).await?;
+    replace_tags(&mut tx, id, &payload.tag_ids).await?;
+    tx.commit().await?;
 
     Ok(Json(Item {
         id,
         month_id,
-        category_id: payload.category_id,
+        category_id,
         description: payload.description,
         amount: payload.amount,
         spent_on: payload.spent_on,
+        account_id: payload.account_id,
+        splits: item_splits(&pool, id).await?,
+        tags: item_tags(&pool, id).await?,
     }))
 }
 
@@ -132,7 +376,7 @@ pub async fn create_item(
     ),
     tag = "Items",
     summary = "Update transaction details",
-    description = "Updates an existing transaction. Supports partial updates for category, description, amount, or date."
+    description = "Updates an existing transaction. Supports partial updates for category, description, amount, date, account, splits, or tags. Splits must still add up to the amount."
 )]
 pub async fn update_item(
     State(pool): State<SqlitePool>,
@@ -144,7 +388,7 @@ pub async fn update_item(
     verify_month_not_closed(&pool, claims.sub, month_id).await?;
 
     let existing: Item = sqlx::query_as(
-        "SELECT id, month_id, category_id, description, amount, spent_on FROM items WHERE id = ? AND month_id = ?",
+        "SELECT id, month_id, category_id, description, amount, spent_on, account_id FROM items WHERE id = ? AND month_id = ?",
     )
     .bind(item_id)
     .bind(month_id)
@@ -156,27 +400,51 @@ pub async fn update_item(
     let description = payload.description.unwrap_or(existing.description);
     let amount = payload.amount.unwrap_or(existing.amount);
     let spent_on = payload.spent_on.unwrap_or(existing.spent_on);
+    let account_id = payload.account_id.unwrap_or(existing.account_id);
 
     if payload.category_id.is_some() {
-        let _category: (i64,) =
-            sqlx::query_as("SELECT id FROM budget_categories WHERE id = ? AND user_id = ?")
-                .bind(category_id)
-                .bind(claims.sub)
-                .fetch_optional(&pool)
-                .await?
-                .ok_or(PaymeError::BadRequest("Invalid category".to_string()))?;
+        verify_category(&pool, claims.sub, category_id).await?;
+    }
+    if payload.account_id.is_some() {
+        verify_account(&pool, claims.sub, account_id).await?;
+    }
+    match &payload.splits {
+        Some(splits) => verify_splits(&pool, claims.sub, amount, splits).await?,
+        None if payload.amount.is_some() => {
+            let split_total: Option<Money> =
+                sqlx::query_scalar("SELECT SUM(amount) FROM item_splits WHERE item_id = ?")
+                    .bind(item_id)
+                    .fetch_one(&pool)
+                    .await?;
+            if split_total.is_some_and(|total| total != amount) {
+                return Err(splits_mismatch());
+            }
+        }
+        None => {}
+    }
+    if let Some(tag_ids) = &payload.tag_ids {
+        verify_tags(&pool, claims.sub, tag_ids).await?;
     }
 
+    let mut tx = pool.begin().await?;
     sqlx::query(
-        "UPDATE items SET category_id = ?, description = ?, amount = ?, spent_on = ? WHERE id = ?",
+        "UPDATE items SET category_id = ?, description = ?, amount = ?, spent_on = ?, account_id = ? WHERE id = ?",
     )
     .bind(category_id)
     .bind(&description)
     .bind(amount)
     .bind(spent_on)
+    .bind(account_id)
     .bind(item_id)
-    .execute(&pool)
+    .execute(&mut *tx)
     .await?;
+    if let Some(splits) = &payload.splits {
+        replace_splits(&mut tx, item_id, splits).await?;
+    }
+    if let Some(tag_ids) = &payload.tag_ids {
+        replace_tags(&mut tx, item_id, tag_ids).await?;
+    }
+    tx.commit().await?;
 
     Ok(Json(Item {
         id: item_id,
@@ -185,6 +453,9 @@ pub async fn update_item(
         description,
         amount,
         spent_on,
+        account_id,
+        splits: item_splits(&pool, item_id).await?,
+        tags: item_tags(&pool, item_id).await?,
     }))
 }
 
@@ -219,7 +490,161 @@ pub async fn delete_item(
     Ok(StatusCode::NO_CONTENT)
 }
 
-async fn verify_month_access(
+/// Fills in the splits and tags of the given items.
+pub(crate) async fn attach_details<'a>(
+    pool: &SqlitePool,
+    items: impl IntoIterator<Item = &'a mut ItemWithCategory>,
+) -> Result<(), sqlx::Error> {
+    let items: Vec<&mut ItemWithCategory> = items.into_iter().collect();
+    let ids = serde_json::to_string(&items.iter().map(|i| i.id).collect::<Vec<_>>())
+        .expect("ids serialize");
+
+    let splits: Vec<ItemSplit> = sqlx::query_as(&format!(
+        "{SPLIT_SELECT} WHERE s.item_id IN (SELECT value FROM json_each(?)) ORDER BY s.id"
+    ))
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+    let tags: Vec<(i64, i64, i64, String)> = sqlx::query_as(
+        r#"
+        SELECT it.item_id, t.id, t.user_id, t.name
+        FROM item_tags it
+        JOIN tags t ON it.tag_id = t.id
+        WHERE it.item_id IN (SELECT value FROM json_each(?))
+        ORDER BY t.name
+        "#,
+    )
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    for item in items {
+        item.splits = splits
+            .iter()
+            .filter(|s| s.item_id == item.id)
+            .cloned()
+            .collect();
+        item.tags = tags
+            .iter()
+            .filter(|(item_id, ..)| *item_id == item.id)
+            .map(|(_, id, user_id, name)| Tag {
+                id: *id,
+                user_id: *user_id,
+                name: name.clone(),
+            })
+            .collect();
+    }
+    Ok(())
+}
+
+async fn item_splits(pool: &SqlitePool, item_id: i64) -> Result<Vec<ItemSplit>, sqlx::Error> {
+    sqlx::query_as(&format!("{SPLIT_SELECT} WHERE s.item_id = ? ORDER BY s.id"))
+        .bind(item_id)
+        .fetch_all(pool)
+        .await
+}
+
+async fn replace_splits(
+    conn: &mut SqliteConnection,
+    item_id: i64,
+    splits: &[SaveSplit],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM item_splits WHERE item_id = ?")
+        .bind(item_id)
+        .execute(&mut *conn)
+        .await?;
+    for split in splits {
+        sqlx::query("INSERT INTO item_splits (item_id, category_id, amount) VALUES (?, ?, ?)")
+            .bind(item_id)
+            .bind(split.category_id)
+            .bind(split.amount)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Checks that splits use the user's categories and add up to `amount`.
+async fn verify_splits(
+    pool: &SqlitePool,
+    user_id: i64,
+    amount: Money,
+    splits: &[SaveSplit],
+) -> Result<(), PaymeError> {
+    if splits.is_empty() {
+        return Ok(());
+    }
+    let total =
+        Money::checked_sum(splits.iter().map(|s| s.amount)).ok_or(PaymeError::AmountOutOfRange)?;
+    if total != amount {
+        return Err(splits_mismatch());
+    }
+    for split in splits {
+        verify_category(pool, user_id, split.category_id).await?;
+    }
+    Ok(())
+}
+
+async fn item_tags(pool: &SqlitePool, item_id: i64) -> Result<Vec<Tag>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT t.id, t.user_id, t.name FROM tags t JOIN item_tags it ON it.tag_id = t.id WHERE it.item_id = ? ORDER BY t.name",
+    )
+    .bind(item_id)
+    .fetch_all(pool)
+    .await
+}
+
+async fn replace_tags(
+    conn: &mut SqliteConnection,
+    item_id: i64,
+    tag_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM item_tags WHERE item_id = ?")
+        .bind(item_id)
+        .execute(&mut *conn)
+        .await?;
+    for tag_id in tag_ids {
+        sqlx::query("INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?, ?)")
+            .bind(item_id)
+            .bind(tag_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn verify_tags(pool: &SqlitePool, user_id: i64, tag_ids: &[i64]) -> Result<(), PaymeError> {
+    for tag_id in tag_ids {
+        let _tag: (i64,) = sqlx::query_as("SELECT id FROM tags WHERE id = ? AND user_id = ?")
+            .bind(tag_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(PaymeError::InvalidReference("tag"))?;
+    }
+    Ok(())
+}
+
+fn splits_mismatch() -> PaymeError {
+    PaymeError::BadRequest("Splits must add up to the item amount".to_string())
+}
+
+async fn verify_category(
+    pool: &SqlitePool,
+    user_id: i64,
+    category_id: i64,
+) -> Result<(), PaymeError> {
+    let _category: (i64,) =
+        sqlx::query_as("SELECT id FROM budget_categories WHERE id = ? AND user_id = ?")
+            .bind(category_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(PaymeError::InvalidReference("category"))?;
+    Ok(())
+}
+
+pub(crate) async fn verify_month_access(
     pool: &SqlitePool,
     user_id: i64,
     month_id: i64,
@@ -234,21 +659,23 @@ async fn verify_month_access(
     exists.map(|_| ()).ok_or(PaymeError::NotFound)
 }
 
-async fn verify_month_not_closed(
+/// Fails unless the month is the user's and open, returning its year and
+/// month.
+pub(crate) async fn verify_month_not_closed(
     pool: &SqlitePool,
     user_id: i64,
     month_id: i64,
-) -> Result<(), PaymeError> {
-    let month: Option<(bool,)> =
-        sqlx::query_as("SELECT is_closed FROM months WHERE id = ? AND user_id = ?")
+) -> Result<(i32, i32), PaymeError> {
+    let month: Option<(bool, i32, i32)> =
+        sqlx::query_as("SELECT is_closed, year, month FROM months WHERE id = ? AND user_id = ?")
             .bind(month_id)
             .bind(user_id)
             .fetch_optional(pool)
             .await?;
 
     match month {
-        Some((true,)) => Err(PaymeError::BadRequest("Month is closed".to_string())),
-        Some((false,)) => Ok(()),
+        Some((true, _, _)) => Err(PaymeError::MonthClosed),
+        Some((false, year, month)) => Ok((year, month)),
         None => Err(PaymeError::NotFound),
     }
 }
@@ -1,10 +1,23 @@
+pub mod accounts;
+pub mod api_tokens;
+pub mod attachments;
 pub mod auth;
 pub mod budget;
 pub mod export;
 pub mod fixed_expenses;
+pub mod goals;
 pub mod health;
+pub mod import;
 pub mod income;
+pub mod income_templates;
 pub mod items;
 pub mod months;
+pub mod pagination;
+pub mod recurring;
+pub mod rules;
 pub mod savings;
+pub mod sessions;
 pub mod stats;
+pub mod tags;
+pub mod transfers;
+pub mod two_factor;
@@ -1,40 +1,107 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
 use chrono::{Datelike, Utc};
-use sqlx::SqlitePool;
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use utoipa::ToSchema;
+use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::goals::list_goal_progress;
+use crate::handlers::items::{attach_details, CATEGORY_SPENDING};
+use crate::handlers::pagination::{DateFilter, ListQuery, Paginated, SortDirection, DEFAULT_LIMIT};
+use crate::handlers::recurring;
+use crate::handlers::transfers::TRANSFERS_IN_MONTH;
 use crate::middleware::auth::Claims;
 use crate::models::{
-    FixedExpense, IncomeEntry, ItemWithCategory, Month, MonthSummary, MonthlyBudgetWithCategory,
+    Currency, IncomeEntry, IncomeTemplate, ItemWithCategory, Money, Month, MonthSnapshot,
+    MonthSummary, MonthlyBudgetWithCategory, MonthlyFixedExpense, RolloverMode,
+    TransferWithAccounts,
 };
 use crate::pdf;
+use crate::schedule;
+
+/// The longest range `POST /api/months/bulk` creates in one request.
+const MAX_BULK_MONTHS: i32 = 120;
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema, Validate)]
+pub struct CreateMonth {
+    #[validate(range(min = 1900, max = 9999))]
+    pub year: i32,
+    #[validate(range(min = 1, max = 12))]
+    pub month: i32,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateMonthRange {
+    #[validate(nested)]
+    pub from: CreateMonth,
+    /// Inclusive.
+    #[validate(nested)]
+    pub to: CreateMonth,
+}
+
+impl CreateMonth {
+    fn index(self) -> i32 {
+        self.year * 12 + self.month - 1
+    }
+}
 
 #[utoipa::path(
     get,
     path = "/api/months",
+    params(ListQuery, DateFilter),
     responses(
-        (status = 200, description = "List all months for the user", body = [Month]),
+        (status = 200, description = "List all months for the user", body = [Month], headers(
+            ("X-Total-Count" = i64, description = "Number of matching rows"),
+            ("Link" = String, description = "URL of the next page, if any, with rel=\"next\"")
+        )),
+        (status = 400, description = "Invalid filter, sort or page"),
         (status = 500, description = "Internal server error")
     ),
     tag = "Months",
     summary = "List all budget months",
-    description = "Retrieves a history of all months created by the user, ordered by date."
+    description = "Retrieves a history of the months created by the user, newest first, optionally only those overlapping a date range. The only `sort` is `date`."
 )]
 pub async fn list_months(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
-) -> Result<Json<Vec<Month>>, PaymeError> {
-    let months: Vec<Month> = sqlx::query_as(
-        "SELECT id, user_id, year, month, is_closed, closed_at FROM months WHERE user_id = ? ORDER BY year DESC, month DESC",
-    )
+    list: ListQuery,
+    Query(dates): Query<DateFilter>,
+) -> Result<Paginated<Month>, PaymeError> {
+    let page = list.page_or(DEFAULT_LIMIT)?;
+    let order_by = list.order_by(&[("date", &["year", "month"])], SortDirection::Desc, "id")?;
+    let filter = r#"
+        FROM months
+        WHERE user_id = ?
+            AND (? IS NULL OR date(printf('%04d-%02d-01', year, month), '+1 month', '-1 day') >= ?)
+            AND (? IS NULL OR printf('%04d-%02d-01', year, month) <= ?)
+    "#;
+
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) {filter}"))
+        .bind(claims.sub)
+        .bind(dates.from)
+        .bind(dates.from)
+        .bind(dates.to)
+        .bind(dates.to)
+        .fetch_one(&pool)
+        .await?;
+    let months: Vec<Month> = sqlx::query_as(&format!(
+        "SELECT id, user_id, year, month, is_closed, closed_at {filter} {order_by} LIMIT ? OFFSET ?"
+    ))
     .bind(claims.sub)
+    .bind(dates.from)
+    .bind(dates.from)
+    .bind(dates.to)
+    .bind(dates.to)
+    .bind(page.sql_limit())
+    .bind(page.offset)
     .fetch_all(&pool)
     .await?;
 
-    Ok(Json(months))
+    Ok(list.paginate(months, total, page))
 }
 
 #[utoipa::path(
@@ -68,33 +135,9 @@ pub async fn get_or_create_current_month(
     let month_record = match existing {
         Some(m) => m,
         None => {
-            let id: i64 = sqlx::query_scalar(
-                "INSERT INTO months (user_id, year, month) VALUES (?, ?, ?) RETURNING id",
-            )
-            .bind(claims.sub)
-            .bind(year)
-            .bind(month)
-            .fetch_one(&pool)
-            .await?;
-
-            let categories: Vec<(i64, f64)> = sqlx::query_as(
-                "SELECT id, default_amount FROM budget_categories WHERE user_id = ?",
-            )
-            .bind(claims.sub)
-            .fetch_all(&pool)
-            .await?;
-
-            for (cat_id, default_amount) in categories {
-                sqlx::query(
-                    "INSERT INTO monthly_budgets (month_id, category_id, allocated_amount) VALUES (?, ?, ?)",
-                )
-                .bind(id)
-                .bind(cat_id)
-                .bind(default_amount)
-                .execute(&pool)
-                .await
-                .ok();
-            }
+            let mut tx = pool.begin().await?;
+            let id = insert_month(&mut tx, claims.sub, year, month).await?;
+            tx.commit().await?;
 
             Month {
                 id,
@@ -107,7 +150,202 @@ pub async fn get_or_create_current_month(
         }
     };
 
-    get_month_summary(&pool, claims.sub, month_record.id).await
+    get_month_summary(&pool, month_record.id).await
+}
+
+/// Creates a month for `user_id`, copying in the user's fixed expenses,
+/// entering the income its templates schedule for the month, and seeding its
+/// budgets from the default amounts of the user's categories plus whatever
+/// their rollover mode carries over from the previous calendar month.
+///
+/// This writes many rows, so callers run it inside a transaction.
+pub(crate) async fn insert_month(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    year: i32,
+    month: i32,
+) -> Result<i64, sqlx::Error> {
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO months (user_id, year, month) VALUES (?, ?, ?) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(year)
+    .bind(month)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO monthly_fixed_expenses (month_id, fixed_expense_id, label, amount)
+        SELECT ?, id, label, amount FROM fixed_expenses WHERE user_id = ? ORDER BY id
+        "#,
+    )
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?;
+
+    let templates: Vec<IncomeTemplate> = sqlx::query_as(
+        "SELECT id, user_id, label, amount, schedule, anchor_date FROM income_templates WHERE user_id = ? ORDER BY id",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    for template in templates {
+        for _ in schedule::occurrences(template.schedule, template.anchor_date, year, month) {
+            sqlx::query("INSERT INTO income_entries (month_id, label, amount) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(&template.label)
+                .bind(template.amount)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    recurring::materialize_month(conn, user_id, id, year, month).await?;
+
+    let (previous_year, previous_month) = if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    };
+    let categories: Vec<(i64, Money, RolloverMode, Option<Money>, Money)> =
+        sqlx::query_as(&format!(
+            r#"
+        SELECT bc.id, bc.default_amount, bc.rollover_mode, mb.allocated_amount,
+            (SELECT COALESCE(SUM(s.amount), 0) FROM ({CATEGORY_SPENDING}) s
+             WHERE s.month_id = mb.month_id AND s.category_id = bc.id)
+        FROM budget_categories bc
+        LEFT JOIN monthly_budgets mb ON mb.category_id = bc.id
+            AND mb.month_id = (SELECT id FROM months WHERE user_id = ? AND year = ? AND month = ?)
+        WHERE bc.user_id = ?
+        "#
+        ))
+        .bind(user_id)
+        .bind(previous_year)
+        .bind(previous_month)
+        .bind(user_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (cat_id, default_amount, rollover_mode, previous_allocated, previous_spent) in categories {
+        let carried = previous_allocated.map_or(Money::ZERO, |allocated| {
+            rollover_mode.carry(allocated - previous_spent)
+        });
+        sqlx::query(
+            "INSERT INTO monthly_budgets (month_id, category_id, allocated_amount, carried_amount) VALUES (?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(cat_id)
+        .bind(default_amount + carried)
+        .bind(carried)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(id)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/months",
+    request_body = CreateMonth,
+    responses(
+        (status = 200, body = Month),
+        (status = 400, description = "Invalid year or month"),
+        (status = 409, description = "Month already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Months",
+    summary = "Create a month",
+    description = "Creates any past or future month, seeding its budgets the same way as the current month."
+)]
+pub async fn create_month(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateMonth>,
+) -> Result<Json<Month>, PaymeError> {
+    payload.validate()?;
+
+    let mut tx = pool.begin().await?;
+    let id = match insert_month(&mut tx, claims.sub, payload.year, payload.month).await {
+        Ok(id) => id,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(PaymeError::Conflict(format!(
+                "{}/{} already exists",
+                payload.month, payload.year
+            )))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    tx.commit().await?;
+
+    Ok(Json(Month {
+        id,
+        user_id: claims.sub,
+        year: payload.year,
+        month: payload.month,
+        is_closed: false,
+        closed_at: None,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/months/bulk",
+    request_body = CreateMonthRange,
+    responses(
+        (status = 200, description = "The months that were created, oldest first", body = [Month]),
+        (status = 400, description = "Invalid or too long range"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Months",
+    summary = "Create a range of months",
+    description = "Creates every month from `from` to `to` inclusive, oldest first so that budget rollover flows through the range. Months that already exist are left untouched."
+)]
+pub async fn create_months(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateMonthRange>,
+) -> Result<Json<Vec<Month>>, PaymeError> {
+    payload.validate()?;
+
+    let (from, to) = (payload.from.index(), payload.to.index());
+    if from > to {
+        return Err(PaymeError::BadRequest("from is after to".to_string()));
+    }
+    if to - from >= MAX_BULK_MONTHS {
+        return Err(PaymeError::BadRequest(format!(
+            "At most {MAX_BULK_MONTHS} months can be created at once"
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let existing: Vec<(i32, i32)> =
+        sqlx::query_as("SELECT year, month FROM months WHERE user_id = ?")
+            .bind(claims.sub)
+            .fetch_all(&mut *tx)
+            .await?;
+
+    let mut created = Vec::new();
+    for index in from..=to {
+        let (year, month) = (index / 12, index % 12 + 1);
+        if existing.contains(&(year, month)) {
+            continue;
+        }
+        let id = insert_month(&mut tx, claims.sub, year, month).await?;
+        created.push(Month {
+            id,
+            user_id: claims.sub,
+            year,
+            month,
+            is_closed: false,
+            closed_at: None,
+        });
+    }
+    tx.commit().await?;
+
+    Ok(Json(created))
 }
 
 #[utoipa::path(
@@ -138,12 +376,15 @@ pub async fn get_month(
     .await?
     .ok_or(PaymeError::NotFound)?;
 
-    get_month_summary(&pool, claims.sub, month.id).await
+    get_month_summary(&pool, month.id).await
+}
+
+fn total(amounts: impl IntoIterator<Item = Money>) -> Result<Money, PaymeError> {
+    Money::checked_sum(amounts).ok_or(PaymeError::AmountOutOfRange)
 }
 
 async fn get_month_summary(
     pool: &SqlitePool,
-    user_id: i64,
     month_id: i64,
 ) -> Result<Json<MonthSummary>, PaymeError> {
     let month: Month = sqlx::query_as(
@@ -153,22 +394,24 @@ async fn get_month_summary(
     .fetch_one(pool)
     .await?;
 
-    let income_entries: Vec<IncomeEntry> =
-        sqlx::query_as("SELECT id, month_id, label, amount FROM income_entries WHERE month_id = ?")
-            .bind(month_id)
-            .fetch_all(pool)
-            .await?;
+    let income_entries: Vec<IncomeEntry> = sqlx::query_as(
+        "SELECT id, month_id, label, amount, account_id FROM income_entries WHERE month_id = ?",
+    )
+    .bind(month_id)
+    .fetch_all(pool)
+    .await?;
 
-    let fixed_expenses: Vec<FixedExpense> =
-        sqlx::query_as("SELECT id, user_id, label, amount FROM fixed_expenses WHERE user_id = ?")
-            .bind(user_id)
-            .fetch_all(pool)
-            .await?;
+    let fixed_expenses: Vec<MonthlyFixedExpense> = sqlx::query_as(
+        "SELECT id, month_id, fixed_expense_id, label, amount FROM monthly_fixed_expenses WHERE month_id = ? ORDER BY id",
+    )
+    .bind(month_id)
+    .fetch_all(pool)
+    .await?;
 
     let budgets: Vec<MonthlyBudgetWithCategory> =
-        sqlx::query_as::<_, (i64, i64, i64, String, f64)>(
+        sqlx::query_as::<_, (i64, i64, i64, String, Money, Money)>(
             r#"
-        SELECT mb.id, mb.month_id, mb.category_id, bc.label, mb.allocated_amount
+        SELECT mb.id, mb.month_id, mb.category_id, bc.label, mb.allocated_amount, mb.carried_amount
         FROM monthly_budgets mb
         JOIN budget_categories bc ON mb.category_id = bc.id
         WHERE mb.month_id = ?
@@ -179,22 +422,23 @@ async fn get_month_summary(
         .await?
         .into_iter()
         .map(
-            |(id, month_id, category_id, category_label, allocated_amount)| {
+            |(id, month_id, category_id, category_label, allocated_amount, carried_amount)| {
                 MonthlyBudgetWithCategory {
                     id,
                     month_id,
                     category_id,
                     category_label,
                     allocated_amount,
-                    spent_amount: 0.0,
+                    carried_amount,
+                    spent_amount: Money::ZERO,
                 }
             },
         )
         .collect();
 
-    let items: Vec<ItemWithCategory> = sqlx::query_as(
+    let mut items: Vec<ItemWithCategory> = sqlx::query_as(
         r#"
-        SELECT i.id, i.month_id, i.category_id, bc.label as category_label, i.description, i.amount, i.spent_on
+        SELECT i.id, i.month_id, i.category_id, bc.label as category_label, i.description, i.amount, i.spent_on, i.account_id
         FROM items i
         JOIN budget_categories bc ON i.category_id = bc.id
         WHERE i.month_id = ?
@@ -204,35 +448,57 @@ async fn get_month_summary(
     .bind(month_id)
     .fetch_all(pool)
     .await?;
+    attach_details(pool, &mut items).await?;
 
     let budgets: Vec<MonthlyBudgetWithCategory> = budgets
         .into_iter()
         .map(|mut b| {
-            b.spent_amount = items
-                .iter()
-                .filter(|i| i.category_id == b.category_id)
-                .map(|i| i.amount)
-                .sum();
-            b
+            b.spent_amount = total(
+                items
+                    .iter()
+                    .flat_map(|i| i.category_amounts())
+                    .filter(|(category_id, _)| *category_id == b.category_id)
+                    .map(|(_, amount)| amount),
+            )?;
+            Ok(b)
         })
-        .collect();
+        .collect::<Result<_, PaymeError>>()?;
 
-    let total_income: f64 = income_entries.iter().map(|i| i.amount).sum();
-    let total_fixed: f64 = fixed_expenses.iter().map(|e| e.amount).sum();
-    let total_budgeted: f64 = budgets.iter().map(|b| b.allocated_amount).sum();
-    let total_spent: f64 = items.iter().map(|i| i.amount).sum();
-    let remaining = total_income - total_fixed - total_spent;
+    let total_income = total(income_entries.iter().map(|i| i.amount))?;
+    let total_fixed = total(fixed_expenses.iter().map(|e| e.amount))?;
+    let total_budgeted = total(budgets.iter().map(|b| b.allocated_amount))?;
+    let transfers: Vec<TransferWithAccounts> = sqlx::query_as(TRANSFERS_IN_MONTH)
+        .bind(month_id)
+        .fetch_all(pool)
+        .await?;
+
+    let goals = list_goal_progress(pool, month.user_id).await?;
+    let currency: Currency = sqlx::query_scalar("SELECT currency FROM users WHERE id = ?")
+        .bind(month.user_id)
+        .fetch_one(pool)
+        .await?;
+
+    let total_spent = total(items.iter().map(|i| i.amount))?;
+    let total_transferred = total(transfers.iter().map(|t| t.amount))?;
+    let remaining = total_income
+        .checked_sub(total_fixed)
+        .and_then(|m| m.checked_sub(total_spent))
+        .ok_or(PaymeError::AmountOutOfRange)?;
 
     Ok(Json(MonthSummary {
         month,
+        currency,
         income_entries,
         fixed_expenses,
         budgets,
         items,
+        transfers,
+        goals,
         total_income,
         total_fixed,
         total_budgeted,
         total_spent,
+        total_transferred,
         remaining,
     }))
 }
@@ -251,7 +517,7 @@ async fn get_month_summary(
     ),
     tag = "Months",
     summary = "Close month and generate report",
-    description = "Finalizes the month, prevents further edits, and generates a PDF snapshot for long-term storage."
+    description = "Finalizes the month, prevents further edits, and generates a PDF snapshot for long-term storage. Closing a reopened month adds a new snapshot version."
 )]
 pub async fn close_month(
     State(pool): State<SqlitePool>,
@@ -273,21 +539,38 @@ pub async fn close_month(
         ));
     }
 
-    let summary = get_month_summary(&pool, claims.sub, month_id).await?.0;
+    let summary = get_month_summary(&pool, month_id).await?.0;
     let pdf_data = pdf::generate_pdf(&summary).map_err(|e| PaymeError::Internal(e.to_string()))?;
 
-    sqlx::query("INSERT INTO monthly_snapshots (month_id, pdf_data) VALUES (?, ?)")
-        .bind(month_id)
-        .bind(&pdf_data)
-        .execute(&pool)
-        .await?;
-
+    // The snapshot and the closed flag are written together, and only by
+    // the request that actually closes the month.
+    let mut tx = pool.begin().await?;
     let now = Utc::now();
-    sqlx::query("UPDATE months SET is_closed = 1, closed_at = ? WHERE id = ?")
-        .bind(now)
-        .bind(month_id)
-        .execute(&pool)
-        .await?;
+    let closed = sqlx::query(
+        "UPDATE months SET is_closed = 1, closed_at = ? WHERE id = ? AND is_closed = 0",
+    )
+    .bind(now)
+    .bind(month_id)
+    .execute(&mut *tx)
+    .await?;
+    if closed.rows_affected() == 0 {
+        return Err(PaymeError::BadRequest(
+            "Month is already closed".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO monthly_snapshots (month_id, version, pdf_data)
+        SELECT ?, COALESCE(MAX(version), 0) + 1, ? FROM monthly_snapshots WHERE month_id = ?
+        "#,
+    )
+    .bind(month_id)
+    .bind(&pdf_data)
+    .bind(month_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
 
     let updated: Month = sqlx::query_as(
         "SELECT id, user_id, year, month, is_closed, closed_at FROM months WHERE id = ?",
@@ -299,6 +582,52 @@ pub async fn close_month(
     Ok(Json(updated))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/months/{id}/reopen",
+    params(
+        ("id" = i64, Path, description = "Month ID")
+    ),
+    responses(
+        (status = 200, description = "Month reopened for editing", body = Month),
+        (status = 400, description = "Month is not closed"),
+        (status = 404, description = "Month not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Months",
+    summary = "Reopen a closed month",
+    description = "Allows edits to a closed month again. Its PDF snapshots are kept, and closing it again adds a new version."
+)]
+pub async fn reopen_month(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(month_id): Path<i64>,
+) -> Result<Json<Month>, PaymeError> {
+    let month: Month = sqlx::query_as(
+        "SELECT id, user_id, year, month, is_closed, closed_at FROM months WHERE id = ? AND user_id = ?",
+    )
+    .bind(month_id)
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    if !month.is_closed {
+        return Err(PaymeError::BadRequest("Month is not closed".to_string()));
+    }
+
+    sqlx::query("UPDATE months SET is_closed = 0, closed_at = NULL WHERE id = ?")
+        .bind(month_id)
+        .execute(&pool)
+        .await?;
+
+    Ok(Json(Month {
+        is_closed: false,
+        closed_at: None,
+        ..month
+    }))
+}
+
 #[utoipa::path(
     get,
     path = "/api/months/{id}/pdf",
@@ -311,7 +640,7 @@ pub async fn close_month(
     ),
     tag = "Months",
     summary = "Download month PDF",
-    description = "Retrieves the binary PDF data for a closed month's financial report."
+    description = "Retrieves the latest PDF snapshot of a month's financial report."
 )]
 pub async fn get_month_pdf(
     State(pool): State<SqlitePool>,
@@ -327,12 +656,13 @@ pub async fn get_month_pdf(
     .await?
     .ok_or(PaymeError::NotFound)?;
 
-    let snapshot: (Vec<u8>,) =
-        sqlx::query_as("SELECT pdf_data FROM monthly_snapshots WHERE month_id = ?")
-            .bind(month_id)
-            .fetch_optional(&pool)
-            .await?
-            .ok_or(PaymeError::NotFound)?;
+    let snapshot: (Vec<u8>,) = sqlx::query_as(
+        "SELECT pdf_data FROM monthly_snapshots WHERE month_id = ? ORDER BY version DESC LIMIT 1",
+    )
+    .bind(month_id)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
 
     Ok((
         [
@@ -342,3 +672,87 @@ pub async fn get_month_pdf(
         snapshot.0,
     ))
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/months/{id}/snapshots",
+    params(
+        ("id" = i64, Path, description = "Month ID")
+    ),
+    responses(
+        (status = 200, description = "Every PDF snapshot of the month, oldest first", body = [MonthSnapshot]),
+        (status = 404, description = "Month not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Months",
+    summary = "List month snapshots",
+    description = "Lists the PDF snapshot taken each time the month was closed."
+)]
+pub async fn list_month_snapshots(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(month_id): Path<i64>,
+) -> Result<Json<Vec<MonthSnapshot>>, PaymeError> {
+    let _month: (i64,) = sqlx::query_as("SELECT id FROM months WHERE id = ? AND user_id = ?")
+        .bind(month_id)
+        .bind(claims.sub)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(PaymeError::NotFound)?;
+
+    let snapshots: Vec<MonthSnapshot> = sqlx::query_as(
+        "SELECT month_id, version, created_at FROM monthly_snapshots WHERE month_id = ? ORDER BY version",
+    )
+    .bind(month_id)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(snapshots))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/months/{id}/snapshots/{version}",
+    params(
+        ("id" = i64, Path, description = "Month ID"),
+        ("version" = i64, Path, description = "Snapshot version")
+    ),
+    responses(
+        (status = 200, description = "Download the PDF snapshot", content_type = "application/pdf"),
+        (status = 404, description = "Month or snapshot version not found")
+    ),
+    tag = "Months",
+    summary = "Download a month snapshot version",
+    description = "Retrieves the PDF generated by a specific close of the month."
+)]
+pub async fn get_month_snapshot(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, version)): Path<(i64, i64)>,
+) -> Result<impl axum::response::IntoResponse, PaymeError> {
+    let snapshot: (Vec<u8>,) = sqlx::query_as(
+        r#"
+        SELECT s.pdf_data
+        FROM monthly_snapshots s
+        JOIN months m ON s.month_id = m.id
+        WHERE s.month_id = ? AND s.version = ? AND m.user_id = ?
+        "#,
+    )
+    .bind(month_id)
+    .bind(version)
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    Ok((
+        [
+            ("Content-Type", "application/pdf".to_string()),
+            (
+                "Content-Disposition",
+                format!("attachment; filename=\"month-v{version}.pdf\""),
+            ),
+        ],
+        snapshot.0,
+    ))
+}
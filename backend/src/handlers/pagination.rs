@@ -0,0 +1,204 @@
+use axum::{
+    extract::{rejection::QueryRejection, FromRequestParts, Query},
+    http::{request::Parts, Uri},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::PaymeError;
+use crate::models::Money;
+
+/// Page size used when a paginated endpoint requires one and none is given.
+pub const DEFAULT_LIMIT: i64 = 50;
+
+/// Largest page size a client may ask for.
+pub const MAX_LIMIT: i64 = 500;
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    fn sql(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+/// Paging and sorting accepted by every list endpoint. Extracted straight
+/// from the request, whose URI the next-page link is built from.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Field to sort by; each endpoint lists the fields it accepts.
+    pub sort: Option<String>,
+    #[param(inline)]
+    pub direction: Option<SortDirection>,
+    /// Rows per page, at most 500.
+    pub limit: Option<i64>,
+    /// Rows to skip.
+    pub offset: Option<i64>,
+    #[serde(skip)]
+    uri: Uri,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ListQuery {
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(mut query) = Query::<ListQuery>::from_request_parts(parts, state).await?;
+        query.uri = parts.uri.clone();
+        Ok(query)
+    }
+}
+
+impl ListQuery {
+    /// The requested page, returning every row when no limit is given.
+    pub fn page(&self) -> Result<Page, PaymeError> {
+        Page::new(self.limit, self.offset)
+    }
+
+    /// The requested page, `default_limit` rows long when no limit is given.
+    pub fn page_or(&self, default_limit: i64) -> Result<Page, PaymeError> {
+        Page::new(Some(self.limit.unwrap_or(default_limit)), self.offset)
+    }
+
+    /// An `ORDER BY` clause for the requested sort. `fields` pairs each
+    /// accepted `sort` value with the columns it orders by; the first is the
+    /// default. `id` breaks ties so pages never overlap.
+    pub fn order_by(
+        &self,
+        fields: &[(&str, &[&str])],
+        default_direction: SortDirection,
+        id: &str,
+    ) -> Result<String, PaymeError> {
+        let columns = match &self.sort {
+            None => fields[0].1,
+            Some(sort) => fields
+                .iter()
+                .find(|(name, _)| name == sort)
+                .map(|(_, columns)| *columns)
+                .ok_or_else(|| {
+                    let names: Vec<&str> = fields.iter().map(|(name, _)| *name).collect();
+                    PaymeError::BadRequest(format!("sort must be one of: {}", names.join(", ")))
+                })?,
+        };
+        let direction = self.direction.unwrap_or(default_direction).sql();
+        let terms: Vec<String> = columns
+            .iter()
+            .chain([&id])
+            .map(|column| format!("{column} {direction}"))
+            .collect();
+        Ok(format!("ORDER BY {}", terms.join(", ")))
+    }
+
+    pub fn paginate<T>(&self, rows: Vec<T>, total: i64, page: Page) -> Paginated<T> {
+        Paginated {
+            rows,
+            total,
+            page,
+            uri: self.uri.clone(),
+        }
+    }
+}
+
+/// Bounds on a list's amounts, both inclusive.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AmountFilter {
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+}
+
+/// Bounds on a list's dates, both inclusive.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DateFilter {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// A validated `limit`/`offset` window over a list. Without a limit every
+/// row from `offset` on is returned.
+#[derive(Debug, Clone, Copy)]
+pub struct Page {
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
+impl Page {
+    pub fn new(limit: Option<i64>, offset: Option<i64>) -> Result<Self, PaymeError> {
+        if limit.is_some_and(|l| !(1..=MAX_LIMIT).contains(&l)) {
+            return Err(PaymeError::BadRequest(format!(
+                "limit must be between 1 and {MAX_LIMIT}"
+            )));
+        }
+        let offset = offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(PaymeError::BadRequest(
+                "offset must not be negative".to_string(),
+            ));
+        }
+        Ok(Self { limit, offset })
+    }
+
+    /// Value to bind to SQLite's `LIMIT`, where -1 means no limit.
+    pub fn sql_limit(&self) -> i64 {
+        self.limit.unwrap_or(-1)
+    }
+}
+
+/// One page of rows, sent as a JSON array with the total number of matching
+/// rows in `X-Total-Count` and, when more rows follow, a `Link` header with
+/// `rel="next"`.
+pub struct Paginated<T> {
+    pub rows: Vec<T>,
+    pub total: i64,
+    pub page: Page,
+    pub uri: Uri,
+}
+
+impl<T: Serialize> IntoResponse for Paginated<T> {
+    fn into_response(self) -> Response {
+        let mut headers = vec![("X-Total-Count", self.total.to_string())];
+        if let Some(next) = next_link(&self.uri, self.page, self.rows.len(), self.total) {
+            headers.push(("Link", format!("<{next}>; rel=\"next\"")));
+        }
+        let mut response = Json(self.rows).into_response();
+        for (name, value) in headers {
+            if let Ok(value) = value.parse() {
+                response.headers_mut().insert(name, value);
+            }
+        }
+        response
+    }
+}
+
+/// The request URI with `offset` moved past the current page, if any rows
+/// remain.
+fn next_link(uri: &Uri, page: Page, returned: usize, total: i64) -> Option<String> {
+    let limit = page.limit?;
+    let next_offset = page.offset + returned as i64;
+    if returned == 0 || next_offset >= total {
+        return None;
+    }
+
+    let mut params: Vec<String> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|p| !p.is_empty() && !p.starts_with("offset=") && !p.starts_with("limit="))
+        .map(str::to_string)
+        .collect();
+    params.push(format!("limit={limit}"));
+    params.push(format!("offset={next_offset}"));
+    Some(format!("{}?{}", uri.path(), params.join("&")))
+}
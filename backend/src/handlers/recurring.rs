@@ -0,0 +1,604 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use sqlx::{SqliteConnection, SqlitePool};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{
+    Frequency, Money, RecurringOverride, RecurringTransaction, UpcomingTransaction,
+};
+use crate::schedule;
+
+const RECURRING_COLUMNS: &str =
+    "id, user_id, category_id, description, amount, frequency, interval, start_date, day_of_month, end_date";
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveRecurring {
+    pub category_id: i64,
+    #[validate(length(min = 1, max = 200))]
+    pub description: String,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Money,
+    pub frequency: Frequency,
+    #[serde(default = "default_interval")]
+    #[validate(range(min = 1, max = 120))]
+    pub interval: i64,
+    pub start_date: NaiveDate,
+    #[validate(range(min = 1, max = 31))]
+    pub day_of_month: Option<i64>,
+    pub end_date: Option<NaiveDate>,
+}
+
+fn default_interval() -> i64 {
+    1
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveOccurrence {
+    #[serde(default)]
+    pub skipped: bool,
+    #[validate(range(min = Money::ZERO))]
+    pub amount: Option<Money>,
+    #[validate(length(min = 1, max = 200))]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UpcomingQuery {
+    /// How many days ahead to look, from 1 to 366. Defaults to 30.
+    pub days: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/recurring",
+    responses(
+        (status = 200, body = [RecurringTransaction]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "List recurring transactions",
+    description = "Retrieves the transactions that are entered into new months on a schedule."
+)]
+pub async fn list_recurring(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<RecurringTransaction>>, PaymeError> {
+    let recurring: Vec<RecurringTransaction> = sqlx::query_as(&format!(
+        "SELECT {RECURRING_COLUMNS} FROM recurring_transactions WHERE user_id = ? ORDER BY id"
+    ))
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(recurring))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/recurring",
+    request_body = SaveRecurring,
+    responses(
+        (status = 200, body = RecurringTransaction),
+        (status = 400, description = "Invalid schedule or category"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Create recurring transaction",
+    description = "Adds a recurring transaction. Every month created afterwards gets one item per occurrence; existing months are not changed."
+)]
+pub async fn create_recurring(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveRecurring>,
+) -> Result<Json<RecurringTransaction>, PaymeError> {
+    check_recurring(&pool, claims.sub, &payload).await?;
+
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO recurring_transactions
+            (user_id, category_id, description, amount, frequency, interval,
+             start_date, day_of_month, end_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(payload.category_id)
+    .bind(&payload.description)
+    .bind(payload.amount)
+    .bind(payload.frequency)
+    .bind(payload.interval)
+    .bind(payload.start_date)
+    .bind(payload.day_of_month)
+    .bind(payload.end_date)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(recurring_from(id, claims.sub, payload)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/recurring/{id}",
+    params(("id" = i64, Path, description = "Recurring transaction ID")),
+    request_body = SaveRecurring,
+    responses(
+        (status = 200, body = RecurringTransaction),
+        (status = 400, description = "Invalid schedule or category"),
+        (status = 404, description = "Recurring transaction not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Update recurring transaction",
+    description = "Replaces a recurring transaction. Items already entered into existing months are not changed."
+)]
+pub async fn update_recurring(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveRecurring>,
+) -> Result<Json<RecurringTransaction>, PaymeError> {
+    check_recurring(&pool, claims.sub, &payload).await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE recurring_transactions
+        SET category_id = ?, description = ?, amount = ?, frequency = ?, interval = ?,
+            start_date = ?, day_of_month = ?, end_date = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+    )
+    .bind(payload.category_id)
+    .bind(&payload.description)
+    .bind(payload.amount)
+    .bind(payload.frequency)
+    .bind(payload.interval)
+    .bind(payload.start_date)
+    .bind(payload.day_of_month)
+    .bind(payload.end_date)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(recurring_from(id, claims.sub, payload)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/recurring/{id}",
+    params(("id" = i64, Path, description = "Recurring transaction ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Configuration",
+    summary = "Delete recurring transaction",
+    description = "Stops entering this transaction into new months. Items already entered are kept."
+)]
+pub async fn delete_recurring(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM recurring_transactions WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/recurring/{id}/occurrences/{date}",
+    params(
+        ("id" = i64, Path, description = "Recurring transaction ID"),
+        ("date" = NaiveDate, Path, description = "Date of the occurrence")
+    ),
+    request_body = SaveOccurrence,
+    responses(
+        (status = 200, body = RecurringOverride),
+        (status = 400, description = "Not an occurrence, or its month is closed"),
+        (status = 404, description = "Recurring transaction not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Skip or edit an occurrence",
+    description = "Skips a single occurrence or changes its amount or description. If the occurrence's month already exists, its item is updated to match."
+)]
+pub async fn update_occurrence(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((id, occurs_on)): Path<(i64, NaiveDate)>,
+    Json(payload): Json<SaveOccurrence>,
+) -> Result<Json<RecurringOverride>, PaymeError> {
+    payload.validate()?;
+    let recurring = find_occurrence(&pool, claims.sub, id, occurs_on).await?;
+    let month_id = open_month_for(&pool, claims.sub, occurs_on).await?;
+
+    let change = RecurringOverride {
+        recurring_id: id,
+        occurs_on,
+        skipped: payload.skipped,
+        amount: payload.amount,
+        description: payload.description,
+    };
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO recurring_overrides (recurring_id, occurs_on, skipped, amount, description)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT(recurring_id, occurs_on) DO UPDATE SET
+            skipped = excluded.skipped, amount = excluded.amount, description = excluded.description
+        "#,
+    )
+    .bind(id)
+    .bind(occurs_on)
+    .bind(change.skipped)
+    .bind(change.amount)
+    .bind(&change.description)
+    .execute(&mut *tx)
+    .await?;
+    if let Some(month_id) = month_id {
+        replace_item(&mut tx, month_id, &recurring, occurs_on, Some(&change)).await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(change))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/recurring/{id}/occurrences/{date}",
+    params(
+        ("id" = i64, Path, description = "Recurring transaction ID"),
+        ("date" = NaiveDate, Path, description = "Date of the occurrence")
+    ),
+    responses(
+        (status = 204, description = "Occurrence restored"),
+        (status = 400, description = "Not an occurrence, or its month is closed"),
+        (status = 404, description = "Recurring transaction not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Restore an occurrence",
+    description = "Undoes a skip or edit so the occurrence is entered as scheduled again."
+)]
+pub async fn restore_occurrence(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((id, occurs_on)): Path<(i64, NaiveDate)>,
+) -> Result<StatusCode, PaymeError> {
+    let recurring = find_occurrence(&pool, claims.sub, id, occurs_on).await?;
+    let month_id = open_month_for(&pool, claims.sub, occurs_on).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM recurring_overrides WHERE recurring_id = ? AND occurs_on = ?")
+        .bind(id)
+        .bind(occurs_on)
+        .execute(&mut *tx)
+        .await?;
+    if let Some(month_id) = month_id {
+        replace_item(&mut tx, month_id, &recurring, occurs_on, None).await?;
+    }
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/recurring/upcoming",
+    params(UpcomingQuery),
+    responses(
+        (status = 200, body = [UpcomingTransaction]),
+        (status = 400, description = "Invalid number of days"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "List upcoming transactions",
+    description = "Lists the occurrences of recurring transactions due from today through the given number of days, with skipped occurrences left out and edits applied."
+)]
+pub async fn list_upcoming(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Query(query): Query<UpcomingQuery>,
+) -> Result<Json<Vec<UpcomingTransaction>>, PaymeError> {
+    let days = query.days.unwrap_or(30);
+    if !(1..=366).contains(&days) {
+        return Err(PaymeError::BadRequest(
+            "days must be between 1 and 366".to_string(),
+        ));
+    }
+    let from = Utc::now().date_naive();
+    let to = from + Duration::days(days);
+
+    let mut conn = pool.acquire().await?;
+    let recurring = load_recurring(&mut conn, claims.sub).await?;
+    let overrides = load_overrides(&mut conn, claims.sub, from, to).await?;
+
+    let mut upcoming: Vec<UpcomingTransaction> = recurring
+        .iter()
+        .flat_map(|recurring| {
+            schedule::recurring_dates(recurring, from, to)
+                .into_iter()
+                .filter_map(|due_on| {
+                    let change = overrides.get(&(recurring.id, due_on));
+                    let (description, amount) = effective(recurring, change)?;
+                    Some(UpcomingTransaction {
+                        recurring_id: recurring.id,
+                        category_id: recurring.category_id,
+                        description: description.to_string(),
+                        amount,
+                        due_on,
+                    })
+                })
+        })
+        .collect();
+    upcoming.sort_by_key(|u| (u.due_on, u.recurring_id));
+
+    Ok(Json(upcoming))
+}
+
+/// Enters the occurrences of the user's recurring transactions that fall in a
+/// newly created month as items.
+pub(crate) async fn materialize_month(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    month_id: i64,
+    year: i32,
+    month: i32,
+) -> Result<(), sqlx::Error> {
+    let Some((first, last)) = schedule::month_bounds(year, month) else {
+        return Ok(());
+    };
+    let recurring = load_recurring(conn, user_id).await?;
+    let overrides = load_overrides(conn, user_id, first, last).await?;
+
+    for recurring in &recurring {
+        for date in schedule::recurring_dates(recurring, first, last) {
+            insert_item(
+                conn,
+                month_id,
+                recurring,
+                date,
+                overrides.get(&(recurring.id, date)),
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_recurring(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+) -> Result<Vec<RecurringTransaction>, sqlx::Error> {
+    sqlx::query_as(&format!(
+        "SELECT {RECURRING_COLUMNS} FROM recurring_transactions WHERE user_id = ? ORDER BY id"
+    ))
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+}
+
+async fn load_overrides(
+    conn: &mut SqliteConnection,
+    user_id: i64,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HashMap<(i64, NaiveDate), RecurringOverride>, sqlx::Error> {
+    let overrides: Vec<RecurringOverride> = sqlx::query_as(
+        r#"
+        SELECT o.recurring_id, o.occurs_on, o.skipped, o.amount, o.description
+        FROM recurring_overrides o
+        JOIN recurring_transactions r ON r.id = o.recurring_id
+        WHERE r.user_id = ? AND o.occurs_on BETWEEN ? AND ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(overrides
+        .into_iter()
+        .map(|o| ((o.recurring_id, o.occurs_on), o))
+        .collect())
+}
+
+/// The description and amount of an occurrence, or `None` when it is skipped.
+fn effective<'a>(
+    recurring: &'a RecurringTransaction,
+    change: Option<&'a RecurringOverride>,
+) -> Option<(&'a str, Money)> {
+    match change {
+        Some(change) if change.skipped => None,
+        Some(change) => Some((
+            change
+                .description
+                .as_deref()
+                .unwrap_or(&recurring.description),
+            change.amount.unwrap_or(recurring.amount),
+        )),
+        None => Some((&recurring.description, recurring.amount)),
+    }
+}
+
+async fn insert_item(
+    conn: &mut SqliteConnection,
+    month_id: i64,
+    recurring: &RecurringTransaction,
+    date: NaiveDate,
+    change: Option<&RecurringOverride>,
+) -> Result<(), sqlx::Error> {
+    let Some((description, amount)) = effective(recurring, change) else {
+        return Ok(());
+    };
+    sqlx::query(
+        "INSERT INTO items (month_id, category_id, description, amount, spent_on, recurring_id) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(month_id)
+    .bind(recurring.category_id)
+    .bind(description)
+    .bind(amount)
+    .bind(date)
+    .bind(recurring.id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Brings an occurrence's item in line with `change`. An existing item is
+/// edited in place so its tags and attachments stay with it; its splits are
+/// kept too unless the amount changes, since they would no longer add up.
+async fn replace_item(
+    conn: &mut SqliteConnection,
+    month_id: i64,
+    recurring: &RecurringTransaction,
+    date: NaiveDate,
+    change: Option<&RecurringOverride>,
+) -> Result<(), sqlx::Error> {
+    let existing: Option<(i64, Money)> = sqlx::query_as(
+        "SELECT id, amount FROM items WHERE month_id = ? AND recurring_id = ? AND spent_on = ?",
+    )
+    .bind(month_id)
+    .bind(recurring.id)
+    .bind(date)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((item_id, previous_amount)) = existing else {
+        return insert_item(conn, month_id, recurring, date, change).await;
+    };
+
+    let Some((description, amount)) = effective(recurring, change) else {
+        sqlx::query("DELETE FROM items WHERE id = ?")
+            .bind(item_id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    };
+    if amount != previous_amount {
+        sqlx::query("DELETE FROM item_splits WHERE item_id = ?")
+            .bind(item_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    sqlx::query("UPDATE items SET description = ?, amount = ? WHERE id = ?")
+        .bind(description)
+        .bind(amount)
+        .bind(item_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Loads a recurring transaction, checking that it falls on `date`.
+async fn find_occurrence(
+    pool: &SqlitePool,
+    user_id: i64,
+    id: i64,
+    date: NaiveDate,
+) -> Result<RecurringTransaction, PaymeError> {
+    let recurring: RecurringTransaction = sqlx::query_as(&format!(
+        "SELECT {RECURRING_COLUMNS} FROM recurring_transactions WHERE id = ? AND user_id = ?"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    if schedule::recurring_dates(&recurring, date, date).is_empty() {
+        return Err(PaymeError::BadRequest(
+            "Not an occurrence of this transaction".to_string(),
+        ));
+    }
+    Ok(recurring)
+}
+
+/// The id of the month containing `date` if it has been created, failing
+/// when that month is closed.
+async fn open_month_for(
+    pool: &SqlitePool,
+    user_id: i64,
+    date: NaiveDate,
+) -> Result<Option<i64>, PaymeError> {
+    let month: Option<(i64, bool)> = sqlx::query_as(
+        "SELECT id, is_closed FROM months WHERE user_id = ? AND year = ? AND month = ?",
+    )
+    .bind(user_id)
+    .bind(date.year())
+    .bind(date.month() as i32)
+    .fetch_optional(pool)
+    .await?;
+
+    match month {
+        Some((_, true)) => Err(PaymeError::MonthClosed),
+        Some((id, false)) => Ok(Some(id)),
+        None => Ok(None),
+    }
+}
+
+async fn check_recurring(
+    pool: &SqlitePool,
+    user_id: i64,
+    recurring: &SaveRecurring,
+) -> Result<(), PaymeError> {
+    recurring.validate()?;
+
+    if recurring.day_of_month.is_some() && recurring.frequency != Frequency::Monthly {
+        return Err(PaymeError::BadRequest(
+            "day_of_month only applies to monthly schedules".to_string(),
+        ));
+    }
+    if recurring
+        .end_date
+        .is_some_and(|end| end < recurring.start_date)
+    {
+        return Err(PaymeError::BadRequest(
+            "end_date is before start_date".to_string(),
+        ));
+    }
+
+    let _category: (i64,) =
+        sqlx::query_as("SELECT id FROM budget_categories WHERE id = ? AND user_id = ?")
+            .bind(recurring.category_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(PaymeError::InvalidReference("category"))?;
+
+    Ok(())
+}
+
+fn recurring_from(id: i64, user_id: i64, recurring: SaveRecurring) -> RecurringTransaction {
+    RecurringTransaction {
+        id,
+        user_id,
+        category_id: recurring.category_id,
+        description: recurring.description,
+        amount: recurring.amount,
+        frequency: recurring.frequency,
+        interval: recurring.interval,
+        start_date: recurring.start_date,
+        day_of_month: recurring.day_of_month,
+        end_date: recurring.end_date,
+    }
+}
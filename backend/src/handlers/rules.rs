@@ -0,0 +1,302 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::categorization::{RuleSet, RULE_COLUMNS};
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{CategorizationRule, Money};
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveRule {
+    pub category_id: i64,
+    #[serde(default)]
+    pub priority: i64,
+    #[validate(length(min = 1, max = 200))]
+    pub description_contains: Option<String>,
+    #[validate(length(min = 1, max = 500))]
+    pub description_regex: Option<String>,
+    #[validate(range(min = Money::ZERO))]
+    pub min_amount: Option<Money>,
+    #[validate(range(min = Money::ZERO))]
+    pub max_amount: Option<Money>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApplyRulesResult {
+    /// Number of items whose category changed.
+    pub updated: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/rules",
+    responses(
+        (status = 200, body = [CategorizationRule]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "List categorization rules",
+    description = "Retrieves the user's categorization rules in the order they are tried."
+)]
+pub async fn list_rules(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<CategorizationRule>>, PaymeError> {
+    let rules: Vec<CategorizationRule> = sqlx::query_as(&format!(
+        "SELECT {RULE_COLUMNS} FROM categorization_rules WHERE user_id = ? ORDER BY priority, id"
+    ))
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(rules))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/rules",
+    request_body = SaveRule,
+    responses(
+        (status = 200, body = CategorizationRule),
+        (status = 400, description = "Invalid conditions or category"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Create categorization rule",
+    description = "Adds a rule that files matching items into a category when they are created or imported."
+)]
+pub async fn create_rule(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveRule>,
+) -> Result<Json<CategorizationRule>, PaymeError> {
+    check_rule(&pool, claims.sub, &payload).await?;
+
+    let id: i64 = sqlx::query_scalar(
+        r#"
+        INSERT INTO categorization_rules
+            (user_id, category_id, priority, description_contains, description_regex,
+             min_amount, max_amount, start_date, end_date)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING id
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(payload.category_id)
+    .bind(payload.priority)
+    .bind(&payload.description_contains)
+    .bind(&payload.description_regex)
+    .bind(payload.min_amount)
+    .bind(payload.max_amount)
+    .bind(payload.start_date)
+    .bind(payload.end_date)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(rule_from(id, claims.sub, payload)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/rules/{id}",
+    params(("id" = i64, Path, description = "Rule ID")),
+    request_body = SaveRule,
+    responses(
+        (status = 200, body = CategorizationRule),
+        (status = 400, description = "Invalid conditions or category"),
+        (status = 404, description = "Rule not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Update categorization rule",
+    description = "Replaces the conditions, priority and category of a rule."
+)]
+pub async fn update_rule(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveRule>,
+) -> Result<Json<CategorizationRule>, PaymeError> {
+    check_rule(&pool, claims.sub, &payload).await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE categorization_rules
+        SET category_id = ?, priority = ?, description_contains = ?, description_regex = ?,
+            min_amount = ?, max_amount = ?, start_date = ?, end_date = ?
+        WHERE id = ? AND user_id = ?
+        "#,
+    )
+    .bind(payload.category_id)
+    .bind(payload.priority)
+    .bind(&payload.description_contains)
+    .bind(&payload.description_regex)
+    .bind(payload.min_amount)
+    .bind(payload.max_amount)
+    .bind(payload.start_date)
+    .bind(payload.end_date)
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(rule_from(id, claims.sub, payload)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/rules/{id}",
+    params(("id" = i64, Path, description = "Rule ID")),
+    responses(
+        (status = 204, description = "Rule deleted successfully"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Delete categorization rule",
+    description = "Removes a rule. Items it already categorized keep their category."
+)]
+pub async fn delete_rule(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM categorization_rules WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/months/{id}/rules/apply",
+    params(("id" = i64, Path, description = "Month ID")),
+    responses(
+        (status = 200, body = ApplyRulesResult),
+        (status = 400, description = "Month is closed"),
+        (status = 404, description = "Month not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Items",
+    summary = "Re-apply rules to month",
+    description = "Runs the categorization rules over every item of an open month. Items that no rule matches keep their current category."
+)]
+pub async fn apply_rules_to_month(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(month_id): Path<i64>,
+) -> Result<Json<ApplyRulesResult>, PaymeError> {
+    let month: Option<(bool,)> =
+        sqlx::query_as("SELECT is_closed FROM months WHERE id = ? AND user_id = ?")
+            .bind(month_id)
+            .bind(claims.sub)
+            .fetch_optional(&pool)
+            .await?;
+    match month {
+        Some((true,)) => return Err(PaymeError::MonthClosed),
+        Some((false,)) => {}
+        None => return Err(PaymeError::NotFound),
+    }
+
+    let rules = RuleSet::load(&pool, claims.sub).await?;
+    let items: Vec<(i64, i64, String, Money, NaiveDate)> = sqlx::query_as(
+        "SELECT id, category_id, description, amount, spent_on FROM items WHERE month_id = ? AND id NOT IN (SELECT item_id FROM item_splits)",
+    )
+    .bind(month_id)
+    .fetch_all(&pool)
+    .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut updated = 0;
+    for (id, category_id, description, amount, spent_on) in items {
+        match rules.category_for(&description, amount, spent_on) {
+            Some(matched) if matched != category_id => {
+                sqlx::query("UPDATE items SET category_id = ? WHERE id = ?")
+                    .bind(matched)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                updated += 1;
+            }
+            _ => {}
+        }
+    }
+    tx.commit().await?;
+
+    Ok(Json(ApplyRulesResult { updated }))
+}
+
+async fn check_rule(pool: &SqlitePool, user_id: i64, rule: &SaveRule) -> Result<(), PaymeError> {
+    rule.validate()?;
+
+    if rule.description_contains.is_none()
+        && rule.description_regex.is_none()
+        && rule.min_amount.is_none()
+        && rule.max_amount.is_none()
+        && rule.start_date.is_none()
+        && rule.end_date.is_none()
+    {
+        return Err(PaymeError::BadRequest(
+            "A rule needs at least one condition".to_string(),
+        ));
+    }
+    if let Some(pattern) = &rule.description_regex {
+        Regex::new(pattern).map_err(|e| PaymeError::BadRequest(format!("Invalid regex: {e}")))?;
+    }
+    if let (Some(min), Some(max)) = (rule.min_amount, rule.max_amount) {
+        if min > max {
+            return Err(PaymeError::BadRequest(
+                "min_amount is greater than max_amount".to_string(),
+            ));
+        }
+    }
+    if let (Some(start), Some(end)) = (rule.start_date, rule.end_date) {
+        if start > end {
+            return Err(PaymeError::BadRequest(
+                "start_date is after end_date".to_string(),
+            ));
+        }
+    }
+
+    let _category: (i64,) =
+        sqlx::query_as("SELECT id FROM budget_categories WHERE id = ? AND user_id = ?")
+            .bind(rule.category_id)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(PaymeError::InvalidReference("category"))?;
+
+    Ok(())
+}
+
+fn rule_from(id: i64, user_id: i64, rule: SaveRule) -> CategorizationRule {
+    CategorizationRule {
+        id,
+        user_id,
+        category_id: rule.category_id,
+        priority: rule.priority,
+        description_contains: rule.description_contains,
+        description_regex: rule.description_regex,
+        min_amount: rule.min_amount,
+        max_amount: rule.max_amount,
+        start_date: rule.start_date,
+        end_date: rule.end_date,
+    }
+}
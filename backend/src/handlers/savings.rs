@@ -1,39 +1,119 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, Months, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::error::PaymeError;
+use crate::handlers::accounts::{primary_account, set_total_balance, total_balance};
 use crate::middleware::auth::Claims;
+use crate::models::{AccountKind, LedgerEntry, LedgerEntryKind, Money};
+
+/// Number of complete months averaged for the contribution rate.
+const CONTRIBUTION_WINDOW: i32 = 3;
+
+/// Dated changes to the user's savings balance; binds the user id and
+/// [`AccountKind::Savings`]. Income entries are dated on the first of their month.
+const SAVINGS_CHANGES: &str = r#"
+    WITH savings AS (SELECT id FROM accounts WHERE user_id = ? AND kind = ?)
+    SELECT entry_date AS occurred_on, amount, kind != 'correction' AS contributes
+    FROM ledger_entries WHERE account_id IN (SELECT id FROM savings)
+    UNION ALL
+    SELECT transferred_on, amount, 1 FROM transfers
+    WHERE to_account_id IN (SELECT id FROM savings)
+    UNION ALL
+    SELECT transferred_on, -amount, 1 FROM transfers
+    WHERE from_account_id IN (SELECT id FROM savings)
+    UNION ALL
+    SELECT spent_on, -amount, 1 FROM items
+    WHERE account_id IN (SELECT id FROM savings)
+    UNION ALL
+    SELECT printf('%04d-%02d-01', m.year, m.month), e.amount, 1
+    FROM income_entries e JOIN months m ON e.month_id = m.id
+    WHERE e.account_id IN (SELECT id FROM savings)
+    ORDER BY occurred_on
+"#;
 
 #[derive(Serialize, ToSchema)]
 pub struct SavingsResponse {
-    pub savings: f64,
-    pub savings_goal: f64,
+    pub savings: Money,
+    pub savings_goal: Money,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateSavings {
-    #[validate(range(min = 0.0))]
-    pub savings: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub savings: Money,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateSavingsGoal {
-    #[validate(range(min = 0.0))]
-    pub savings_goal: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub savings_goal: Money,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct CreateLedgerEntry {
+    /// One of the user's savings accounts; defaults to the oldest.
+    pub account_id: Option<i64>,
+    pub kind: LedgerEntryKind,
+    /// Positive for deposits and withdrawals; the signed change for corrections.
+    pub amount: Money,
+    /// Defaults to today.
+    pub entry_date: Option<NaiveDate>,
+    #[validate(length(max = 200))]
+    pub note: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BalancePoint {
+    pub date: NaiveDate,
+    /// Balance at the end of the day.
+    pub balance: Money,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MonthlyContribution {
+    pub year: i32,
+    pub month: i32,
+    /// Money paid in minus money taken out, excluding corrections.
+    pub amount: Money,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SavingsProjection {
+    pub savings_goal: Money,
+    /// Average net contribution over the last three complete months.
+    pub monthly_contribution: Money,
+    /// Zero once the goal is reached; absent without a goal or while savings
+    /// are not growing.
+    pub months_to_goal: Option<u32>,
+    pub projected_date: Option<NaiveDate>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SavingsHistory {
+    /// Combined opening balance of the savings accounts.
+    pub opening_balance: Money,
+    pub balances: Vec<BalancePoint>,
+    pub contributions: Vec<MonthlyContribution>,
+    pub projection: SavingsProjection,
 }
 
 #[derive(Serialize, ToSchema)]
 pub struct RetirementSavingsResponse {
-    pub retirement_savings: f64,
+    pub retirement_savings: Money,
 }
 
 #[derive(Deserialize, ToSchema, Validate)]
 pub struct UpdateRetirementSavings {
-    #[validate(range(min = 0.0))]
-    pub retirement_savings: f64,
+    #[validate(range(min = Money::ZERO))]
+    pub retirement_savings: Money,
 }
 
 #[utoipa::path(
@@ -45,17 +125,22 @@ pub struct UpdateRetirementSavings {
     ),
     tag = "Wealth",
     summary = "Get savings balance",
-    description = "Retrieves the user's total liquid savings amount stored in their profile."
+    description = "Retrieves the combined balance of the user's savings accounts and their savings goal."
 )]
 pub async fn get_savings(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
 ) -> Result<Json<SavingsResponse>, PaymeError> {
-    let (savings, savings_goal): (f64, f64) =
-        sqlx::query_as("SELECT savings, savings_goal FROM users WHERE id = ?")
-            .bind(claims.sub)
-            .fetch_one(&pool)
-            .await?;
+    let savings = total_balance(
+        &mut *pool.acquire().await?,
+        claims.sub,
+        AccountKind::Savings,
+    )
+    .await?;
+    let savings_goal: Money = sqlx::query_scalar("SELECT savings_goal FROM users WHERE id = ?")
+        .bind(claims.sub)
+        .fetch_one(&pool)
+        .await?;
 
     Ok(Json(SavingsResponse {
         savings,
@@ -73,7 +158,7 @@ pub async fn get_savings(
     ),
     tag = "Wealth",
     summary = "Update savings balance",
-    description = "Sets a new value for the user's total liquid savings."
+    description = "Sets the combined balance of the user's savings accounts by recording a correction on the oldest one, creating a savings account if there is none."
 )]
 pub async fn update_savings(
     State(pool): State<SqlitePool>,
@@ -81,13 +166,19 @@ pub async fn update_savings(
     Json(payload): Json<UpdateSavings>,
 ) -> Result<Json<SavingsResponse>, PaymeError> {
     payload.validate()?;
-    sqlx::query("UPDATE users SET savings = ? WHERE id = ?")
-        .bind(payload.savings)
-        .bind(claims.sub)
-        .execute(&pool)
-        .await?;
+    let mut tx = pool.begin().await?;
+    set_total_balance(
+        &mut tx,
+        claims.sub,
+        AccountKind::Savings,
+        "Savings",
+        payload.savings,
+        Utc::now().date_naive(),
+    )
+    .await?;
+    tx.commit().await?;
 
-    let savings_goal: f64 = sqlx::query_scalar("SELECT savings_goal FROM users WHERE id = ?")
+    let savings_goal: Money = sqlx::query_scalar("SELECT savings_goal FROM users WHERE id = ?")
         .bind(claims.sub)
         .fetch_one(&pool)
         .await?;
@@ -122,10 +213,12 @@ pub async fn update_savings_goal(
         .execute(&pool)
         .await?;
 
-    let savings: f64 = sqlx::query_scalar("SELECT savings FROM users WHERE id = ?")
-        .bind(claims.sub)
-        .fetch_one(&pool)
-        .await?;
+    let savings = total_balance(
+        &mut *pool.acquire().await?,
+        claims.sub,
+        AccountKind::Savings,
+    )
+    .await?;
 
     Ok(Json(SavingsResponse {
         savings,
@@ -133,6 +226,236 @@ pub async fn update_savings_goal(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/savings/entries",
+    responses(
+        (status = 200, body = [LedgerEntry]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "List savings ledger",
+    description = "Retrieves the deposits, withdrawals and corrections recorded on the user's savings accounts, newest first."
+)]
+pub async fn list_ledger_entries(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<LedgerEntry>>, PaymeError> {
+    let entries: Vec<LedgerEntry> = sqlx::query_as(
+        r#"
+        SELECT e.id, e.account_id, e.kind, e.amount, e.entry_date, e.note
+        FROM ledger_entries e
+        JOIN accounts a ON e.account_id = a.id
+        WHERE a.user_id = ? AND a.kind = ?
+        ORDER BY e.entry_date DESC, e.id DESC
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(AccountKind::Savings)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(entries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/savings/entries",
+    request_body = CreateLedgerEntry,
+    responses(
+        (status = 200, body = LedgerEntry),
+        (status = 400, description = "Invalid amount or account"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Record savings change",
+    description = "Records a dated deposit, withdrawal or correction on a savings account, creating a savings account if there is none."
+)]
+pub async fn create_ledger_entry(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateLedgerEntry>,
+) -> Result<Json<LedgerEntry>, PaymeError> {
+    payload.validate()?;
+    let amount = match payload.kind {
+        LedgerEntryKind::Correction => payload.amount,
+        _ if payload.amount <= Money::ZERO => {
+            return Err(PaymeError::BadRequest(
+                "Amount must be positive".to_string(),
+            ));
+        }
+        LedgerEntryKind::Deposit => payload.amount,
+        LedgerEntryKind::Withdrawal => -payload.amount,
+    };
+    let entry_date = payload
+        .entry_date
+        .unwrap_or_else(|| Utc::now().date_naive());
+
+    let mut tx = pool.begin().await?;
+    let account_id = match payload.account_id {
+        Some(account_id) => {
+            sqlx::query_scalar("SELECT id FROM accounts WHERE id = ? AND user_id = ? AND kind = ?")
+                .bind(account_id)
+                .bind(claims.sub)
+                .bind(AccountKind::Savings)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(PaymeError::InvalidReference("account"))?
+        }
+        None => primary_account(&mut tx, claims.sub, AccountKind::Savings, "Savings").await?,
+    };
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO ledger_entries (account_id, kind, amount, entry_date, note) VALUES (?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(account_id)
+    .bind(payload.kind)
+    .bind(amount)
+    .bind(entry_date)
+    .bind(&payload.note)
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(LedgerEntry {
+        id,
+        account_id,
+        kind: payload.kind,
+        amount,
+        entry_date,
+        note: payload.note,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/savings/entries/{id}",
+    params(("id" = i64, Path, description = "Ledger entry ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Wealth",
+    summary = "Delete savings change",
+    description = "Removes a ledger entry, undoing its effect on the account balance."
+)]
+pub async fn delete_ledger_entry(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query(
+        "DELETE FROM ledger_entries WHERE id = ? AND account_id IN (SELECT id FROM accounts WHERE user_id = ?)",
+    )
+    .bind(id)
+    .bind(claims.sub)
+    .execute(&pool)
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/savings/history",
+    responses(
+        (status = 200, body = SavingsHistory),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Wealth",
+    summary = "Get savings history",
+    description = "Retrieves the savings balance after each day it changed, the net contribution per month, and when the savings goal will be reached at the recent contribution rate."
+)]
+pub async fn get_savings_history(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<SavingsHistory>, PaymeError> {
+    let (opening_balance, savings_goal): (Money, Money) = sqlx::query_as(
+        r#"
+        SELECT COALESCE((SELECT SUM(opening_balance) FROM accounts WHERE user_id = u.id AND kind = ?), 0),
+            savings_goal
+        FROM users u WHERE id = ?
+        "#,
+    )
+    .bind(AccountKind::Savings)
+    .bind(claims.sub)
+    .fetch_one(&pool)
+    .await?;
+    let changes: Vec<(NaiveDate, Money, bool)> = sqlx::query_as(SAVINGS_CHANGES)
+        .bind(claims.sub)
+        .bind(AccountKind::Savings)
+        .fetch_all(&pool)
+        .await?;
+
+    let mut balance = opening_balance;
+    let mut balances: Vec<BalancePoint> = Vec::new();
+    let mut contributions: Vec<MonthlyContribution> = Vec::new();
+    for (date, amount, contributes) in changes {
+        balance = balance
+            .checked_add(amount)
+            .ok_or(PaymeError::AmountOutOfRange)?;
+        match balances.last_mut() {
+            Some(point) if point.date == date => point.balance = balance,
+            _ => balances.push(BalancePoint { date, balance }),
+        }
+        if !contributes {
+            continue;
+        }
+        let (year, month) = (date.year(), date.month() as i32);
+        match contributions.last_mut() {
+            Some(c) if c.year == year && c.month == month => {
+                c.amount = c
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(PaymeError::AmountOutOfRange)?
+            }
+            _ => contributions.push(MonthlyContribution {
+                year,
+                month,
+                amount,
+            }),
+        }
+    }
+
+    let today = Utc::now().date_naive();
+    let current = today.year() * 12 + today.month0() as i32;
+    let monthly_contribution = Money::checked_sum(
+        contributions
+            .iter()
+            .filter(|c| {
+                (current - CONTRIBUTION_WINDOW..current).contains(&(c.year * 12 + c.month - 1))
+            })
+            .map(|c| c.amount),
+    )
+    .ok_or(PaymeError::AmountOutOfRange)?
+    .checked_div_rounded(CONTRIBUTION_WINDOW.into())
+    .unwrap_or(Money::ZERO);
+    let months_to_goal = months_to_goal(balance, savings_goal, monthly_contribution);
+
+    Ok(Json(SavingsHistory {
+        opening_balance,
+        balances,
+        contributions,
+        projection: SavingsProjection {
+            savings_goal,
+            monthly_contribution,
+            months_to_goal,
+            projected_date: months_to_goal
+                .and_then(|months| today.checked_add_months(Months::new(months))),
+        },
+    }))
+}
+
+/// Whole months until `balance` reaches `goal` when growing by `rate` a month.
+fn months_to_goal(balance: Money, goal: Money, rate: Money) -> Option<u32> {
+    let remaining = goal - balance;
+    if goal.is_zero() {
+        None
+    } else if remaining <= Money::ZERO {
+        Some(0)
+    } else if rate <= Money::ZERO {
+        None
+    } else {
+        u32::try_from((remaining.minor() + rate.minor() - 1) / rate.minor()).ok()
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/retirement-savings",
@@ -142,18 +465,18 @@ pub async fn update_savings_goal(
     ),
     tag = "Wealth",
     summary = "Get retirement savings balance",
-    description = "Retrieves the user's total retirement savings balance."
+    description = "Retrieves the combined balance of the user's retirement accounts."
 )]
 pub async fn get_retirement_savings(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
 ) -> Result<Json<RetirementSavingsResponse>, PaymeError> {
-    let retirement_savings: f64 =
-        sqlx::query_scalar("SELECT retirement_savings FROM users WHERE id = ?")
-            .bind(claims.sub)
-            .fetch_one(&pool)
-            .await
-            .unwrap_or(0.0);
+    let retirement_savings = total_balance(
+        &mut *pool.acquire().await?,
+        claims.sub,
+        AccountKind::Retirement,
+    )
+    .await?;
 
     Ok(Json(RetirementSavingsResponse { retirement_savings }))
 }
@@ -168,7 +491,7 @@ pub async fn get_retirement_savings(
     ),
     tag = "Wealth",
     summary = "Update retirement savings balance",
-    description = "Sets a new value for the user's total retirement savings balance."
+    description = "Sets the combined balance of the user's retirement accounts by recording a correction on the oldest one, creating a retirement account if there is none."
 )]
 pub async fn update_retirement_savings(
     State(pool): State<SqlitePool>,
@@ -176,11 +499,17 @@ pub async fn update_retirement_savings(
     Json(payload): Json<UpdateRetirementSavings>,
 ) -> Result<Json<RetirementSavingsResponse>, PaymeError> {
     payload.validate()?;
-    sqlx::query("UPDATE users SET retirement_savings = ? WHERE id = ?")
-        .bind(payload.retirement_savings)
-        .bind(claims.sub)
-        .execute(&pool)
-        .await?;
+    let mut tx = pool.begin().await?;
+    set_total_balance(
+        &mut tx,
+        claims.sub,
+        AccountKind::Retirement,
+        "Retirement",
+        payload.retirement_savings,
+        Utc::now().date_naive(),
+    )
+    .await?;
+    tx.commit().await?;
 
     Ok(Json(RetirementSavingsResponse {
         retirement_savings: payload.retirement_savings,
@@ -0,0 +1,346 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Path, State},
+    http::{request::Parts, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+use crate::error::PaymeError;
+use crate::middleware::auth::{jwt_secret, Claims};
+use crate::models::Session;
+
+/// Lifetime of an access token; clients renew it with their refresh token.
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
+
+/// Lifetime of a refresh token, renewed each time it is used.
+pub const REFRESH_TOKEN_DAYS: i64 = 30;
+
+/// A replayed refresh token within this window is treated as a race between
+/// two tabs rather than theft.
+const REUSE_GRACE_SECONDS: i64 = 30;
+
+/// Refresh tokens are only sent to the auth endpoints.
+const REFRESH_COOKIE_PATH: &str = "/api/auth";
+
+/// The device a request comes from, recorded on its session.
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let peer = ConnectInfo::<SocketAddr>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+
+        // Forwarding headers are only believed when a trusted proxy sent them.
+        let trusted = trusted_proxies();
+        let ip = match peer {
+            Some(peer) if trusted.contains(&peer) => header("X-Forwarded-For")
+                .and_then(|v| {
+                    // The nearest hop not run by us is the client.
+                    v.rsplit(',')
+                        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+                        .find(|ip| !trusted.contains(ip))
+                })
+                .or_else(|| header("X-Real-IP").and_then(|v| v.trim().parse().ok()))
+                .or(Some(peer)),
+            peer => peer,
+        };
+        Ok(Self {
+            user_agent: header("User-Agent").map(|ua| ua.chars().take(256).collect()),
+            ip: ip.map(|ip| ip.to_string()),
+        })
+    }
+}
+
+/// Reverse proxies allowed to report the client address, from the
+/// comma-separated `TRUSTED_PROXIES` environment variable.
+fn trusted_proxies() -> Vec<IpAddr> {
+    std::env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect()
+}
+
+/// A freshly issued access and refresh token pair.
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+impl SessionTokens {
+    /// Adds both tokens to the cookie jar.
+    pub fn set_cookies(&self, jar: CookieJar) -> CookieJar {
+        let access = Cookie::build(("token", self.access_token.clone()))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::minutes(ACCESS_TOKEN_MINUTES))
+            .build();
+        let refresh = Cookie::build(("refresh_token", self.refresh_token.clone()))
+            .path(REFRESH_COOKIE_PATH)
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .max_age(time::Duration::days(REFRESH_TOKEN_DAYS))
+            .build();
+        jar.add(access).add(refresh)
+    }
+}
+
+/// Expires both token cookies.
+pub fn clear_cookies(jar: CookieJar) -> CookieJar {
+    let access = Cookie::build(("token", ""))
+        .path("/")
+        .http_only(true)
+        .max_age(time::Duration::seconds(0))
+        .build();
+    let refresh = Cookie::build(("refresh_token", ""))
+        .path(REFRESH_COOKIE_PATH)
+        .http_only(true)
+        .max_age(time::Duration::seconds(0))
+        .build();
+    jar.add(access).add(refresh)
+}
+
+/// Opens a session for the user and issues its first tokens.
+pub async fn start_session(
+    pool: &SqlitePool,
+    user_id: i64,
+    username: &str,
+    client: &ClientInfo,
+) -> Result<SessionTokens, PaymeError> {
+    let refresh_token = random_token();
+    let session_id: i64 = sqlx::query_scalar(
+        "INSERT INTO sessions (user_id, token_hash, user_agent, ip, expires_at) VALUES (?, ?, ?, ?, datetime('now', ?)) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(hash_token(&refresh_token))
+    .bind(&client.user_agent)
+    .bind(&client.ip)
+    .bind(format!("+{REFRESH_TOKEN_DAYS} days"))
+    .fetch_one(pool)
+    .await?;
+
+    Ok(SessionTokens {
+        access_token: access_token(user_id, username, session_id)?,
+        refresh_token,
+    })
+}
+
+/// Exchanges a refresh token for a new token pair. The old refresh token
+/// stops working; presenting it again later revokes the whole session.
+pub async fn rotate_session(
+    pool: &SqlitePool,
+    refresh_token: &str,
+    client: &ClientInfo,
+) -> Result<(i64, String, SessionTokens), PaymeError> {
+    let presented = hash_token(refresh_token);
+    let session: (i64, i64, String, bool, bool) = sqlx::query_as(
+        r#"
+        SELECT s.id, s.user_id, u.username, s.token_hash = ?,
+               COALESCE(s.rotated_at >= datetime('now', ?), 0)
+        FROM sessions s
+        JOIN users u ON s.user_id = u.id
+        WHERE (s.token_hash = ? OR s.previous_token_hash = ?)
+            AND s.revoked_at IS NULL
+            AND s.expires_at > datetime('now')
+        "#,
+    )
+    .bind(&presented)
+    .bind(format!("-{REUSE_GRACE_SECONDS} seconds"))
+    .bind(&presented)
+    .bind(&presented)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::Unauthorized)?;
+    let (session_id, user_id, username, is_current, recently_rotated) = session;
+
+    if !is_current {
+        if !recently_rotated {
+            tracing::warn!("Refresh token reused for session {session_id}; revoking it");
+            revoke_session(pool, user_id, session_id).await?;
+        }
+        return Err(PaymeError::Unauthorized);
+    }
+
+    let new_token = random_token();
+    let rotated = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET previous_token_hash = token_hash, token_hash = ?, rotated_at = datetime('now'),
+            last_seen_at = datetime('now'), expires_at = datetime('now', ?),
+            user_agent = COALESCE(?, user_agent), ip = COALESCE(?, ip)
+        WHERE id = ? AND token_hash = ?
+        "#,
+    )
+    .bind(hash_token(&new_token))
+    .bind(format!("+{REFRESH_TOKEN_DAYS} days"))
+    .bind(&client.user_agent)
+    .bind(&client.ip)
+    .bind(session_id)
+    .bind(&presented)
+    .execute(pool)
+    .await?;
+    if rotated.rows_affected() == 0 {
+        return Err(PaymeError::Unauthorized);
+    }
+
+    let tokens = SessionTokens {
+        access_token: access_token(user_id, &username, session_id)?,
+        refresh_token: new_token,
+    };
+    Ok((user_id, username, tokens))
+}
+
+/// Revokes one of the user's sessions, returning whether it was active.
+pub async fn revoke_session(
+    pool: &SqlitePool,
+    user_id: i64,
+    session_id: i64,
+) -> Result<bool, PaymeError> {
+    let result = sqlx::query(
+        "UPDATE sessions SET revoked_at = datetime('now') WHERE id = ? AND user_id = ? AND revoked_at IS NULL",
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revokes every session the user has open.
+pub async fn revoke_all_sessions(pool: &SqlitePool, user_id: i64) -> Result<(), PaymeError> {
+    sqlx::query(
+        "UPDATE sessions SET revoked_at = datetime('now') WHERE user_id = ? AND revoked_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn access_token(user_id: i64, username: &str, session_id: i64) -> Result<String, PaymeError> {
+    let claims = Claims {
+        sub: user_id,
+        username: username.to_string(),
+        exp: (Utc::now() + Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
+        sid: Some(session_id),
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret().as_bytes()),
+    )
+    .map_err(|e| PaymeError::Internal(e.to_string()))
+}
+
+/// 32 random bytes, hex encoded.
+pub(crate) fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    responses(
+        (status = 200, body = [Session]),
+        (status = 401, description = "Not authenticated"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "List sessions",
+    description = "Lists the devices signed in to the account, most recently active first."
+)]
+pub async fn list_sessions(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<Session>>, PaymeError> {
+    let sessions: Vec<Session> = sqlx::query_as(
+        r#"
+        SELECT id, user_agent, ip, created_at, last_seen_at, expires_at, id IS ? AS current
+        FROM sessions
+        WHERE user_id = ? AND revoked_at IS NULL AND expires_at > datetime('now')
+        ORDER BY last_seen_at DESC, id DESC
+        "#,
+    )
+    .bind(claims.sid)
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(sessions))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{id}",
+    params(("id" = i64, Path, description = "Session ID")),
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 404, description = "Session not found or already revoked"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Revoke session",
+    description = "Signs a device out. Its refresh token stops working at once and its access token is rejected on the next request."
+)]
+pub async fn delete_session(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    if !revoke_session(&pool, claims.sub, id).await? {
+        return Err(PaymeError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions",
+    responses(
+        (status = 204, description = "All sessions revoked"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Revoke all sessions",
+    description = "Signs every device out, including the one making the request."
+)]
+pub async fn delete_all_sessions(
+    State(pool): State<SqlitePool>,
+    jar: CookieJar,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<impl IntoResponse, PaymeError> {
+    revoke_all_sessions(&pool, claims.sub).await?;
+    Ok((StatusCode::NO_CONTENT, clear_cookies(jar)))
+}
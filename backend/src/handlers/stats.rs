@@ -2,8 +2,9 @@ use axum::{extract::State, Json};
 use sqlx::SqlitePool;
 
 use crate::error::PaymeError;
+use crate::handlers::items::CATEGORY_SPENDING;
 use crate::middleware::auth::Claims;
-use crate::models::{CategoryStats, MonthlyStats, StatsResponse};
+use crate::models::{CategoryStats, Money, MonthlyStats, StatsResponse};
 
 #[utoipa::path(
     get,
@@ -31,38 +32,42 @@ pub async fn get_stats(
         return Ok(Json(StatsResponse {
             category_comparisons: vec![],
             monthly_trends: vec![],
-            average_monthly_spending: 0.0,
-            average_monthly_income: 0.0,
+            average_monthly_spending: Money::ZERO,
+            average_monthly_income: Money::ZERO,
         }));
     }
 
     let mut monthly_trends: Vec<MonthlyStats> = vec![];
-    let mut total_spending = 0.0;
-    let mut total_income_all = 0.0;
+    let mut total_spending = Money::ZERO;
+    let mut total_income_all = Money::ZERO;
 
     for (month_id, year, month) in &months {
-        let income: (f64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(amount), 0.0) FROM income_entries WHERE month_id = ?",
+        let income: (Money,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(amount), 0) FROM income_entries WHERE month_id = ?",
         )
         .bind(month_id)
         .fetch_one(&pool)
         .await?;
 
-        let spent: (f64,) =
-            sqlx::query_as("SELECT COALESCE(SUM(amount), 0.0) FROM items WHERE month_id = ?")
+        let spent: (Money,) =
+            sqlx::query_as("SELECT COALESCE(SUM(amount), 0) FROM items WHERE month_id = ?")
                 .bind(month_id)
                 .fetch_one(&pool)
                 .await?;
 
-        let fixed: (f64,) = sqlx::query_as(
-            "SELECT COALESCE(SUM(amount), 0.0) FROM fixed_expenses WHERE user_id = ?",
+        let fixed: (Money,) = sqlx::query_as(
+            "SELECT COALESCE(SUM(amount), 0) FROM monthly_fixed_expenses WHERE month_id = ?",
         )
-        .bind(claims.sub)
+        .bind(month_id)
         .fetch_one(&pool)
         .await?;
 
-        total_spending += spent.0;
-        total_income_all += income.0;
+        total_spending = total_spending
+            .checked_add(spent.0)
+            .ok_or(PaymeError::AmountOutOfRange)?;
+        total_income_all = total_income_all
+            .checked_add(income.0)
+            .ok_or(PaymeError::AmountOutOfRange)?;
 
         monthly_trends.push(MonthlyStats {
             year: *year,
@@ -70,21 +75,22 @@ pub async fn get_stats(
             total_income: income.0,
             total_spent: spent.0,
             total_fixed: fixed.0,
-            net: income.0 - fixed.0 - spent.0,
+            net: income
+                .0
+                .checked_sub(fixed.0)
+                .and_then(|m| m.checked_sub(spent.0))
+                .ok_or(PaymeError::AmountOutOfRange)?,
         });
     }
 
-    let month_count = months.len() as f64;
-    let average_monthly_spending = if month_count > 0.0 {
-        total_spending / month_count
-    } else {
-        0.0
-    };
-    let average_monthly_income = if month_count > 0.0 {
-        total_income_all / month_count
-    } else {
-        0.0
-    };
+    let month_count = months.len() as i64;
+    // With no months there is nothing to average.
+    let average_monthly_spending = total_spending
+        .checked_div_rounded(month_count)
+        .unwrap_or(Money::ZERO);
+    let average_monthly_income = total_income_all
+        .checked_div_rounded(month_count)
+        .unwrap_or(Money::ZERO);
 
     let mut category_comparisons: Vec<CategoryStats> = vec![];
 
@@ -99,30 +105,30 @@ pub async fn get_stats(
                 .await?;
 
         for (cat_id, cat_label) in categories {
-            let current_spent: (f64,) = sqlx::query_as(
-                "SELECT COALESCE(SUM(amount), 0.0) FROM items WHERE month_id = ? AND category_id = ?",
-            )
+            let current_spent: (Money,) = sqlx::query_as(&format!(
+                "SELECT COALESCE(SUM(amount), 0) FROM ({CATEGORY_SPENDING}) WHERE month_id = ? AND category_id = ?"
+            ))
             .bind(current_month_id)
             .bind(cat_id)
             .fetch_one(&pool)
             .await?;
 
-            let previous_spent: f64 = if let Some(prev_id) = previous_month_id {
-                let result: (f64,) = sqlx::query_as(
-                    "SELECT COALESCE(SUM(amount), 0.0) FROM items WHERE month_id = ? AND category_id = ?",
-                )
+            let previous_spent: Money = if let Some(prev_id) = previous_month_id {
+                let result: (Money,) = sqlx::query_as(&format!(
+                    "SELECT COALESCE(SUM(amount), 0) FROM ({CATEGORY_SPENDING}) WHERE month_id = ? AND category_id = ?"
+                ))
                 .bind(prev_id)
                 .bind(cat_id)
                 .fetch_one(&pool)
                 .await?;
                 result.0
             } else {
-                0.0
+                Money::ZERO
             };
 
             let change_amount = current_spent.0 - previous_spent;
-            let change_percent = if previous_spent > 0.0 {
-                Some((change_amount / previous_spent) * 100.0)
+            let change_percent = if previous_spent > Money::ZERO {
+                Some(change_amount.minor() as f64 / previous_spent.minor() as f64 * 100.0)
             } else {
                 None
             };
@@ -0,0 +1,208 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::middleware::auth::Claims;
+use crate::models::{Tag, TagSpending};
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveTag {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TagReportQuery {
+    /// First day of the range, inclusive.
+    pub from: NaiveDate,
+    /// Last day of the range, inclusive.
+    pub to: NaiveDate,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags",
+    responses(
+        (status = 200, body = [Tag]),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "List tags",
+    description = "Retrieves the user's tags in alphabetical order."
+)]
+pub async fn list_tags(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<Tag>>, PaymeError> {
+    let tags: Vec<Tag> =
+        sqlx::query_as("SELECT id, user_id, name FROM tags WHERE user_id = ? ORDER BY name")
+            .bind(claims.sub)
+            .fetch_all(&pool)
+            .await?;
+
+    Ok(Json(tags))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tags",
+    request_body = SaveTag,
+    responses(
+        (status = 200, body = Tag),
+        (status = 400, description = "Invalid name"),
+        (status = 409, description = "Tag already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Create tag",
+    description = "Adds a tag that items can then carry alongside their category."
+)]
+pub async fn create_tag(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<SaveTag>,
+) -> Result<Json<Tag>, PaymeError> {
+    payload.validate()?;
+
+    let id: i64 = sqlx::query_scalar("INSERT INTO tags (user_id, name) VALUES (?, ?) RETURNING id")
+        .bind(claims.sub)
+        .bind(&payload.name)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| duplicate_tag(e, &payload.name))?;
+
+    Ok(Json(Tag {
+        id,
+        user_id: claims.sub,
+        name: payload.name,
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/tags/{id}",
+    params(("id" = i64, Path, description = "Tag ID")),
+    request_body = SaveTag,
+    responses(
+        (status = 200, body = Tag),
+        (status = 400, description = "Invalid name"),
+        (status = 404, description = "Tag not found"),
+        (status = 409, description = "Tag already exists"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Configuration",
+    summary = "Rename tag",
+    description = "Renames a tag on every item that carries it."
+)]
+pub async fn update_tag(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+    Json(payload): Json<SaveTag>,
+) -> Result<Json<Tag>, PaymeError> {
+    payload.validate()?;
+
+    let result = sqlx::query("UPDATE tags SET name = ? WHERE id = ? AND user_id = ?")
+        .bind(&payload.name)
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await
+        .map_err(|e| duplicate_tag(e, &payload.name))?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(Tag {
+        id,
+        user_id: claims.sub,
+        name: payload.name,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tags/{id}",
+    params(("id" = i64, Path, description = "Tag ID")),
+    responses((status = 204, description = "Deleted")),
+    tag = "Configuration",
+    summary = "Delete tag",
+    description = "Removes a tag from the user's items and deletes it. The items are kept."
+)]
+pub async fn delete_tag(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, PaymeError> {
+    sqlx::query("DELETE FROM tags WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(claims.sub)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/tags/report",
+    params(TagReportQuery),
+    responses(
+        (status = 200, body = [TagSpending]),
+        (status = 400, description = "Invalid date range"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Insights",
+    summary = "Spending by tag",
+    description = "Totals the items spent between two dates, inclusive, for each of the user's tags, whichever months they fall in. Largest totals come first."
+)]
+pub async fn tag_report(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Query(query): Query<TagReportQuery>,
+) -> Result<Json<Vec<TagSpending>>, PaymeError> {
+    if query.from > query.to {
+        return Err(PaymeError::BadRequest(
+            "from must not be after to".to_string(),
+        ));
+    }
+
+    let report: Vec<TagSpending> = sqlx::query_as(
+        r#"
+        SELECT t.id AS tag_id, t.name,
+            COALESCE(SUM(i.amount), 0) AS total, COUNT(i.id) AS item_count
+        FROM tags t
+        LEFT JOIN item_tags it ON it.tag_id = t.id
+        LEFT JOIN items i ON i.id = it.item_id AND i.spent_on BETWEEN ? AND ?
+        WHERE t.user_id = ?
+        GROUP BY t.id
+        ORDER BY total DESC, t.name
+        "#,
+    )
+    .bind(query.from)
+    .bind(query.to)
+    .bind(claims.sub)
+    .fetch_all(&pool)
+    .await?;
+
+    Ok(Json(report))
+}
+
+fn duplicate_tag(error: sqlx::Error, name: &str) -> PaymeError {
+    match error {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            PaymeError::Conflict(format!("Tag {name} already exists"))
+        }
+        e => e.into(),
+    }
+}
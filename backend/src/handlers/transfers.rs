@@ -0,0 +1,217 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::handlers::accounts::verify_account;
+use crate::handlers::items::{verify_month_access, verify_month_not_closed};
+use crate::middleware::auth::Claims;
+use crate::models::{Money, Transfer, TransferWithAccounts};
+
+/// Selects a month's [`TransferWithAccounts`]; binds the month id.
+pub(crate) const TRANSFERS_IN_MONTH: &str = r#"
+    SELECT t.id, t.month_id, t.from_account_id, fa.name AS from_account_name,
+        t.to_account_id, ta.name AS to_account_name, t.amount, t.transferred_on
+    FROM transfers t
+    JOIN accounts fa ON t.from_account_id = fa.id
+    JOIN accounts ta ON t.to_account_id = ta.id
+    WHERE t.month_id = ?
+    ORDER BY t.transferred_on DESC, t.id
+"#;
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct SaveTransfer {
+    pub from_account_id: i64,
+    pub to_account_id: i64,
+    #[validate(range(exclusive_min = Money::ZERO))]
+    pub amount: Money,
+    /// Must fall within the month the transfer is recorded in.
+    pub transferred_on: NaiveDate,
+}
+
+impl SaveTransfer {
+    async fn check(
+        &self,
+        pool: &SqlitePool,
+        user_id: i64,
+        (year, month): (i32, i32),
+    ) -> Result<(), PaymeError> {
+        self.validate()?;
+        if (
+            self.transferred_on.year(),
+            self.transferred_on.month() as i32,
+        ) != (year, month)
+        {
+            return Err(PaymeError::BadRequest(format!(
+                "transferred_on must be in {year}-{month:02}"
+            )));
+        }
+        if self.from_account_id == self.to_account_id {
+            return Err(PaymeError::BadRequest(
+                "Cannot transfer to the same account".to_string(),
+            ));
+        }
+        verify_account(pool, user_id, Some(self.from_account_id)).await?;
+        verify_account(pool, user_id, Some(self.to_account_id)).await
+    }
+}
+
+#[utoipa::path(
+    get, path = "/api/months/{id}/transfers",
+    params(("id" = i64, Path)),
+    responses(
+        (status = 200, body = [TransferWithAccounts]),
+        (status = 404, description = "Month not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Transfers",
+    summary = "List transfers",
+    description = "Retrieves the money moved between the user's accounts during the month, with account names."
+)]
+pub async fn list_transfers(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(month_id): Path<i64>,
+) -> Result<Json<Vec<TransferWithAccounts>>, PaymeError> {
+    verify_month_access(&pool, claims.sub, month_id).await?;
+
+    let transfers: Vec<TransferWithAccounts> = sqlx::query_as(TRANSFERS_IN_MONTH)
+        .bind(month_id)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Json(transfers))
+}
+
+#[utoipa::path(
+    post, path = "/api/months/{id}/transfers",
+    params(("id" = i64, Path)),
+    request_body = SaveTransfer,
+    responses(
+        (status = 200, body = Transfer),
+        (status = 400, description = "Month is closed, invalid accounts, a date outside the month, or an amount that is not positive"),
+        (status = 404, description = "Month not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Transfers",
+    summary = "Record transfer",
+    description = "Moves money from one account to another. Both balances change, but the transfer is not counted as spending."
+)]
+pub async fn create_transfer(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path(month_id): Path<i64>,
+    Json(payload): Json<SaveTransfer>,
+) -> Result<Json<Transfer>, PaymeError> {
+    let period = verify_month_not_closed(&pool, claims.sub, month_id).await?;
+    payload.check(&pool, claims.sub, period).await?;
+
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO transfers (month_id, from_account_id, to_account_id, amount, transferred_on) VALUES (?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(month_id)
+    .bind(payload.from_account_id)
+    .bind(payload.to_account_id)
+    .bind(payload.amount)
+    .bind(payload.transferred_on)
+    .fetch_one(&pool)
+    .await?;
+
+    Ok(Json(transfer_from(id, month_id, payload)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/months/{month_id}/transfers/{id}",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("id" = i64, Path, description = "Transfer ID")
+    ),
+    request_body = SaveTransfer,
+    responses(
+        (status = 200, body = Transfer),
+        (status = 400, description = "Month is closed, invalid accounts, a date outside the month, or an amount that is not positive"),
+        (status = 404, description = "Transfer not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Transfers",
+    summary = "Update transfer",
+    description = "Replaces the accounts, amount and date of a transfer."
+)]
+pub async fn update_transfer(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, id)): Path<(i64, i64)>,
+    Json(payload): Json<SaveTransfer>,
+) -> Result<Json<Transfer>, PaymeError> {
+    let period = verify_month_not_closed(&pool, claims.sub, month_id).await?;
+    payload.check(&pool, claims.sub, period).await?;
+
+    let result = sqlx::query(
+        "UPDATE transfers SET from_account_id = ?, to_account_id = ?, amount = ?, transferred_on = ? WHERE id = ? AND month_id = ?",
+    )
+    .bind(payload.from_account_id)
+    .bind(payload.to_account_id)
+    .bind(payload.amount)
+    .bind(payload.transferred_on)
+    .bind(id)
+    .bind(month_id)
+    .execute(&pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(PaymeError::NotFound);
+    }
+
+    Ok(Json(transfer_from(id, month_id, payload)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/months/{month_id}/transfers/{id}",
+    params(
+        ("month_id" = i64, Path, description = "Month ID"),
+        ("id" = i64, Path, description = "Transfer ID")
+    ),
+    responses(
+        (status = 204, description = "Transfer deleted successfully"),
+        (status = 400, description = "Month is closed"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Transfers",
+    summary = "Delete transfer",
+    description = "Removes a transfer, restoring both account balances."
+)]
+pub async fn delete_transfer(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Path((month_id, id)): Path<(i64, i64)>,
+) -> Result<StatusCode, PaymeError> {
+    verify_month_not_closed(&pool, claims.sub, month_id).await?;
+
+    sqlx::query("DELETE FROM transfers WHERE id = ? AND month_id = ?")
+        .bind(id)
+        .bind(month_id)
+        .execute(&pool)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn transfer_from(id: i64, month_id: i64, transfer: SaveTransfer) -> Transfer {
+    Transfer {
+        id,
+        month_id,
+        from_account_id: transfer.from_account_id,
+        to_account_id: transfer.to_account_id,
+        amount: transfer.amount,
+        transferred_on: transfer.transferred_on,
+    }
+}
@@ -0,0 +1,410 @@
+use argon2::{
+    password_hash::rand_core::{OsRng, RngCore},
+    Argon2, PasswordHash, PasswordVerifier,
+};
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::error::PaymeError;
+use crate::handlers::sessions::{hash_token, random_token};
+use crate::middleware::auth::Claims;
+use crate::totp::{self, Clock};
+
+/// How long a login challenge can be answered for.
+pub const CHALLENGE_MINUTES: i64 = 5;
+
+/// Wrong codes allowed per login challenge before it stops working.
+const MAX_CHALLENGE_ATTEMPTS: i64 = 5;
+
+/// Consecutive wrong codes, across challenges, that lock the second step.
+const FAILURES_BEFORE_LOCKOUT: i64 = 5;
+
+/// How long the second step stays locked after too many wrong codes.
+pub const LOCKOUT_MINUTES: i64 = 15;
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Unused recovery codes left.
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorSetup {
+    /// Base32 secret, for entering into an authenticator app by hand.
+    pub secret: String,
+    /// `otpauth://` URI, usually shown as a QR code.
+    pub provisioning_uri: String,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct ConfirmTwoFactorRequest {
+    /// The current code from the authenticator app.
+    #[validate(length(equal = 6))]
+    pub code: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodes {
+    /// One-time codes for signing in without the authenticator app. They
+    /// cannot be retrieved again.
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct DisableTwoFactorRequest {
+    #[validate(length(min = 1))]
+    pub password: String,
+}
+
+/// Returned by login instead of tokens when the account has a second factor.
+#[derive(Serialize, ToSchema)]
+pub struct LoginChallenge {
+    /// Pass to `/api/auth/login/verify` along with a code.
+    pub challenge_token: String,
+    /// Seconds until the challenge expires.
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, ToSchema, Validate)]
+pub struct VerifyLoginRequest {
+    pub challenge_token: String,
+    /// A code from the authenticator app or an unused recovery code.
+    #[validate(length(min = 6, max = 64))]
+    pub code: String,
+    /// Also return the refresh token in the response body, as at login.
+    #[serde(default)]
+    pub include_refresh_token: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/2fa",
+    responses(
+        (status = 200, body = TwoFactorStatus),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Get two-factor status",
+    description = "Reports whether a second factor is required at login and how many recovery codes are left."
+)]
+pub async fn get_two_factor(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<TwoFactorStatus>, PaymeError> {
+    let (enabled, recovery_codes_remaining): (bool, i64) = sqlx::query_as(
+        r#"
+        SELECT totp_enabled_at IS NOT NULL,
+               (SELECT COUNT(*) FROM recovery_codes WHERE user_id = users.id AND used_at IS NULL)
+        FROM users WHERE id = ?
+        "#,
+    )
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or(PaymeError::NotFound)?;
+
+    Ok(Json(TwoFactorStatus {
+        enabled,
+        recovery_codes_remaining,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/setup",
+    responses(
+        (status = 200, description = "Enrollment started", body = TwoFactorSetup),
+        (status = 409, description = "Two-factor authentication is already enabled"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Start two-factor enrollment",
+    description = "Generates a new TOTP secret. It takes effect once a code from it is sent to `/api/auth/2fa/confirm`; starting again replaces an unconfirmed secret."
+)]
+pub async fn setup_two_factor(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<TwoFactorSetup>, PaymeError> {
+    let secret = totp::generate_secret();
+    let username: String = sqlx::query_scalar(
+        "UPDATE users SET totp_secret = ?, totp_last_step = NULL WHERE id = ? AND totp_enabled_at IS NULL RETURNING username",
+    )
+    .bind(&secret)
+    .bind(claims.sub)
+    .fetch_optional(&pool)
+    .await?
+    .ok_or_else(|| {
+        PaymeError::Conflict("Two-factor authentication is already enabled".to_string())
+    })?;
+
+    let provisioning_uri = totp::provisioning_uri(&secret, &username)
+        .ok_or_else(|| PaymeError::Internal("Invalid TOTP secret".to_string()))?;
+    Ok(Json(TwoFactorSetup {
+        secret,
+        provisioning_uri,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/2fa/confirm",
+    request_body = ConfirmTwoFactorRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = RecoveryCodes),
+        (status = 400, description = "Enrollment not started or the code is wrong"),
+        (status = 409, description = "Two-factor authentication is already enabled"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Confirm two-factor enrollment",
+    description = "Checks a code from the new secret and turns the second factor on. Returns ten one-time recovery codes, shown only this once."
+)]
+pub async fn confirm_two_factor(
+    State(pool): State<SqlitePool>,
+    axum::Extension(clock): axum::Extension<Clock>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<ConfirmTwoFactorRequest>,
+) -> Result<Json<RecoveryCodes>, PaymeError> {
+    payload.validate()?;
+
+    let (secret, enabled): (Option<String>, bool) =
+        sqlx::query_as("SELECT totp_secret, totp_enabled_at IS NOT NULL FROM users WHERE id = ?")
+            .bind(claims.sub)
+            .fetch_optional(&pool)
+            .await?
+            .ok_or(PaymeError::NotFound)?;
+    if enabled {
+        return Err(PaymeError::Conflict(
+            "Two-factor authentication is already enabled".to_string(),
+        ));
+    }
+    let secret = secret.ok_or_else(|| {
+        PaymeError::BadRequest("Two-factor enrollment has not been started".to_string())
+    })?;
+    let step = totp::verify(&secret, &payload.code, clock.now(), None)
+        .ok_or_else(|| PaymeError::BadRequest("Invalid verification code".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+    let enabled = sqlx::query(
+        "UPDATE users SET totp_enabled_at = datetime('now'), totp_last_step = ? WHERE id = ? AND totp_secret = ? AND totp_enabled_at IS NULL",
+    )
+    .bind(step)
+    .bind(claims.sub)
+    .bind(&secret)
+    .execute(&mut *tx)
+    .await?;
+    if enabled.rows_affected() == 0 {
+        return Err(PaymeError::Conflict(
+            "Two-factor enrollment changed; start again".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| recovery_code()).collect();
+    for code in &recovery_codes {
+        sqlx::query("INSERT INTO recovery_codes (user_id, code_hash) VALUES (?, ?)")
+            .bind(claims.sub)
+            .bind(hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/2fa",
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 401, description = "Invalid password"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Auth",
+    summary = "Disable two-factor authentication",
+    description = "Removes the TOTP secret and recovery codes after checking the password. Logins need only the password again."
+)]
+pub async fn disable_two_factor(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<DisableTwoFactorRequest>,
+) -> Result<StatusCode, PaymeError> {
+    payload.validate()?;
+
+    let user: (String,) = sqlx::query_as("SELECT password_hash FROM users WHERE id = ?")
+        .bind(claims.sub)
+        .fetch_optional(&pool)
+        .await?
+        .ok_or(PaymeError::NotFound)?;
+
+    let parsed_hash =
+        PasswordHash::new(&user.0).map_err(|e| PaymeError::Internal(e.to_string()))?;
+    Argon2::default()
+        .verify_password(payload.password.as_bytes(), &parsed_hash)
+        .map_err(|_| PaymeError::Unauthorized)?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL, totp_failures = 0, totp_locked_until = NULL WHERE id = ?",
+    )
+    .bind(claims.sub)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM login_challenges WHERE user_id = ?")
+        .bind(claims.sub)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Issues a challenge for a user whose password checked out, to be answered
+/// with a code.
+pub async fn start_challenge(
+    pool: &SqlitePool,
+    user_id: i64,
+) -> Result<LoginChallenge, PaymeError> {
+    sqlx::query("DELETE FROM login_challenges WHERE user_id = ? AND expires_at <= datetime('now')")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    let challenge_token = random_token();
+    sqlx::query(
+        "INSERT INTO login_challenges (user_id, token_hash, expires_at) VALUES (?, ?, datetime('now', ?))",
+    )
+    .bind(user_id)
+    .bind(hash_token(&challenge_token))
+    .bind(format!("+{CHALLENGE_MINUTES} minutes"))
+    .execute(pool)
+    .await?;
+
+    Ok(LoginChallenge {
+        challenge_token,
+        expires_in: CHALLENGE_MINUTES * 60,
+    })
+}
+
+/// Answers a login challenge with a TOTP code or a recovery code, returning
+/// the user's id and username. The challenge is used up on success and after
+/// too many wrong codes, and the user's second step locks for a while after
+/// too many wrong codes in a row, whichever challenges they came through.
+pub async fn complete_challenge(
+    pool: &SqlitePool,
+    challenge_token: &str,
+    code: &str,
+    now: u64,
+) -> Result<(i64, String), PaymeError> {
+    // Attempts are claimed before the code is checked, so parallel requests
+    // cannot all slip under the limits.
+    let (challenge_id, user_id): (i64, i64) = sqlx::query_as(
+        r#"
+        UPDATE login_challenges SET attempts = attempts + 1
+        WHERE token_hash = ? AND expires_at > datetime('now') AND attempts < ?
+        RETURNING id, user_id
+        "#,
+    )
+    .bind(hash_token(challenge_token))
+    .bind(MAX_CHALLENGE_ATTEMPTS)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::Unauthorized)?;
+
+    let user: Option<(String, Option<String>, Option<i64>, bool)> = sqlx::query_as(
+        r#"
+        UPDATE users
+        SET totp_failures = totp_failures + 1,
+            totp_locked_until = CASE WHEN totp_failures + 1 >= ?
+                THEN datetime('now', ?) ELSE NULL END
+        WHERE id = ? AND (totp_locked_until IS NULL OR totp_locked_until <= datetime('now'))
+        RETURNING username, totp_secret, totp_last_step, totp_enabled_at IS NOT NULL
+        "#,
+    )
+    .bind(FAILURES_BEFORE_LOCKOUT)
+    .bind(format!("+{LOCKOUT_MINUTES} minutes"))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((username, secret, last_step, enabled)) = user else {
+        return Err(PaymeError::TooManyRequests(
+            "Too many wrong codes; try again later".to_string(),
+        ));
+    };
+    if !enabled {
+        return Err(PaymeError::Unauthorized);
+    }
+
+    let code = code.trim();
+    let accepted = if code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()) {
+        match secret.and_then(|secret| totp::verify(&secret, code, now, last_step)) {
+            // Only one login can claim a step, even when two race.
+            Some(step) => sqlx::query(
+                "UPDATE users SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+            )
+            .bind(step)
+            .bind(user_id)
+            .bind(step)
+            .execute(pool)
+            .await?
+            .rows_affected()
+                > 0,
+            None => false,
+        }
+    } else {
+        sqlx::query(
+            "UPDATE recovery_codes SET used_at = datetime('now') WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+        )
+        .bind(user_id)
+        .bind(hash_token(&normalize_recovery_code(code)))
+        .execute(pool)
+        .await?
+        .rows_affected()
+            > 0
+    };
+
+    if !accepted {
+        return Err(PaymeError::Unauthorized);
+    }
+
+    sqlx::query("UPDATE users SET totp_failures = 0, totp_locked_until = NULL WHERE id = ?")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM login_challenges WHERE id = ?")
+        .bind(challenge_id)
+        .execute(pool)
+        .await?;
+    Ok((user_id, username))
+}
+
+/// 64 random bits as four groups of hex digits, e.g. `3f9a-07c2-b41e-d85a`.
+fn recovery_code() -> String {
+    let mut bytes = [0u8; 8];
+    OsRng.fill_bytes(&mut bytes);
+    let hex = hex::encode(bytes);
+    [&hex[0..4], &hex[4..8], &hex[8..12], &hex[12..16]].join("-")
+}
+
+/// Recovery codes are matched ignoring case, spaces and dashes.
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
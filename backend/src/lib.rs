@@ -1,3 +1,4 @@
+pub mod categorization;
 pub mod config;
 pub mod db;
 pub mod error;
@@ -6,42 +7,95 @@ pub mod middleware;
 pub mod models;
 pub mod openapi;
 pub mod pdf;
+pub mod schedule;
+pub mod statements;
+pub mod totp;
 
 use axum::{
-    middleware::from_fn,
+    extract::DefaultBodyLimit,
+    middleware::{from_fn, from_fn_with_state},
     routing::{delete, get, post, put},
-    Router,
+    Extension, Router,
 };
 use sqlx::SqlitePool;
 use tower_http::cors::{Any, CorsLayer};
 
 use handlers::{
-    auth, budget, export, fixed_expenses, health, income, items, months, savings, stats,
+    accounts, api_tokens, attachments, auth, budget, export, fixed_expenses, goals, health, import,
+    income, income_templates, items, months, recurring, rules, savings, sessions, stats, tags,
+    transfers, two_factor,
 };
-use middleware::auth::auth_middleware;
+use middleware::{auth::auth_middleware, errors::json_errors};
+use totp::Clock;
+
+/// Upload limit for SQLite imports, which carry closed-month PDF snapshots
+/// and item attachments.
+const IMPORT_BODY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Upload limit for attachments, leaving room for the multipart framing.
+const ATTACHMENT_BODY_LIMIT: usize = attachments::MAX_ATTACHMENT_SIZE + 64 * 1024;
 
 /// Create the application router with all routes
 pub fn create_app(pool: SqlitePool) -> Router {
+    create_app_with_clock(pool, Clock::system())
+}
+
+/// Like [`create_app`], but checks one-time codes against `clock`.
+pub fn create_app_with_clock(pool: SqlitePool, clock: Clock) -> Router {
     let public_routes = Router::new()
         .route("/health", get(health::health_check))
         .route("/api/auth/register", post(auth::register))
-        .route("/api/auth/login", post(auth::login));
+        .route("/api/auth/login", post(auth::login))
+        .route("/api/auth/login/verify", post(auth::verify_login))
+        .route("/api/auth/refresh", post(auth::refresh));
 
     let protected_routes = Router::new()
         .route("/api/auth/logout", post(auth::logout))
         .route("/api/auth/me", get(auth::me))
+        .route("/api/auth/sessions", get(sessions::list_sessions))
+        .route("/api/auth/sessions", delete(sessions::delete_all_sessions))
+        .route("/api/auth/sessions/{id}", delete(sessions::delete_session))
+        .route("/api/auth/tokens", get(api_tokens::list_api_tokens))
+        .route("/api/auth/tokens", post(api_tokens::create_api_token))
+        .route(
+            "/api/auth/tokens/{id}",
+            delete(api_tokens::delete_api_token),
+        )
+        .route("/api/auth/2fa", get(two_factor::get_two_factor))
+        .route("/api/auth/2fa", delete(two_factor::disable_two_factor))
+        .route("/api/auth/2fa/setup", post(two_factor::setup_two_factor))
+        .route(
+            "/api/auth/2fa/confirm",
+            post(two_factor::confirm_two_factor),
+        )
         .route("/api/auth/change-username", put(auth::change_username))
         .route("/api/auth/change-password", put(auth::change_password))
+        .route("/api/auth/change-currency", put(auth::change_currency))
         .route("/api/auth/clear-data", delete(auth::clear_all_data))
-        .route("/api/export", get(auth::export_db))
+        .route("/api/export", get(export::export_sqlite))
+        .route(
+            "/api/import",
+            post(export::import_sqlite).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
         .route("/api/months", get(months::list_months))
+        .route("/api/months", post(months::create_month))
+        .route("/api/months/bulk", post(months::create_months))
         .route(
             "/api/months/current",
             get(months::get_or_create_current_month),
         )
         .route("/api/months/{id}", get(months::get_month))
         .route("/api/months/{id}/close", post(months::close_month))
+        .route("/api/months/{id}/reopen", post(months::reopen_month))
         .route("/api/months/{id}/pdf", get(months::get_month_pdf))
+        .route(
+            "/api/months/{id}/snapshots",
+            get(months::list_month_snapshots),
+        )
+        .route(
+            "/api/months/{id}/snapshots/{version}",
+            get(months::get_month_snapshot),
+        )
         .route(
             "/api/fixed-expenses",
             get(fixed_expenses::list_fixed_expenses),
@@ -58,6 +112,35 @@ pub fn create_app(pool: SqlitePool) -> Router {
             "/api/fixed-expenses/{id}",
             delete(fixed_expenses::delete_fixed_expense),
         )
+        .route(
+            "/api/income-templates",
+            get(income_templates::list_income_templates),
+        )
+        .route(
+            "/api/income-templates",
+            post(income_templates::create_income_template),
+        )
+        .route(
+            "/api/income-templates/{id}",
+            put(income_templates::update_income_template),
+        )
+        .route(
+            "/api/income-templates/{id}",
+            delete(income_templates::delete_income_template),
+        )
+        .route("/api/recurring", get(recurring::list_recurring))
+        .route("/api/recurring", post(recurring::create_recurring))
+        .route("/api/recurring/upcoming", get(recurring::list_upcoming))
+        .route("/api/recurring/{id}", put(recurring::update_recurring))
+        .route("/api/recurring/{id}", delete(recurring::delete_recurring))
+        .route(
+            "/api/recurring/{id}/occurrences/{date}",
+            put(recurring::update_occurrence),
+        )
+        .route(
+            "/api/recurring/{id}/occurrences/{date}",
+            delete(recurring::restore_occurrence),
+        )
         .route("/api/categories", get(budget::list_categories))
         .route("/api/categories", post(budget::create_category))
         .route("/api/categories/{id}", put(budget::update_category))
@@ -80,6 +163,7 @@ pub fn create_app(pool: SqlitePool) -> Router {
             "/api/months/{month_id}/income/{id}",
             delete(income::delete_income),
         )
+        .route("/api/items/search", get(items::search_items))
         .route("/api/months/{id}/items", get(items::list_items))
         .route("/api/months/{id}/items", post(items::create_item))
         .route("/api/months/{month_id}/items/{id}", put(items::update_item))
@@ -87,10 +171,77 @@ pub fn create_app(pool: SqlitePool) -> Router {
             "/api/months/{month_id}/items/{id}",
             delete(items::delete_item),
         )
+        .route(
+            "/api/months/{month_id}/items/{id}/attachments",
+            get(attachments::list_attachments),
+        )
+        .route(
+            "/api/months/{month_id}/items/{id}/attachments",
+            post(attachments::upload_attachment)
+                .layer(DefaultBodyLimit::max(ATTACHMENT_BODY_LIMIT)),
+        )
+        .route(
+            "/api/months/{month_id}/items/{item_id}/attachments/{id}",
+            get(attachments::get_attachment),
+        )
+        .route(
+            "/api/months/{month_id}/items/{item_id}/attachments/{id}",
+            delete(attachments::delete_attachment),
+        )
+        .route("/api/months/{id}/transfers", get(transfers::list_transfers))
+        .route(
+            "/api/months/{id}/transfers",
+            post(transfers::create_transfer),
+        )
+        .route(
+            "/api/months/{month_id}/transfers/{id}",
+            put(transfers::update_transfer),
+        )
+        .route(
+            "/api/months/{month_id}/transfers/{id}",
+            delete(transfers::delete_transfer),
+        )
+        .route(
+            "/api/months/{id}/rules/apply",
+            post(rules::apply_rules_to_month),
+        )
+        .route("/api/rules", get(rules::list_rules))
+        .route("/api/rules", post(rules::create_rule))
+        .route("/api/rules/{id}", put(rules::update_rule))
+        .route("/api/rules/{id}", delete(rules::delete_rule))
         .route("/api/stats", get(stats::get_stats))
+        .route("/api/tags", get(tags::list_tags))
+        .route("/api/tags", post(tags::create_tag))
+        .route("/api/tags/report", get(tags::tag_report))
+        .route("/api/tags/{id}", put(tags::update_tag))
+        .route("/api/tags/{id}", delete(tags::delete_tag))
+        .route("/api/accounts", get(accounts::list_accounts))
+        .route("/api/accounts", post(accounts::create_account))
+        .route("/api/accounts/{id}", put(accounts::update_account))
+        .route("/api/accounts/{id}", delete(accounts::delete_account))
         .route("/api/savings", get(savings::get_savings))
         .route("/api/savings", put(savings::update_savings))
         .route("/api/savings/goal", put(savings::update_savings_goal))
+        .route("/api/savings/entries", get(savings::list_ledger_entries))
+        .route("/api/savings/entries", post(savings::create_ledger_entry))
+        .route(
+            "/api/savings/entries/{id}",
+            delete(savings::delete_ledger_entry),
+        )
+        .route("/api/savings/history", get(savings::get_savings_history))
+        .route("/api/goals", get(goals::list_goals))
+        .route("/api/goals", post(goals::create_goal))
+        .route("/api/goals/{id}", put(goals::update_goal))
+        .route("/api/goals/{id}", delete(goals::delete_goal))
+        .route("/api/goals/{id}/allocations", get(goals::list_allocations))
+        .route(
+            "/api/goals/{id}/allocations",
+            post(goals::create_allocation),
+        )
+        .route(
+            "/api/goals/{goal_id}/allocations/{id}",
+            delete(goals::delete_allocation),
+        )
         .route(
             "/api/retirement-savings",
             get(savings::get_retirement_savings),
@@ -101,7 +252,21 @@ pub fn create_app(pool: SqlitePool) -> Router {
         )
         .route("/api/export/json", get(export::export_json))
         .route("/api/import/json", post(export::import_json))
-        .layer(from_fn(auth_middleware));
+        .route("/api/import/csv", post(import::import_csv))
+        .route("/api/import/csv/preview", post(import::preview_csv_import))
+        .route("/api/import/ofx", post(import::import_ofx))
+        .route("/api/import/ofx/preview", post(import::preview_ofx_import))
+        .route("/api/import/csv/profiles", get(import::list_csv_profiles))
+        .route("/api/import/csv/profiles", post(import::create_csv_profile))
+        .route(
+            "/api/import/csv/profiles/{id}",
+            put(import::update_csv_profile),
+        )
+        .route(
+            "/api/import/csv/profiles/{id}",
+            delete(import::delete_csv_profile),
+        )
+        .layer(from_fn_with_state(pool.clone(), auth_middleware));
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -112,6 +277,8 @@ pub fn create_app(pool: SqlitePool) -> Router {
     Router::new()
         .merge(public_routes)
         .merge(protected_routes)
+        .layer(Extension(clock))
+        .layer(from_fn(json_errors))
         .layer(cors)
         .with_state(pool)
 }
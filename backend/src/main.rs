@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use tower_http::services::ServeDir;
 
 use payme::config::Config;
@@ -15,9 +17,15 @@ async fn main() {
         .await
         .expect("Failed to create database pool");
 
-    db::run_migrations(&pool)
-        .await
-        .expect("Failed to run migrations");
+    if std::env::args().nth(1).as_deref() == Some("migrations") {
+        print_migration_status(&pool).await;
+        return;
+    }
+
+    if let Err(e) = db::run_migrations(&pool).await {
+        tracing::error!("{e}");
+        std::process::exit(1);
+    }
 
     let app = create_app(pool)
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()))
@@ -30,10 +38,34 @@ async fn main() {
         .await
         .expect("Failed to bind to address");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .expect("Server error");
+}
+
+async fn print_migration_status(pool: &sqlx::SqlitePool) {
+    let status = db::migration_status(pool)
         .await
-        .expect("Server error");
+        .expect("Failed to read migration status");
+
+    println!(
+        "Schema version supported by this binary: {}",
+        db::latest_version()
+    );
+    for migration in status {
+        let state = match &migration.applied_at {
+            Some(applied_at) if migration.version > db::latest_version() => {
+                format!("applied {applied_at} (unknown to this binary)")
+            }
+            Some(applied_at) => format!("applied {applied_at}"),
+            None => "pending".to_string(),
+        };
+        println!("{:>4}  {:<24} {}", migration.version, migration.name, state);
+    }
 }
 
 async fn shutdown_signal() {
@@ -1,18 +1,34 @@
-use axum::{extract::Request, middleware::Next, response::Response};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
 use axum_extra::extract::CookieJar;
 use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 
 use crate::error::PaymeError;
+use crate::handlers::api_tokens::{authenticate_api_token, API_TOKEN_PREFIX};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: i64,
     pub username: String,
     pub exp: usize,
+    /// Session the token was issued for. Only API tokens, which never pass
+    /// through JWT decoding, have none; JWTs without one are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<i64>,
+}
+
+pub fn jwt_secret() -> String {
+    std::env::var("JWT_SECRET")
+        .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string())
 }
 
 pub async fn auth_middleware(
+    State(pool): State<SqlitePool>,
     jar: CookieJar,
     mut request: Request,
     next: Next,
@@ -30,16 +46,44 @@ pub async fn auth_middleware(
         })
         .ok_or(PaymeError::Unauthorized)?;
 
-    let secret = std::env::var("JWT_SECRET")
-        .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string());
+    if token.starts_with(API_TOKEN_PREFIX) {
+        let claims =
+            authenticate_api_token(&pool, &token, request.method(), request.uri().path()).await?;
+        request.extensions_mut().insert(claims);
+        return Ok(next.run(request).await);
+    }
 
     let token_data = decode::<Claims>(
         &token,
-        &DecodingKey::from_secret(secret.as_bytes()),
+        &DecodingKey::from_secret(jwt_secret().as_bytes()),
         &Validation::default(),
     )
     .map_err(|_| PaymeError::Unauthorized)?;
 
+    let sid = token_data.claims.sid.ok_or(PaymeError::Unauthorized)?;
+    verify_session(&pool, token_data.claims.sub, sid).await?;
+
     request.extensions_mut().insert(token_data.claims);
     Ok(next.run(request).await)
 }
+
+/// Rejects tokens whose session was revoked or has expired, and records when
+/// the session was last used, at most once a minute.
+async fn verify_session(pool: &SqlitePool, user_id: i64, sid: i64) -> Result<(), PaymeError> {
+    let (stale,): (bool,) = sqlx::query_as(
+        "SELECT last_seen_at < datetime('now', '-1 minute') FROM sessions WHERE id = ? AND user_id = ? AND revoked_at IS NULL AND expires_at > datetime('now')",
+    )
+    .bind(sid)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or(PaymeError::Unauthorized)?;
+
+    if stale {
+        sqlx::query("UPDATE sessions SET last_seen_at = datetime('now') WHERE id = ?")
+            .bind(sid)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
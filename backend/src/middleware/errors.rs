@@ -0,0 +1,63 @@
+use axum::{
+    body::to_bytes,
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::error::{ErrorBody, ErrorCode};
+
+/// Largest plain-text error body carried over into the JSON message.
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+/// Rewrites error responses that did not come from `PaymeError`, such as
+/// axum's extractor rejections, into the same JSON envelope.
+pub async fn json_errors(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let text = to_bytes(body, MAX_MESSAGE_BYTES)
+        .await
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .unwrap_or_default();
+    let code = match status {
+        StatusCode::UNAUTHORIZED => ErrorCode::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorCode::Forbidden,
+        StatusCode::NOT_FOUND => ErrorCode::NotFound,
+        StatusCode::CONFLICT => ErrorCode::Conflict,
+        StatusCode::TOO_MANY_REQUESTS => ErrorCode::TooManyRequests,
+        s if s.is_server_error() => ErrorCode::InternalError,
+        _ => ErrorCode::BadRequest,
+    };
+    let message = if text.is_empty() || code == ErrorCode::InternalError {
+        status.canonical_reason().unwrap_or("Error").to_string()
+    } else {
+        text
+    };
+
+    parts.headers.remove(header::CONTENT_TYPE);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = Json(ErrorBody {
+        code,
+        message,
+        fields: None,
+    })
+    .into_response()
+    .into_body();
+    parts.headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    Response::from_parts(parts, body)
+}
@@ -1,13 +1,30 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::Validate;
+
+mod money;
+
+pub use money::{Currency, Money, ParseMoneyError};
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct FixedExpense {
     pub id: i64,
     pub user_id: i64,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+}
+
+/// A fixed expense as it applies to one month, copied from the user's fixed
+/// expenses when the month is created.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct MonthlyFixedExpense {
+    pub id: i64,
+    pub month_id: i64,
+    /// The fixed expense this was copied from, unless it has since been deleted.
+    pub fixed_expense_id: Option<i64>,
+    pub label: String,
+    pub amount: Money,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -15,7 +32,165 @@ pub struct BudgetCategory {
     pub id: i64,
     pub user_id: i64,
     pub label: String,
-    pub default_amount: f64,
+    pub default_amount: Money,
+    pub rollover_mode: RolloverMode,
+}
+
+/// What happens to a category's unspent or overspent budget when the next
+/// month is created.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum RolloverMode {
+    /// Every month starts from the default amount.
+    #[default]
+    None,
+    /// Money left over is added to the next month's allocation.
+    CarrySurplus,
+    /// Money left over is added, and overspending is taken out of the next month.
+    CarrySurplusAndDeficit,
+}
+
+impl RolloverMode {
+    /// The amount brought into the next month, given what was left of this
+    /// month's allocation (negative when overspent).
+    pub fn carry(self, remaining: Money) -> Money {
+        match self {
+            RolloverMode::None => Money::ZERO,
+            RolloverMode::CarrySurplus if remaining.is_negative() => Money::ZERO,
+            RolloverMode::CarrySurplus | RolloverMode::CarrySurplusAndDeficit => remaining,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum AccountKind {
+    Checking,
+    Savings,
+    CreditCard,
+    Cash,
+    Retirement,
+    Brokerage,
+}
+
+/// Somewhere money is held. The balance is derived from the opening balance,
+/// plus income paid into the account, minus items spent from it, plus or
+/// minus transfers and ledger entries.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Account {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub kind: AccountKind,
+    /// Balance before any tracked transaction; negative for money owed.
+    pub opening_balance: Money,
+    pub balance: Money,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum LedgerEntryKind {
+    Deposit,
+    Withdrawal,
+    /// Brings the balance in line with reality, e.g. after setting it directly.
+    /// Not counted as a contribution.
+    Correction,
+}
+
+/// A dated change to an account balance made by hand.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct LedgerEntry {
+    pub id: i64,
+    pub account_id: i64,
+    pub kind: LedgerEntryKind,
+    /// Signed change to the balance; negative for withdrawals.
+    pub amount: Money,
+    pub entry_date: NaiveDate,
+    pub note: Option<String>,
+}
+
+/// A named target to save towards by a date. Progress comes from the linked
+/// account's balance, or from allocations when no account is linked.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct SavingsGoal {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub target_amount: Money,
+    pub target_date: NaiveDate,
+    /// Lower numbers come first.
+    pub priority: i32,
+    pub account_id: Option<i64>,
+    pub saved: Money,
+    /// Still to save; zero once the goal is reached.
+    #[sqlx(default)]
+    pub remaining: Money,
+    /// What needs saving each month, including the current one, to reach the
+    /// target by the target date.
+    #[sqlx(default)]
+    pub required_monthly: Money,
+}
+
+impl SavingsGoal {
+    /// Fills in `remaining` and `required_monthly` as of `today`.
+    pub fn with_progress(mut self, today: NaiveDate) -> Self {
+        let remaining = self.target_amount - self.saved;
+        self.remaining = if remaining.is_negative() {
+            Money::ZERO
+        } else {
+            remaining
+        };
+
+        let months = (self.target_date.year() - today.year()) * 12
+            + self.target_date.month0() as i32
+            - today.month0() as i32
+            + 1;
+        let months = i64::from(months.max(1));
+        self.required_monthly = Money::new(
+            (self.remaining.minor() + months - 1) / months,
+            self.remaining.currency(),
+        );
+        self
+    }
+}
+
+/// Money set aside for a goal that has no linked account.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct GoalAllocation {
+    pub id: i64,
+    pub goal_id: i64,
+    /// Negative to take money back out.
+    pub amount: Money,
+    pub allocated_on: NaiveDate,
+}
+
+/// Money moved between two of the user's accounts. It changes both balances
+/// but is not spending.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Transfer {
+    pub id: i64,
+    pub month_id: i64,
+    pub from_account_id: i64,
+    pub to_account_id: i64,
+    pub amount: Money,
+    pub transferred_on: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct TransferWithAccounts {
+    pub id: i64,
+    pub month_id: i64,
+    pub from_account_id: i64,
+    pub from_account_name: String,
+    pub to_account_id: i64,
+    pub to_account_name: String,
+    pub amount: Money,
+    pub transferred_on: NaiveDate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -28,12 +203,104 @@ pub struct Month {
     pub closed_at: Option<DateTime<Utc>>,
 }
 
+/// A PDF report generated when a month was closed. Every close after a
+/// reopen adds the next version.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct MonthSnapshot {
+    pub month_id: i64,
+    pub version: i64,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
 pub struct IncomeEntry {
     pub id: i64,
     pub month_id: i64,
     pub label: String,
-    pub amount: f64,
+    pub amount: Money,
+    /// The account the income was paid into.
+    pub account_id: Option<i64>,
+}
+
+/// How often something recurs. See [`crate::schedule::occurrences`] for the
+/// dates each schedule produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Schedule {
+    /// Once a month, on the anchor date's day.
+    Monthly,
+    /// Every 14 days from the anchor date.
+    Biweekly,
+    /// On the 15th and the last day of each month.
+    SemiMonthly,
+    /// Once a year, on the anchor date's month and day.
+    Annual,
+}
+
+/// Income that is entered automatically into every month it falls in.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct IncomeTemplate {
+    pub id: i64,
+    pub user_id: i64,
+    pub label: String,
+    /// Received on each occurrence.
+    pub amount: Money,
+    pub schedule: Schedule,
+    /// The first occurrence. Required for biweekly and annual schedules.
+    pub anchor_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Frequency {
+    Weekly,
+    Monthly,
+}
+
+/// A transaction that repeats on a schedule and is entered as an item into
+/// every month it falls in. See [`crate::schedule::recurring_dates`].
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct RecurringTransaction {
+    pub id: i64,
+    pub user_id: i64,
+    pub category_id: i64,
+    pub description: String,
+    pub amount: Money,
+    pub frequency: Frequency,
+    /// Repeats every `interval` weeks or months.
+    pub interval: i64,
+    /// The first occurrence.
+    pub start_date: NaiveDate,
+    /// Day of month for monthly schedules, clamped to short months. Defaults
+    /// to the day of `start_date`.
+    pub day_of_month: Option<i64>,
+    /// No occurrences after this date.
+    pub end_date: Option<NaiveDate>,
+}
+
+/// A change to a single occurrence of a recurring transaction.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct RecurringOverride {
+    pub recurring_id: i64,
+    pub occurs_on: NaiveDate,
+    /// The occurrence is not entered at all.
+    pub skipped: bool,
+    /// Replaces the transaction's amount for this occurrence.
+    pub amount: Option<Money>,
+    /// Replaces the transaction's description for this occurrence.
+    pub description: Option<String>,
+}
+
+/// A coming occurrence of a recurring transaction, with any override applied.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UpcomingTransaction {
+    pub recurring_id: i64,
+    pub category_id: i64,
+    pub description: String,
+    pub amount: Money,
+    pub due_on: NaiveDate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -41,7 +308,7 @@ pub struct MonthlyBudget {
     pub id: i64,
     pub month_id: i64,
     pub category_id: i64,
-    pub allocated_amount: f64,
+    pub allocated_amount: Money,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -50,8 +317,120 @@ pub struct Item {
     pub month_id: i64,
     pub category_id: i64,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
     pub spent_on: NaiveDate,
+    /// The account the money was spent from.
+    pub account_id: Option<i64>,
+    /// When present, the item's amount is counted towards these categories
+    /// instead of `category_id`.
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub splits: Vec<ItemSplit>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+/// A receipt or other document uploaded for an item. The file itself is
+/// downloaded separately.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Attachment {
+    pub id: i64,
+    pub item_id: i64,
+    pub filename: String,
+    pub content_type: String,
+    /// Size in bytes.
+    pub size: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A signed-in device, kept alive by its refresh token.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Session {
+    pub id: i64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the session making the request.
+    pub current: bool,
+}
+
+/// What a personal API token may do. `write` includes `read`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    Read,
+    Write,
+    ImportExport,
+}
+
+/// The scopes granted to an API token, stored as a JSON array.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(transparent)]
+pub struct TokenScopes(pub Vec<TokenScope>);
+
+impl TokenScopes {
+    pub fn contains(&self, scope: TokenScope) -> bool {
+        self.0.contains(&scope)
+            || (scope == TokenScope::Read && self.0.contains(&TokenScope::Write))
+    }
+
+    pub fn to_db(&self) -> String {
+        serde_json::to_string(&self.0).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+impl TryFrom<String> for TokenScopes {
+    type Error = serde_json::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        serde_json::from_str(&value).map(TokenScopes)
+    }
+}
+
+/// A personal access token for scripts. The secret itself is only shown
+/// when the token is created.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    /// The first characters of the secret, to tell tokens apart.
+    pub prefix: String,
+    #[sqlx(try_from = "String")]
+    pub scopes: TokenScopes,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// A free-form label on items, independent of their categories.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct Tag {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+}
+
+/// Spending on items carrying a tag over a date range.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct TagSpending {
+    pub tag_id: i64,
+    pub name: String,
+    /// Full amounts of the tagged items, whatever their splits.
+    pub total: Money,
+    pub item_count: i64,
+}
+
+/// The part of an item's amount spent in one category.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct ItemSplit {
+    pub id: i64,
+    pub item_id: i64,
+    pub category_id: i64,
+    pub category_label: String,
+    pub amount: Money,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -60,22 +439,32 @@ pub struct MonthlyBudgetWithCategory {
     pub month_id: i64,
     pub category_id: i64,
     pub category_label: String,
-    pub allocated_amount: f64,
-    pub spent_amount: f64,
+    /// Includes `carried_amount`.
+    pub allocated_amount: Money,
+    /// Brought forward from the previous month by the category's rollover mode.
+    pub carried_amount: Money,
+    pub spent_amount: Money,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct MonthSummary {
     pub month: Month,
+    /// The owner's currency, which every amount below is in.
+    pub currency: Currency,
     pub income_entries: Vec<IncomeEntry>,
-    pub fixed_expenses: Vec<FixedExpense>,
+    pub fixed_expenses: Vec<MonthlyFixedExpense>,
     pub budgets: Vec<MonthlyBudgetWithCategory>,
     pub items: Vec<ItemWithCategory>,
-    pub total_income: f64,
-    pub total_fixed: f64,
-    pub total_budgeted: f64,
-    pub total_spent: f64,
-    pub remaining: f64,
+    pub transfers: Vec<TransferWithAccounts>,
+    /// Progress of the user's savings goals when the summary was produced.
+    pub goals: Vec<SavingsGoal>,
+    pub total_income: Money,
+    pub total_fixed: Money,
+    pub total_budgeted: Money,
+    /// Items only; transfers are not spending.
+    pub total_spent: Money,
+    pub total_transferred: Money,
+    pub remaining: Money,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
@@ -85,17 +474,48 @@ pub struct ItemWithCategory {
     pub category_id: i64,
     pub category_label: String,
     pub description: String,
-    pub amount: f64,
+    pub amount: Money,
     pub spent_on: NaiveDate,
+    pub account_id: Option<i64>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub splits: Vec<ItemSplit>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+}
+
+impl ItemWithCategory {
+    /// The amounts this item counts towards each category.
+    pub fn category_amounts(&self) -> Vec<(i64, Money)> {
+        if self.splits.is_empty() {
+            vec![(self.category_id, self.amount)]
+        } else {
+            self.splits
+                .iter()
+                .map(|s| (s.category_id, s.amount))
+                .collect()
+        }
+    }
+}
+
+/// An item found by a cross-month search, with the month it belongs to.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow, ToSchema)]
+pub struct ItemSearchResult {
+    pub year: i32,
+    pub month: i32,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub item: ItemWithCategory,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct CategoryStats {
     pub category_id: i64,
     pub category_label: String,
-    pub current_month_spent: f64,
-    pub previous_month_spent: f64,
-    pub change_amount: f64,
+    pub current_month_spent: Money,
+    pub previous_month_spent: Money,
+    pub change_amount: Money,
     pub change_percent: Option<f64>,
 }
 
@@ -103,16 +523,115 @@ pub struct CategoryStats {
 pub struct MonthlyStats {
     pub year: i32,
     pub month: i32,
-    pub total_income: f64,
-    pub total_spent: f64,
-    pub total_fixed: f64,
-    pub net: f64,
+    pub total_income: Money,
+    pub total_spent: Money,
+    pub total_fixed: Money,
+    pub net: Money,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct StatsResponse {
     pub category_comparisons: Vec<CategoryStats>,
     pub monthly_trends: Vec<MonthlyStats>,
-    pub average_monthly_spending: f64,
-    pub average_monthly_income: f64,
+    pub average_monthly_spending: Money,
+    pub average_monthly_income: Money,
+}
+
+/// How amounts in a statement indicate money leaving the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum SignConvention {
+    /// Debits are negative and credits positive, as most banks export them.
+    ExpensesNegative,
+    /// Debits are positive and credits negative, as card statements often show them.
+    ExpensesPositive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum DecimalSeparator {
+    /// `1,234.56`
+    Dot,
+    /// `1.234,56`
+    Comma,
+}
+
+/// Describes where each field lives in a bank's CSV export. Columns are
+/// zero-based.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema, Validate)]
+pub struct CsvMapping {
+    #[serde(default = "CsvMapping::default_delimiter")]
+    #[validate(length(equal = 1))]
+    pub delimiter: String,
+    #[serde(default = "CsvMapping::default_has_header")]
+    pub has_header: bool,
+    pub date_column: u32,
+    pub description_column: u32,
+    pub amount_column: u32,
+    pub sign_convention: SignConvention,
+    #[serde(default = "CsvMapping::default_decimal_separator")]
+    pub decimal_separator: DecimalSeparator,
+    /// A chrono format string such as `%d/%m/%Y`.
+    #[serde(default = "CsvMapping::default_date_format")]
+    #[validate(length(min = 1, max = 32))]
+    pub date_format: String,
+}
+
+impl CsvMapping {
+    fn default_delimiter() -> String {
+        ",".to_string()
+    }
+
+    fn default_has_header() -> bool {
+        true
+    }
+
+    fn default_decimal_separator() -> DecimalSeparator {
+        DecimalSeparator::Dot
+    }
+
+    fn default_date_format() -> String {
+        "%Y-%m-%d".to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct CsvImportProfile {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub mapping: CsvMapping,
+    /// Category given to imported rows unless the request overrides it.
+    pub category_id: Option<i64>,
+}
+
+/// Files items into a category. Every condition that is set must match;
+/// rules are tried by ascending `priority`, then by id, and the first match wins.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow, ToSchema)]
+pub struct CategorizationRule {
+    pub id: i64,
+    pub user_id: i64,
+    pub category_id: i64,
+    pub priority: i64,
+    /// Case-insensitive substring of the description.
+    pub description_contains: Option<String>,
+    pub description_regex: Option<String>,
+    pub min_amount: Option<Money>,
+    pub max_amount: Option<Money>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// Deserializes an update field that distinguishes "absent" (`None`, leave
+/// unchanged) from `null` (`Some(None)`, clear). Use with `#[serde(default)]`.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
@@ -0,0 +1,488 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{Sqlite, SqliteArgumentValue, SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Type};
+use utoipa::openapi::schema::{KnownFormat, ObjectBuilder, SchemaFormat, Type as SchemaType};
+use utoipa::openapi::{RefOr, Schema};
+use utoipa::{PartialSchema, ToSchema};
+use validator::ValidateRange;
+
+/// ISO 4217 currency an amount is denominated in. Each user picks one, stored
+/// in `users.currency`, and all of their amounts are in it.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[sqlx(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Usd,
+    Eur,
+    Gbp,
+    Cad,
+    Aud,
+}
+
+impl Currency {
+    pub fn code(self) -> &'static str {
+        match self {
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Gbp => "GBP",
+            Currency::Cad => "CAD",
+            Currency::Aud => "AUD",
+        }
+    }
+
+    /// Number of decimal places between the major and minor unit.
+    pub fn exponent(self) -> u32 {
+        2
+    }
+
+    fn scale(self) -> i64 {
+        10_i64.pow(self.exponent())
+    }
+
+    fn symbol(self) -> Option<&'static str> {
+        match self {
+            Currency::Usd | Currency::Cad | Currency::Aud => Some("$"),
+            _ => None,
+        }
+    }
+}
+
+/// An exact monetary amount stored as an integer count of minor units (cents).
+///
+/// Amounts are persisted as SQLite `INTEGER` columns and serialized over the
+/// API as a decimal number of major units, so `1999` cents is sent as `19.99`.
+/// The currency is not stored per amount: values read from the database are
+/// in the default currency until tagged with the owner's via [`Money::in_currency`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Money {
+    minor: i64,
+    currency: Currency,
+}
+
+impl Money {
+    pub const ZERO: Money = Money::from_minor(0);
+
+    /// Largest amount, in minor units, accepted from requests and statements.
+    /// It leaves enough headroom below `i64::MAX` that totals only overflow
+    /// when summing millions of maximal amounts.
+    pub const MAX_MINOR: i64 = 1_000_000_000_000_000;
+
+    pub const fn new(minor: i64, currency: Currency) -> Self {
+        Self { minor, currency }
+    }
+
+    pub const fn from_minor(minor: i64) -> Self {
+        Self::new(minor, Currency::Usd)
+    }
+
+    /// Converts a decimal amount of major units, rounding to the nearest minor
+    /// unit. Returns `None` for values that are not finite or whose magnitude
+    /// exceeds [`Money::MAX_MINOR`].
+    pub fn from_major(major: f64) -> Option<Self> {
+        let currency = Currency::default();
+        let minor = (major * currency.scale() as f64).round();
+        (minor.is_finite() && minor.abs() <= Self::MAX_MINOR as f64)
+            .then(|| Self::new(minor as i64, currency))
+    }
+
+    /// Returns `None` when the magnitude of `minor` exceeds [`Money::MAX_MINOR`].
+    pub fn checked_from_minor(minor: i64) -> Option<Self> {
+        (minor.unsigned_abs() <= Self::MAX_MINOR.unsigned_abs()).then(|| Self::from_minor(minor))
+    }
+
+    pub const fn minor(self) -> i64 {
+        self.minor
+    }
+
+    pub const fn currency(self) -> Currency {
+        self.currency
+    }
+
+    /// The same number of minor units, denominated in `currency`.
+    pub const fn in_currency(self, currency: Currency) -> Self {
+        Self::new(self.minor, currency)
+    }
+
+    pub fn to_major(self) -> f64 {
+        self.minor as f64 / self.currency.scale() as f64
+    }
+
+    pub const fn is_negative(self) -> bool {
+        self.minor < 0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.minor == 0
+    }
+
+    pub const fn abs(self) -> Self {
+        Self::new(self.minor.abs(), self.currency)
+    }
+
+    /// Returns `None` on overflow or when the currencies differ.
+    pub fn checked_add(self, rhs: Money) -> Option<Self> {
+        if self.currency != rhs.currency {
+            return None;
+        }
+        Some(Self::new(self.minor.checked_add(rhs.minor)?, self.currency))
+    }
+
+    /// Returns `None` on overflow or when the currencies differ.
+    pub fn checked_sub(self, rhs: Money) -> Option<Self> {
+        if self.currency != rhs.currency {
+            return None;
+        }
+        Some(Self::new(self.minor.checked_sub(rhs.minor)?, self.currency))
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self::new(self.minor.checked_neg()?, self.currency))
+    }
+
+    /// Totals `amounts`, returning `None` on overflow or mixed currencies.
+    /// The total of nothing is [`Money::ZERO`].
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Money>) -> Option<Self> {
+        let mut amounts = amounts.into_iter();
+        let first = amounts.next().unwrap_or(Money::ZERO);
+        amounts.try_fold(first, Money::checked_add)
+    }
+
+    /// Divides the amount, rounding half away from zero to the nearest minor
+    /// unit. Returns `None` when `divisor` is zero.
+    pub fn checked_div_rounded(self, divisor: i64) -> Option<Self> {
+        let quotient = self.minor.checked_div(divisor)?;
+        let remainder = self.minor % divisor;
+        let adjust = if (remainder.abs() * 2) >= divisor.abs() {
+            self.minor.signum() * divisor.signum()
+        } else {
+            0
+        };
+        Some(Self::new(quotient + adjust, self.currency))
+    }
+}
+
+impl PartialOrd for Money {
+    /// Amounts in different currencies are not comparable.
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        (self.currency == other.currency).then(|| self.minor.cmp(&other.minor))
+    }
+}
+
+impl ValidateRange<Money> for Money {
+    fn greater_than(&self, max: Money) -> Option<bool> {
+        self.partial_cmp(&max).map(|o| o.is_gt())
+    }
+
+    fn less_than(&self, min: Money) -> Option<bool> {
+        self.partial_cmp(&min).map(|o| o.is_lt())
+    }
+}
+
+impl ValidateRange<Money> for Option<Money> {
+    fn greater_than(&self, max: Money) -> Option<bool> {
+        self.and_then(|m| m.greater_than(max))
+    }
+
+    fn less_than(&self, min: Money) -> Option<bool> {
+        self.and_then(|m| m.less_than(min))
+    }
+}
+
+const ARITHMETIC_FAILED: &str = "amount overflowed or mixed currencies";
+
+impl Add for Money {
+    type Output = Money;
+
+    /// Panics on overflow or mixed currencies; totals of stored amounts go
+    /// through [`Money::checked_add`] instead.
+    fn add(self, rhs: Money) -> Money {
+        self.checked_add(rhs).expect(ARITHMETIC_FAILED)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    /// Panics on overflow or mixed currencies, like `add`.
+    fn sub(self, rhs: Money) -> Money {
+        self.checked_sub(rhs).expect(ARITHMETIC_FAILED)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        self.checked_neg().expect(ARITHMETIC_FAILED)
+    }
+}
+
+impl Sum for Money {
+    /// Panics on overflow or mixed currencies; see [`Money::checked_sum`].
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        Money::checked_sum(iter).expect(ARITHMETIC_FAILED)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scale = self.currency.scale().unsigned_abs();
+        let sign = if self.is_negative() { "-" } else { "" };
+        let abs = self.minor.unsigned_abs();
+        let digits = self.currency.exponent() as usize;
+        let amount = format!("{}.{:0digits$}", abs / scale, abs % scale);
+        match self.currency.symbol() {
+            Some(symbol) => write!(f, "{sign}{symbol}{amount}"),
+            None => write!(f, "{sign}{amount} {}", self.currency.code()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseMoneyError(String);
+
+impl fmt::Display for ParseMoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// Parses a plain decimal such as `-12.5` or `1999.99` without going through `f64`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseMoneyError(s.to_string());
+        let currency = Currency::default();
+        let trimmed = s.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits = currency.exponent() as usize;
+        if (whole.is_empty() && fraction.is_empty())
+            || fraction.len() > digits
+            || !whole.bytes().all(|b| b.is_ascii_digit())
+            || !fraction.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(err());
+        }
+        let whole: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole.parse().map_err(|_| err())?
+        };
+        let fraction: i64 = format!("{fraction:0<digits$}").parse().map_err(|_| err())?;
+        let minor = whole
+            .checked_mul(currency.scale())
+            .and_then(|m| m.checked_add(fraction))
+            .filter(|m| *m <= Money::MAX_MINOR)
+            .ok_or_else(err)?;
+        Ok(Money::new(if negative { -minor } else { minor }, currency))
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_major())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a decimal amount as a number or string")
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                if !v.is_finite() {
+                    return Err(E::custom("amount must be finite"));
+                }
+                Money::from_major(v).ok_or_else(|| E::custom("amount out of range"))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                v.checked_mul(Currency::default().scale())
+                    .and_then(Money::checked_from_minor)
+                    .ok_or_else(|| E::custom("amount out of range"))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .map_err(|_| E::custom("amount out of range"))
+                    .and_then(|v| self.visit_i64(v))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+impl Type<Sqlite> for Money {
+    fn type_info() -> SqliteTypeInfo {
+        <i64 as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <i64 as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for Money {
+    fn encode_by_ref(&self, buf: &mut Vec<SqliteArgumentValue<'q>>) -> Result<IsNull, BoxDynError> {
+        <i64 as Encode<Sqlite>>::encode(self.minor, buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for Money {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Money::from_minor(<i64 as Decode<Sqlite>>::decode(value)?))
+    }
+}
+
+impl PartialSchema for Money {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(SchemaType::Number)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Double)))
+            .description(Some(
+                "Decimal amount in major currency units, stored exactly as integer minor units",
+            ))
+            .examples([serde_json::json!(19.99)])
+            .into()
+    }
+}
+
+impl ToSchema for Money {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decimal_strings() {
+        assert_eq!("19.99".parse(), Ok(Money::from_minor(1999)));
+        assert_eq!("-0.5".parse(), Ok(Money::from_minor(-50)));
+        assert_eq!("42".parse(), Ok(Money::from_minor(4200)));
+        assert_eq!(".07".parse(), Ok(Money::from_minor(7)));
+        assert!("1.234".parse::<Money>().is_err());
+        assert!("12,50".parse::<Money>().is_err());
+        assert!("".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_sum_is_exact() {
+        let total: Money = std::iter::repeat_n(Money::from_major(0.1).unwrap(), 1000).sum();
+        assert_eq!(total, Money::from_minor(10_000));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let money: Money = serde_json::from_str("19.99").unwrap();
+        assert_eq!(money.minor(), 1999);
+        assert_eq!(serde_json::to_string(&money).unwrap(), "19.99");
+
+        let money: Money = serde_json::from_str("5").unwrap();
+        assert_eq!(money.minor(), 500);
+
+        let money: Money = serde_json::from_str("\"1234.56\"").unwrap();
+        assert_eq!(money.minor(), 123456);
+    }
+
+    #[test]
+    fn test_out_of_range_amounts_are_rejected() {
+        assert_eq!(
+            Money::from_major(1e13),
+            Some(Money::from_minor(Money::MAX_MINOR))
+        );
+        assert_eq!(Money::from_major(1e14), None);
+        assert_eq!(Money::from_major(f64::NAN), None);
+        assert!("10000000000000.01".parse::<Money>().is_err());
+        assert!(serde_json::from_str::<Money>("1e300").is_err());
+        assert!(serde_json::from_str::<Money>("92233720368547758").is_err());
+        assert!(serde_json::from_str::<Money>("-10000000000001").is_err());
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        let max = Money::from_minor(i64::MAX);
+        assert_eq!(max.checked_add(Money::from_minor(1)), None);
+        assert_eq!(Money::from_minor(i64::MIN).checked_neg(), None);
+        assert_eq!(Money::checked_sum([max, max]), None);
+        assert_eq!(Money::checked_sum([]), Some(Money::ZERO));
+        assert_eq!(
+            Money::from_minor(500).checked_sub(Money::from_minor(200)),
+            Some(Money::from_minor(300))
+        );
+        let eur = Money::new(100, Currency::Eur);
+        assert_eq!(Money::from_minor(100).checked_add(eur), None);
+        assert_eq!(
+            Money::checked_sum([eur, eur]),
+            Some(Money::new(200, Currency::Eur))
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Money::from_minor(123456).to_string(), "$1234.56");
+        assert_eq!(Money::from_minor(-5).to_string(), "-$0.05");
+        assert_eq!(Money::new(1000, Currency::Eur).to_string(), "10.00 EUR");
+        assert_eq!(Money::new(250, Currency::Cad).to_string(), "$2.50");
+    }
+
+    #[test]
+    fn test_currencies_do_not_compare() {
+        let usd = Money::from_minor(100);
+        assert_eq!(usd.partial_cmp(&usd.in_currency(Currency::Gbp)), None);
+        assert!(usd < Money::from_minor(200));
+    }
+
+    #[test]
+    fn test_checked_div_rounded() {
+        let div = |minor: i64, divisor| Money::from_minor(minor).checked_div_rounded(divisor);
+        assert_eq!(div(100, 3), Some(Money::from_minor(33)));
+        assert_eq!(div(200, 3), Some(Money::from_minor(67)));
+        assert_eq!(div(-200, 3), Some(Money::from_minor(-67)));
+        assert_eq!(div(5, 0), None);
+    }
+}
@@ -1,63 +1,194 @@
-use utoipa::OpenApi;
+use utoipa::{
+    openapi::{Content, Ref, RefOr},
+    Modify, OpenApi,
+};
 
+use crate::error::{ErrorBody, ErrorCode, FieldError};
 use crate::handlers::{
-    auth::{AuthRequest, AuthResponse},
+    accounts::SaveAccount,
+    api_tokens::{CreateApiToken, CreatedApiToken},
+    auth::{
+        AuthRequest, AuthResponse, ChangeCurrencyRequest, LoginRequest, RefreshRequest,
+        TokenResponse,
+    },
     budget::{CreateCategory, UpdateCategory, UpdateMonthlyBudget},
     export::{
-        BudgetExport, CategoryExport, FixedExpenseExport, IncomeExport, ItemExport, MonthExport,
-        UserExport,
+        AccountExport, AllocationExport, BudgetExport, CategoryExport, FixedExpenseExport,
+        GoalExport, IncomeExport, ItemExport, LedgerEntryExport, MonthExport, SplitExport,
+        TransferExport, UserExport,
     },
     fixed_expenses::{CreateFixedExpense, UpdateFixedExpense},
+    goals::{CreateAllocation, SaveGoal},
+    import::{
+        CategoryOverride, CsvImportRequest, ImportIncome, ImportPreview, ImportResult, ImportRow,
+        OfxImportRequest, SaveCsvProfile, SkippedRow, YearMonth,
+    },
     income::{CreateIncome, UpdateIncome},
-    items::{CreateItem, UpdateItem},
-    savings::{RetirementSavingsResponse, SavingsResponse, UpdateRetirementSavings, UpdateSavings},
+    income_templates::SaveIncomeTemplate,
+    items::{CreateItem, SaveSplit, UpdateItem},
+    months::{CreateMonth, CreateMonthRange},
+    recurring::{SaveOccurrence, SaveRecurring},
+    rules::{ApplyRulesResult, SaveRule},
+    savings::{
+        BalancePoint, CreateLedgerEntry, MonthlyContribution, RetirementSavingsResponse,
+        SavingsHistory, SavingsProjection, SavingsResponse, UpdateRetirementSavings, UpdateSavings,
+        UpdateSavingsGoal,
+    },
+    tags::SaveTag,
+    transfers::SaveTransfer,
+    two_factor::{
+        ConfirmTwoFactorRequest, DisableTwoFactorRequest, LoginChallenge, RecoveryCodes,
+        TwoFactorSetup, TwoFactorStatus, VerifyLoginRequest,
+    },
 };
 use crate::models::{
-    BudgetCategory, CategoryStats, FixedExpense, IncomeEntry, Item, ItemWithCategory, Month,
-    MonthSummary, MonthlyBudget, MonthlyStats, StatsResponse,
+    Account, AccountKind, ApiToken, Attachment, BudgetCategory, CategorizationRule, CategoryStats,
+    CsvImportProfile, CsvMapping, Currency, DecimalSeparator, FixedExpense, Frequency,
+    GoalAllocation, IncomeEntry, IncomeTemplate, Item, ItemSearchResult, ItemSplit,
+    ItemWithCategory, LedgerEntry, LedgerEntryKind, Money, Month, MonthSnapshot, MonthSummary,
+    MonthlyBudget, MonthlyFixedExpense, MonthlyStats, RecurringOverride, RecurringTransaction,
+    RolloverMode, SavingsGoal, Schedule, Session, SignConvention, StatsResponse, Tag, TagSpending,
+    TokenScope, TokenScopes, Transfer, TransferWithAccounts, UpcomingTransaction,
 };
 
 #[derive(OpenApi)]
 #[openapi(
+    modifiers(&ErrorResponses),
     paths(
         crate::handlers::auth::register,
         crate::handlers::auth::login,
+        crate::handlers::auth::verify_login,
+        crate::handlers::auth::refresh,
         crate::handlers::auth::logout,
         crate::handlers::auth::me,
+        crate::handlers::auth::change_currency,
+        crate::handlers::sessions::list_sessions,
+        crate::handlers::sessions::delete_session,
+        crate::handlers::sessions::delete_all_sessions,
+        crate::handlers::api_tokens::list_api_tokens,
+        crate::handlers::api_tokens::create_api_token,
+        crate::handlers::api_tokens::delete_api_token,
+        crate::handlers::two_factor::get_two_factor,
+        crate::handlers::two_factor::setup_two_factor,
+        crate::handlers::two_factor::confirm_two_factor,
+        crate::handlers::two_factor::disable_two_factor,
         crate::handlers::export::export_json,
         crate::handlers::export::import_json,
+        crate::handlers::export::export_sqlite,
+        crate::handlers::export::import_sqlite,
+        crate::handlers::import::list_csv_profiles,
+        crate::handlers::import::create_csv_profile,
+        crate::handlers::import::update_csv_profile,
+        crate::handlers::import::delete_csv_profile,
+        crate::handlers::import::preview_csv_import,
+        crate::handlers::import::import_csv,
+        crate::handlers::import::preview_ofx_import,
+        crate::handlers::import::import_ofx,
         crate::handlers::budget::list_monthly_budgets,
         crate::handlers::budget::update_monthly_budget,
         crate::handlers::income::list_income,
         crate::handlers::income::create_income,
         crate::handlers::income::update_income,
         crate::handlers::income::delete_income,
+        crate::handlers::items::search_items,
         crate::handlers::items::list_items,
         crate::handlers::items::create_item,
         crate::handlers::items::update_item,
         crate::handlers::items::delete_item,
+        crate::handlers::attachments::list_attachments,
+        crate::handlers::attachments::upload_attachment,
+        crate::handlers::attachments::get_attachment,
+        crate::handlers::attachments::delete_attachment,
+        crate::handlers::rules::list_rules,
+        crate::handlers::rules::create_rule,
+        crate::handlers::rules::update_rule,
+        crate::handlers::rules::delete_rule,
+        crate::handlers::rules::apply_rules_to_month,
         crate::handlers::fixed_expenses::list_fixed_expenses,
         crate::handlers::fixed_expenses::create_fixed_expense,
         crate::handlers::fixed_expenses::update_fixed_expense,
         crate::handlers::fixed_expenses::delete_fixed_expense,
+        crate::handlers::income_templates::list_income_templates,
+        crate::handlers::income_templates::create_income_template,
+        crate::handlers::income_templates::update_income_template,
+        crate::handlers::income_templates::delete_income_template,
+        crate::handlers::recurring::list_recurring,
+        crate::handlers::recurring::create_recurring,
+        crate::handlers::recurring::update_recurring,
+        crate::handlers::recurring::delete_recurring,
+        crate::handlers::recurring::update_occurrence,
+        crate::handlers::recurring::restore_occurrence,
+        crate::handlers::recurring::list_upcoming,
         crate::handlers::budget::list_categories,
         crate::handlers::budget::create_category,
         crate::handlers::budget::update_category,
         crate::handlers::budget::delete_category,
         crate::handlers::months::list_months,
+        crate::handlers::months::create_month,
+        crate::handlers::months::create_months,
         crate::handlers::months::get_or_create_current_month,
         crate::handlers::months::get_month,
         crate::handlers::months::close_month,
+        crate::handlers::months::reopen_month,
         crate::handlers::months::get_month_pdf,
+        crate::handlers::months::list_month_snapshots,
+        crate::handlers::months::get_month_snapshot,
+        crate::handlers::transfers::list_transfers,
+        crate::handlers::transfers::create_transfer,
+        crate::handlers::transfers::update_transfer,
+        crate::handlers::transfers::delete_transfer,
+        crate::handlers::accounts::list_accounts,
+        crate::handlers::accounts::create_account,
+        crate::handlers::accounts::update_account,
+        crate::handlers::accounts::delete_account,
         crate::handlers::savings::get_savings,
         crate::handlers::savings::update_savings,
+        crate::handlers::savings::update_savings_goal,
+        crate::handlers::savings::list_ledger_entries,
+        crate::handlers::savings::create_ledger_entry,
+        crate::handlers::savings::delete_ledger_entry,
+        crate::handlers::savings::get_savings_history,
+        crate::handlers::goals::list_goals,
+        crate::handlers::goals::create_goal,
+        crate::handlers::goals::update_goal,
+        crate::handlers::goals::delete_goal,
+        crate::handlers::goals::list_allocations,
+        crate::handlers::goals::create_allocation,
+        crate::handlers::goals::delete_allocation,
         crate::handlers::savings::get_retirement_savings,
         crate::handlers::savings::update_retirement_savings,
-        crate::handlers::stats::get_stats
+        crate::handlers::stats::get_stats,
+        crate::handlers::tags::list_tags,
+        crate::handlers::tags::create_tag,
+        crate::handlers::tags::update_tag,
+        crate::handlers::tags::delete_tag,
+        crate::handlers::tags::tag_report
     ),
     components(schemas(
+        ErrorBody,
+        ErrorCode,
+        FieldError,
+        Money,
         AuthRequest,
+        LoginRequest,
         AuthResponse,
+        ChangeCurrencyRequest,
+        Currency,
+        TokenResponse,
+        RefreshRequest,
+        Session,
+        ApiToken,
+        TokenScope,
+        TokenScopes,
+        CreateApiToken,
+        CreatedApiToken,
+        LoginChallenge,
+        VerifyLoginRequest,
+        TwoFactorStatus,
+        TwoFactorSetup,
+        ConfirmTwoFactorRequest,
+        RecoveryCodes,
+        DisableTwoFactorRequest,
         MonthlyBudget,
         UpdateMonthlyBudget,
         IncomeEntry,
@@ -65,30 +196,124 @@ use crate::models::{
         UpdateIncome,
         Item,
         ItemWithCategory,
+        ItemSearchResult,
+        ItemSplit,
+        SaveSplit,
+        Tag,
+        SaveTag,
+        TagSpending,
+        Attachment,
         CreateItem,
         UpdateItem,
+        CategorizationRule,
+        SaveRule,
+        ApplyRulesResult,
         FixedExpense,
+        MonthlyFixedExpense,
         CreateFixedExpense,
         UpdateFixedExpense,
+        IncomeTemplate,
+        Schedule,
+        SaveIncomeTemplate,
+        RecurringTransaction,
+        Frequency,
+        SaveRecurring,
+        RecurringOverride,
+        SaveOccurrence,
+        UpcomingTransaction,
         BudgetCategory,
         CreateCategory,
         UpdateCategory,
         Month,
+        CreateMonth,
+        CreateMonthRange,
         MonthSummary,
+        MonthSnapshot,
         StatsResponse,
         CategoryStats,
         MonthlyStats,
+        Transfer,
+        TransferWithAccounts,
+        SaveTransfer,
+        Account,
+        AccountKind,
+        SaveAccount,
         RetirementSavingsResponse,
         SavingsResponse,
         UpdateSavings,
+        UpdateSavingsGoal,
+        LedgerEntry,
+        LedgerEntryKind,
+        CreateLedgerEntry,
+        SavingsHistory,
+        BalancePoint,
+        MonthlyContribution,
+        SavingsProjection,
+        SavingsGoal,
+        SaveGoal,
+        GoalAllocation,
+        CreateAllocation,
         UpdateRetirementSavings,
         UserExport,
+        AccountExport,
+        LedgerEntryExport,
+        GoalExport,
+        AllocationExport,
+        TransferExport,
         CategoryExport,
         MonthExport,
         FixedExpenseExport,
         IncomeExport,
         BudgetExport,
-        ItemExport
+        ItemExport,
+        SplitExport,
+        CsvMapping,
+        CsvImportProfile,
+        RolloverMode,
+        SignConvention,
+        DecimalSeparator,
+        SaveCsvProfile,
+        CsvImportRequest,
+        CategoryOverride,
+        ImportPreview,
+        ImportRow,
+        ImportIncome,
+        OfxImportRequest,
+        SkippedRow,
+        YearMonth,
+        ImportResult
     ))
 )]
 pub struct ApiDoc;
+
+/// Documents the `ErrorBody` returned with every 4xx and 5xx response.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for path in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut path.get,
+                &mut path.put,
+                &mut path.post,
+                &mut path.delete,
+                &mut path.patch,
+            ];
+            for operation in operations.into_iter().flatten() {
+                for (status, response) in operation.responses.responses.iter_mut() {
+                    let RefOr::T(response) = response else {
+                        continue;
+                    };
+                    if (status.starts_with('4') || status.starts_with('5'))
+                        && response.content.is_empty()
+                    {
+                        response.content.insert(
+                            "application/json".to_string(),
+                            Content::new(Some(Ref::from_schema_name("ErrorBody"))),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use printpdf::*;
 use std::io::BufWriter;
 
-use crate::models::MonthSummary;
+use crate::models::{Money, MonthSummary};
 
 pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let money = |amount: Money| amount.in_currency(summary.currency);
     let title = format!(
         "Financial Summary - {}/{}",
         summary.month.month, summary.month.year
@@ -25,12 +26,12 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
     y -= line_height;
 
     for entry in &summary.income_entries {
-        let text = format!("  {} - ${:.2}", entry.label, entry.amount);
+        let text = format!("  {} - {}", entry.label, money(entry.amount));
         layer.use_text(&text, 10.0, Mm(left_margin), Mm(y), &font);
         y -= line_height;
     }
 
-    let total_income_text = format!("Total Income: ${:.2}", summary.total_income);
+    let total_income_text = format!("Total Income: {}", money(summary.total_income));
     layer.use_text(&total_income_text, 10.0, Mm(left_margin), Mm(y), &font_bold);
     y -= line_height * 2.0;
 
@@ -38,12 +39,12 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
     y -= line_height;
 
     for expense in &summary.fixed_expenses {
-        let text = format!("  {} - ${:.2}", expense.label, expense.amount);
+        let text = format!("  {} - {}", expense.label, money(expense.amount));
         layer.use_text(&text, 10.0, Mm(left_margin), Mm(y), &font);
         y -= line_height;
     }
 
-    let total_fixed_text = format!("Total Fixed: ${:.2}", summary.total_fixed);
+    let total_fixed_text = format!("Total Fixed: {}", money(summary.total_fixed));
     layer.use_text(&total_fixed_text, 10.0, Mm(left_margin), Mm(y), &font_bold);
     y -= line_height * 2.0;
 
@@ -53,19 +54,29 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
     for budget in &summary.budgets {
         let status = if budget.spent_amount > budget.allocated_amount {
             format!(
-                "OVER by ${:.2}",
-                budget.spent_amount - budget.allocated_amount
+                "OVER by {}",
+                money(budget.spent_amount - budget.allocated_amount)
             )
         } else {
             format!(
-                "${:.2} remaining",
-                budget.allocated_amount - budget.spent_amount
+                "{} remaining",
+                money(budget.allocated_amount - budget.spent_amount)
             )
         };
 
+        let carried = if budget.carried_amount.is_zero() {
+            String::new()
+        } else {
+            format!(", {} carried over", money(budget.carried_amount))
+        };
+
         let text = format!(
-            "  {}: ${:.2} / ${:.2} ({})",
-            budget.category_label, budget.spent_amount, budget.allocated_amount, status
+            "  {}: {} / {} ({}{})",
+            budget.category_label,
+            money(budget.spent_amount),
+            money(budget.allocated_amount),
+            status,
+            carried
         );
         layer.use_text(&text, 10.0, Mm(left_margin), Mm(y), &font);
         y -= line_height;
@@ -80,9 +91,21 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
         if y < 20.0 {
             break;
         }
+        let categories = if item.splits.is_empty() {
+            item.category_label.clone()
+        } else {
+            item.splits
+                .iter()
+                .map(|s| format!("{} {}", s.category_label, money(s.amount)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
         let text = format!(
-            "  {} - {} - ${:.2} ({})",
-            item.spent_on, item.description, item.amount, item.category_label
+            "  {} - {} - {} ({})",
+            item.spent_on,
+            item.description,
+            money(item.amount),
+            categories
         );
         layer.use_text(&text, 9.0, Mm(left_margin), Mm(y), &font);
         y -= line_height;
@@ -90,17 +113,73 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
 
     y -= line_height;
 
+    if !summary.transfers.is_empty() && y >= 40.0 {
+        layer.use_text("TRANSFERS", 12.0, Mm(left_margin), Mm(y), &font_bold);
+        y -= line_height;
+
+        for transfer in &summary.transfers {
+            if y < 40.0 {
+                break;
+            }
+            let text = format!(
+                "  {} - {} -> {} - {}",
+                transfer.transferred_on,
+                transfer.from_account_name,
+                transfer.to_account_name,
+                money(transfer.amount)
+            );
+            layer.use_text(&text, 9.0, Mm(left_margin), Mm(y), &font);
+            y -= line_height;
+        }
+
+        y -= line_height;
+    }
+
+    if !summary.goals.is_empty() && y >= 40.0 {
+        layer.use_text("SAVINGS GOALS", 12.0, Mm(left_margin), Mm(y), &font_bold);
+        y -= line_height;
+
+        for goal in &summary.goals {
+            if y < 40.0 {
+                break;
+            }
+            let status = if goal.remaining.is_zero() {
+                "reached".to_string()
+            } else {
+                format!("{} a month needed", money(goal.required_monthly))
+            };
+            let text = format!(
+                "  {}: {} / {} by {} ({})",
+                goal.name,
+                money(goal.saved),
+                money(goal.target_amount),
+                goal.target_date,
+                status
+            );
+            layer.use_text(&text, 9.0, Mm(left_margin), Mm(y), &font);
+            y -= line_height;
+        }
+
+        y -= line_height;
+    }
+
     layer.use_text("SUMMARY", 12.0, Mm(left_margin), Mm(y), &font_bold);
     y -= line_height;
 
-    let total_spent_text = format!("Total Spent: ${:.2}", summary.total_spent);
+    let total_spent_text = format!("Total Spent: {}", money(summary.total_spent));
     layer.use_text(&total_spent_text, 10.0, Mm(left_margin), Mm(y), &font);
     y -= line_height;
 
-    let remaining_text = if summary.remaining >= 0.0 {
-        format!("Remaining: ${:.2}", summary.remaining)
+    if !summary.total_transferred.is_zero() {
+        let transferred_text = format!("Transferred: {}", money(summary.total_transferred));
+        layer.use_text(&transferred_text, 10.0, Mm(left_margin), Mm(y), &font);
+        y -= line_height;
+    }
+
+    let remaining_text = if summary.remaining.is_negative() {
+        format!("Deficit: {}", money(summary.remaining))
     } else {
-        format!("Deficit: -${:.2}", summary.remaining.abs())
+        format!("Remaining: {}", money(summary.remaining))
     };
 
     layer.use_text(&remaining_text, 10.0, Mm(left_margin), Mm(y), &font_bold);
@@ -114,7 +193,8 @@ pub fn generate_pdf(summary: &MonthSummary) -> Result<Vec<u8>, Box<dyn std::erro
 mod tests {
     use super::*;
     use crate::models::{
-        FixedExpense, IncomeEntry, ItemWithCategory, Month, MonthlyBudgetWithCategory,
+        Currency, IncomeEntry, ItemWithCategory, Money, Month, MonthlyBudgetWithCategory,
+        MonthlyFixedExpense, SavingsGoal, TransferWithAccounts,
     };
     use chrono::NaiveDate;
 
@@ -128,25 +208,29 @@ mod tests {
                 is_closed: false,
                 closed_at: None,
             },
+            currency: Currency::default(),
             income_entries: vec![IncomeEntry {
                 id: 1,
                 month_id: 1,
                 label: "Salary".to_string(),
-                amount: 5000.0,
+                amount: Money::from_major(5000.0).unwrap(),
+                account_id: None,
             }],
-            fixed_expenses: vec![FixedExpense {
+            fixed_expenses: vec![MonthlyFixedExpense {
                 id: 1,
-                user_id: 1,
+                month_id: 1,
+                fixed_expense_id: Some(1),
                 label: "Rent".to_string(),
-                amount: 1500.0,
+                amount: Money::from_major(1500.0).unwrap(),
             }],
             budgets: vec![MonthlyBudgetWithCategory {
                 id: 1,
                 month_id: 1,
                 category_id: 1,
                 category_label: "Food".to_string(),
-                allocated_amount: 500.0,
-                spent_amount: 300.0,
+                allocated_amount: Money::from_major(500.0).unwrap(),
+                carried_amount: Money::from_major(50.0).unwrap(),
+                spent_amount: Money::from_major(300.0).unwrap(),
             }],
             items: vec![ItemWithCategory {
                 id: 1,
@@ -154,14 +238,40 @@ mod tests {
                 category_id: 1,
                 category_label: "Food".to_string(),
                 description: "Groceries".to_string(),
-                amount: 150.0,
+                amount: Money::from_major(150.0).unwrap(),
                 spent_on: NaiveDate::from_ymd_opt(2024, 6, 15).unwrap(),
+                account_id: None,
+                splits: vec![],
+                tags: vec![],
+            }],
+            transfers: vec![TransferWithAccounts {
+                id: 1,
+                month_id: 1,
+                from_account_id: 1,
+                from_account_name: "Checking".to_string(),
+                to_account_id: 2,
+                to_account_name: "Savings".to_string(),
+                amount: Money::from_major(250.0).unwrap(),
+                transferred_on: NaiveDate::from_ymd_opt(2024, 6, 20).unwrap(),
+            }],
+            goals: vec![SavingsGoal {
+                id: 1,
+                user_id: 1,
+                name: "Emergency fund".to_string(),
+                target_amount: Money::from_major(6000.0).unwrap(),
+                target_date: NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+                priority: 0,
+                account_id: Some(2),
+                saved: Money::from_major(2400.0).unwrap(),
+                remaining: Money::from_major(3600.0).unwrap(),
+                required_monthly: Money::from_major(514.29).unwrap(),
             }],
-            total_income: 5000.0,
-            total_fixed: 1500.0,
-            total_budgeted: 500.0,
-            total_spent: 300.0,
-            remaining: 3200.0,
+            total_income: Money::from_major(5000.0).unwrap(),
+            total_fixed: Money::from_major(1500.0).unwrap(),
+            total_budgeted: Money::from_major(500.0).unwrap(),
+            total_spent: Money::from_major(300.0).unwrap(),
+            total_transferred: Money::from_major(250.0).unwrap(),
+            remaining: Money::from_major(3200.0).unwrap(),
         }
     }
 
@@ -191,15 +301,19 @@ mod tests {
                 is_closed: false,
                 closed_at: None,
             },
+            currency: Currency::default(),
             income_entries: vec![],
             fixed_expenses: vec![],
             budgets: vec![],
             items: vec![],
-            total_income: 0.0,
-            total_fixed: 0.0,
-            total_budgeted: 0.0,
-            total_spent: 0.0,
-            remaining: 0.0,
+            transfers: vec![],
+            goals: vec![],
+            total_income: Money::ZERO,
+            total_fixed: Money::ZERO,
+            total_budgeted: Money::ZERO,
+            total_spent: Money::ZERO,
+            total_transferred: Money::ZERO,
+            remaining: Money::ZERO,
         };
 
         let result = generate_pdf(&summary);
@@ -209,7 +323,7 @@ mod tests {
     #[test]
     fn test_generate_pdf_with_deficit() {
         let mut summary = create_test_summary();
-        summary.remaining = -500.0;
+        summary.remaining = Money::from_major(-500.0).unwrap();
 
         let result = generate_pdf(&summary);
         assert!(result.is_ok());
@@ -218,7 +332,7 @@ mod tests {
     #[test]
     fn test_generate_pdf_over_budget() {
         let mut summary = create_test_summary();
-        summary.budgets[0].spent_amount = 600.0; // Over the 500 allocated
+        summary.budgets[0].spent_amount = Money::from_major(600.0).unwrap(); // Over the 500 allocated
 
         let result = generate_pdf(&summary);
         assert!(result.is_ok());
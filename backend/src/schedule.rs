@@ -0,0 +1,241 @@
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::models::{Frequency, RecurringTransaction, Schedule};
+
+/// The dates on which something repeating on `schedule` falls within the
+/// given month, in order.
+///
+/// `anchor` is the first occurrence: nothing falls before it, biweekly dates
+/// are counted from it, and monthly and annual dates fall on its day (or the
+/// month's last day when shorter). Semi-monthly dates are the 15th and the last
+/// day of the month. Returns nothing for an invalid month.
+pub fn occurrences(
+    schedule: Schedule,
+    anchor: Option<NaiveDate>,
+    year: i32,
+    month: i32,
+) -> Vec<NaiveDate> {
+    let Some((first, last)) = month_bounds(year, month) else {
+        return Vec::new();
+    };
+    let on_day = |day: u32| first.with_day(day.min(last.day())).unwrap_or(last);
+
+    let dates = match schedule {
+        Schedule::Monthly => vec![on_day(anchor.map_or(1, |a| a.day()))],
+        Schedule::SemiMonthly => vec![on_day(15), last],
+        Schedule::Annual => match anchor {
+            Some(anchor) if anchor.month() == first.month() => vec![on_day(anchor.day())],
+            _ => Vec::new(),
+        },
+        Schedule::Biweekly => {
+            let Some(anchor) = anchor else {
+                return Vec::new();
+            };
+            // Round up to the first fortnight on or after the month's start.
+            let periods = ((first.max(anchor) - anchor).num_days() + 13) / 14;
+            let mut date = anchor + Duration::days(periods * 14);
+            let mut dates = Vec::new();
+            while date <= last {
+                dates.push(date);
+                date += Duration::days(14);
+            }
+            dates
+        }
+    };
+
+    dates
+        .into_iter()
+        .filter(|date| anchor.is_none_or(|anchor| *date >= anchor))
+        .collect()
+}
+
+/// The dates from `from` to `to` inclusive on which `recurring` falls, in
+/// order, honouring its start and end dates.
+pub fn recurring_dates(
+    recurring: &RecurringTransaction,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<NaiveDate> {
+    let start = recurring.start_date;
+    let from = from.max(start);
+    let to = recurring.end_date.map_or(to, |end| end.min(to));
+    let interval = recurring.interval.max(1);
+    let mut dates = Vec::new();
+    if from > to {
+        return dates;
+    }
+
+    match recurring.frequency {
+        Frequency::Weekly => {
+            let step = 7 * interval;
+            let periods = ((from - start).num_days() + step - 1) / step;
+            let mut date = start + Duration::days(periods * step);
+            while date <= to {
+                dates.push(date);
+                date += Duration::days(step);
+            }
+        }
+        Frequency::Monthly => {
+            let day = recurring
+                .day_of_month
+                .and_then(|day| u32::try_from(day).ok())
+                .unwrap_or(start.day());
+            let index = |date: NaiveDate| i64::from(date.year()) * 12 + i64::from(date.month0());
+            // Round up to the first period in or after the month of `from`.
+            let periods = (index(from) - index(start) + interval - 1) / interval;
+            let mut month = index(start) + periods * interval;
+            while let Some((first, last)) = i32::try_from(month.div_euclid(12))
+                .ok()
+                .and_then(|year| month_bounds(year, month.rem_euclid(12) as i32 + 1))
+            {
+                if first > to {
+                    break;
+                }
+                let date = first.with_day(day.min(last.day())).unwrap_or(last);
+                if date >= from && date <= to {
+                    dates.push(date);
+                }
+                month += interval;
+            }
+        }
+    }
+
+    dates
+}
+
+/// The first and last day of a month, or `None` for an invalid month.
+pub fn month_bounds(year: i32, month: i32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = u32::try_from(month)
+        .ok()
+        .and_then(|month| NaiveDate::from_ymd_opt(year, month, 1))?;
+    Some((first, last_day_of_month(first)))
+}
+
+fn last_day_of_month(first: NaiveDate) -> NaiveDate {
+    first
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_biweekly_lands_three_times_in_some_months() {
+        let anchor = Some(date(2024, 1, 5));
+        assert_eq!(
+            occurrences(Schedule::Biweekly, anchor, 2024, 3),
+            vec![date(2024, 3, 1), date(2024, 3, 15), date(2024, 3, 29)]
+        );
+        assert_eq!(
+            occurrences(Schedule::Biweekly, anchor, 2024, 4),
+            vec![date(2024, 4, 12), date(2024, 4, 26)]
+        );
+        assert!(occurrences(Schedule::Biweekly, anchor, 2023, 12).is_empty());
+        assert!(occurrences(Schedule::Biweekly, None, 2024, 3).is_empty());
+    }
+
+    #[test]
+    fn test_monthly_and_semi_monthly_clamp_to_month_end() {
+        let anchor = Some(date(2024, 1, 31));
+        assert_eq!(
+            occurrences(Schedule::Monthly, anchor, 2024, 2),
+            vec![date(2024, 2, 29)]
+        );
+        assert_eq!(
+            occurrences(Schedule::Monthly, None, 2024, 2),
+            vec![date(2024, 2, 1)]
+        );
+        assert_eq!(
+            occurrences(Schedule::SemiMonthly, None, 2023, 2),
+            vec![date(2023, 2, 15), date(2023, 2, 28)]
+        );
+        assert_eq!(
+            occurrences(Schedule::SemiMonthly, Some(date(2024, 6, 20)), 2024, 6),
+            vec![date(2024, 6, 30)]
+        );
+    }
+
+    #[test]
+    fn test_annual_falls_in_anchor_month() {
+        let anchor = Some(date(2023, 12, 20));
+        assert_eq!(
+            occurrences(Schedule::Annual, anchor, 2024, 12),
+            vec![date(2024, 12, 20)]
+        );
+        assert!(occurrences(Schedule::Annual, anchor, 2024, 11).is_empty());
+        assert!(occurrences(Schedule::Annual, anchor, 2022, 12).is_empty());
+    }
+
+    fn recurring(
+        frequency: Frequency,
+        interval: i64,
+        start_date: NaiveDate,
+        day_of_month: Option<i64>,
+        end_date: Option<NaiveDate>,
+    ) -> RecurringTransaction {
+        RecurringTransaction {
+            id: 1,
+            user_id: 1,
+            category_id: 1,
+            description: "Test".to_string(),
+            amount: crate::models::Money::ZERO,
+            frequency,
+            interval,
+            start_date,
+            day_of_month,
+            end_date,
+        }
+    }
+
+    #[test]
+    fn test_weekly_recurrence_steps_from_start() {
+        let every_other_week = recurring(Frequency::Weekly, 2, date(2024, 1, 3), None, None);
+        assert_eq!(
+            recurring_dates(&every_other_week, date(2024, 2, 1), date(2024, 2, 29)),
+            vec![date(2024, 2, 14), date(2024, 2, 28)]
+        );
+        assert!(
+            recurring_dates(&every_other_week, date(2023, 12, 1), date(2023, 12, 31)).is_empty()
+        );
+
+        let ended = recurring(
+            Frequency::Weekly,
+            1,
+            date(2024, 1, 3),
+            None,
+            Some(date(2024, 1, 17)),
+        );
+        assert_eq!(
+            recurring_dates(&ended, date(2024, 1, 1), date(2024, 1, 31)),
+            vec![date(2024, 1, 3), date(2024, 1, 10), date(2024, 1, 17)]
+        );
+    }
+
+    #[test]
+    fn test_monthly_recurrence_every_n_months_on_day() {
+        let quarterly = recurring(Frequency::Monthly, 3, date(2024, 1, 10), Some(31), None);
+        assert_eq!(
+            recurring_dates(&quarterly, date(2024, 1, 1), date(2024, 12, 31)),
+            vec![
+                date(2024, 1, 31),
+                date(2024, 4, 30),
+                date(2024, 7, 31),
+                date(2024, 10, 31)
+            ]
+        );
+        assert!(recurring_dates(&quarterly, date(2024, 2, 1), date(2024, 3, 31)).is_empty());
+
+        let early_day = recurring(Frequency::Monthly, 1, date(2024, 1, 20), Some(5), None);
+        assert_eq!(
+            recurring_dates(&early_day, date(2024, 1, 1), date(2024, 2, 29)),
+            vec![date(2024, 2, 5)]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::NaiveDate;
+
+use super::{parse_amount, RowError, StatementRow};
+use crate::models::{CsvMapping, Money, SignConvention};
+
+/// Reads every data row of `data` according to `mapping`. Rows that cannot be
+/// parsed are returned as errors without stopping the rest of the file.
+pub fn parse_statement(data: &str, mapping: &CsvMapping) -> Vec<Result<StatementRow, RowError>> {
+    let delimiter = mapping
+        .delimiter
+        .as_bytes()
+        .first()
+        .copied()
+        .unwrap_or(b',');
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(mapping.has_header)
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(data.as_bytes());
+
+    reader
+        .records()
+        .filter_map(|record| match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line() as usize);
+                if record.iter().all(str::is_empty) {
+                    return None;
+                }
+                Some(match parse_record(&record, mapping) {
+                    Ok((spent_on, description, amount)) => Ok(StatementRow {
+                        line,
+                        spent_on,
+                        description,
+                        amount,
+                        fitid: None,
+                        account: None,
+                    }),
+                    Err(message) => Err(RowError { line, message }),
+                })
+            }
+            Err(e) => Some(Err(RowError {
+                line: e.position().map_or(0, |p| p.line() as usize),
+                message: e.to_string(),
+            })),
+        })
+        .collect()
+}
+
+fn parse_record(
+    record: &::csv::StringRecord,
+    mapping: &CsvMapping,
+) -> Result<(NaiveDate, String, Money), String> {
+    let field = |column: u32, name: &str| {
+        record
+            .get(column as usize)
+            .ok_or_else(|| format!("Missing {name} column {column}"))
+    };
+
+    let date = field(mapping.date_column, "date")?;
+    let spent_on = NaiveDate::parse_from_str(date, &mapping.date_format)
+        .map_err(|_| format!("Invalid date '{date}' for format '{}'", mapping.date_format))?;
+
+    let description = field(mapping.description_column, "description")?;
+    if description.is_empty() {
+        return Err("Empty description".to_string());
+    }
+
+    let raw_amount = field(mapping.amount_column, "amount")?;
+    let amount = parse_amount(raw_amount, mapping.decimal_separator)
+        .ok_or_else(|| format!("Invalid amount '{raw_amount}'"))?;
+    let amount = match mapping.sign_convention {
+        SignConvention::ExpensesNegative => -amount,
+        SignConvention::ExpensesPositive => amount,
+    };
+
+    Ok((spent_on, description.to_string(), amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DecimalSeparator;
+
+    fn mapping() -> CsvMapping {
+        CsvMapping {
+            delimiter: ",".to_string(),
+            has_header: true,
+            date_column: 0,
+            description_column: 1,
+            amount_column: 2,
+            sign_convention: SignConvention::ExpensesNegative,
+            decimal_separator: DecimalSeparator::Dot,
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_statement_normalises_sign() {
+        let data = "Date,Payee,Amount\n2024-06-01,Coffee,-3.50\n2024-06-02,Refund,10.00\n";
+        let rows = parse_statement(data, &mapping());
+
+        assert_eq!(rows.len(), 2);
+        let coffee = rows[0].as_ref().unwrap();
+        assert_eq!(coffee.line, 2);
+        assert_eq!(coffee.description, "Coffee");
+        assert_eq!(coffee.amount, Money::from_minor(350));
+        assert_eq!(rows[1].as_ref().unwrap().amount, Money::from_minor(-1000));
+    }
+
+    #[test]
+    fn test_parse_statement_reports_bad_rows() {
+        let mut mapping = mapping();
+        mapping.delimiter = ";".to_string();
+        mapping.date_format = "%d.%m.%Y".to_string();
+        mapping.decimal_separator = DecimalSeparator::Comma;
+        let data = "Datum;Text;Betrag\n01.06.2024;Miete;-950,00\n31.02.2024;Bad;-1,00\n02.06.2024;Short\n\n";
+        let rows = parse_statement(data, &mapping);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].as_ref().unwrap().amount, Money::from_minor(95000));
+        assert_eq!(rows[1].as_ref().unwrap_err().line, 3);
+        assert!(rows[2].as_ref().unwrap_err().message.contains("amount"));
+    }
+}
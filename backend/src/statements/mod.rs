@@ -0,0 +1,87 @@
+//! Parsers that turn bank statement files into transactions.
+
+pub mod csv;
+pub mod ofx;
+
+use chrono::NaiveDate;
+
+use crate::models::{DecimalSeparator, Money};
+
+/// A single statement line, normalised so that spending is positive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementRow {
+    /// 1-based line number in the source file, for reporting back to the user.
+    pub line: usize,
+    pub spent_on: NaiveDate,
+    pub description: String,
+    pub amount: Money,
+    /// The bank's unique id for the transaction, when the format carries one.
+    pub fitid: Option<String>,
+    /// The bank's id for the account the statement covers. FITIDs are only
+    /// unique within one account.
+    pub account: Option<String>,
+}
+
+/// A line that could not be read, with the reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parses a localised amount such as `-1.234,56`, `(12.00)` or `$ 1,000`.
+pub fn parse_amount(raw: &str, separator: DecimalSeparator) -> Option<Money> {
+    let (decimal, grouping) = match separator {
+        DecimalSeparator::Dot => ('.', ','),
+        DecimalSeparator::Comma => (',', '.'),
+    };
+
+    let mut value = raw.trim();
+    let mut negative = false;
+    if let Some(inner) = value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        negative = true;
+        value = inner;
+    }
+    if let Some(rest) = value.strip_suffix('-') {
+        negative = !negative;
+        value = rest;
+    }
+
+    let normalised: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != grouping && *c != '\'')
+        .filter(|c| !matches!(c, '$' | '€' | '£'))
+        .map(|c| if c == decimal { '.' } else { c })
+        .collect();
+
+    let amount: Money = normalised.parse().ok()?;
+    Some(if negative { -amount } else { amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_amount_formats() {
+        let dot = DecimalSeparator::Dot;
+        let comma = DecimalSeparator::Comma;
+        assert_eq!(parse_amount("12.34", dot), Some(Money::from_minor(1234)));
+        assert_eq!(
+            parse_amount("-1,234.5", dot),
+            Some(Money::from_minor(-123450))
+        );
+        assert_eq!(parse_amount("(7.00)", dot), Some(Money::from_minor(-700)));
+        assert_eq!(
+            parse_amount("$ 1,000", dot),
+            Some(Money::from_minor(100000))
+        );
+        assert_eq!(
+            parse_amount("1.234,56", comma),
+            Some(Money::from_minor(123456))
+        );
+        assert_eq!(parse_amount("12,5-", comma), Some(Money::from_minor(-1250)));
+        assert_eq!(parse_amount("abc", dot), None);
+        assert_eq!(parse_amount("", dot), None);
+    }
+}
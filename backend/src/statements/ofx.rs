@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use super::{parse_amount, RowError, StatementRow};
+use crate::models::DecimalSeparator;
+
+/// Reads the `STMTTRN` records of an OFX 1.x (SGML) or 2.x (XML) statement.
+///
+/// Both versions are handled by the same tag scanner: element values run up to
+/// the next tag, so the closing tags that SGML leaves out are never needed.
+/// Returns an error only when `data` is not an OFX document at all.
+pub fn parse_statement(data: &str) -> Result<Vec<Result<StatementRow, RowError>>, String> {
+    if !data.to_ascii_uppercase().contains("<OFX>") {
+        return Err("Not an OFX statement".to_string());
+    }
+
+    let mut rows = Vec::new();
+    let mut current: Option<(usize, HashMap<String, String>)> = None;
+    let mut account: Option<String> = None;
+    let mut line = 1;
+    let mut position = 0;
+
+    while let Some(start) = data[position..].find('<').map(|i| position + i) {
+        line += data[position..start].matches('\n').count();
+        let Some(end) = data[start..].find('>').map(|i| start + i) else {
+            break;
+        };
+        let tag = data[start + 1..end].trim().to_ascii_uppercase();
+        let value_end = data[end + 1..]
+            .find('<')
+            .map_or(data.len(), |i| end + 1 + i);
+        let value = data[end + 1..value_end].trim();
+        position = end + 1;
+
+        match tag.as_str() {
+            "STMTTRN" => {
+                if let Some((line, fields)) = current.replace((line, HashMap::new())) {
+                    rows.push(transaction(line, &fields, &account));
+                }
+            }
+            "/STMTTRN" | "/BANKTRANLIST" => {
+                if let Some((line, fields)) = current.take() {
+                    rows.push(transaction(line, &fields, &account));
+                }
+            }
+            _ => match current.as_mut() {
+                Some((_, fields)) if !tag.starts_with('/') && !value.is_empty() => {
+                    fields.entry(tag).or_insert_with(|| unescape(value));
+                }
+                // The statement's own account, from BANKACCTFROM or CCACCTFROM.
+                None if tag == "ACCTID" && !value.is_empty() => {
+                    account = Some(unescape(value));
+                }
+                _ => {}
+            },
+        }
+    }
+
+    if let Some((line, fields)) = current {
+        rows.push(transaction(line, &fields, &account));
+    }
+
+    Ok(rows)
+}
+
+fn transaction(
+    line: usize,
+    fields: &HashMap<String, String>,
+    account: &Option<String>,
+) -> Result<StatementRow, RowError> {
+    let error = |message: String| RowError { line, message };
+    let field = |name: &str| {
+        fields
+            .get(name)
+            .ok_or_else(|| error(format!("Missing {name}")))
+    };
+
+    let posted = field("DTPOSTED")?;
+    let spent_on = posted
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| error(format!("Invalid DTPOSTED '{posted}'")))?;
+
+    // OFX allows a comma as the decimal mark; it never uses digit grouping.
+    let raw_amount = field("TRNAMT")?;
+    let separator = if raw_amount.contains('.') {
+        DecimalSeparator::Dot
+    } else {
+        DecimalSeparator::Comma
+    };
+    let amount = parse_amount(raw_amount, separator)
+        .ok_or_else(|| error(format!("Invalid TRNAMT '{raw_amount}'")))?;
+
+    let description = ["NAME", "MEMO", "PAYEE", "TRNTYPE"]
+        .iter()
+        .find_map(|name| fields.get(*name))
+        .cloned()
+        .unwrap_or_else(|| "Imported transaction".to_string());
+
+    Ok(StatementRow {
+        line,
+        spent_on,
+        description,
+        amount: -amount,
+        fitid: fields.get("FITID").cloned(),
+        account: account.clone(),
+    })
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Money;
+
+    const SGML: &str = "OFXHEADER:100\r\nDATA:OFXSGML\r\nVERSION:102\r\n\r\n<OFX>\r\n<BANKMSGSRSV1><STMTTRNRS><STMTRS>\r\n<BANKACCTFROM>\r\n<BANKID>121000248\r\n<ACCTID>0001234567\r\n<ACCTTYPE>CHECKING\r\n</BANKACCTFROM>\r\n<BANKTRANLIST>\r\n<DTSTART>20240601\r\n<STMTTRN>\r\n<TRNTYPE>DEBIT\r\n<DTPOSTED>20240603120000[-5:EST]\r\n<TRNAMT>-42.50\r\n<FITID>T1\r\n<NAME>Grocer &amp; Sons\r\n</STMTTRN>\r\n<STMTTRN>\r\n<TRNTYPE>CREDIT\r\n<DTPOSTED>20240615\r\n<TRNAMT>1500,00\r\n<FITID>T2\r\n<MEMO>Payroll\r\n</STMTTRN>\r\n</BANKTRANLIST>\r\n</STMTRS></STMTTRNRS></BANKMSGSRSV1>\r\n</OFX>\r\n";
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<?OFX OFXHEADER="200" VERSION="220"?>
+<OFX>
+  <CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>
+    <BANKTRANLIST>
+      <STMTTRN>
+        <TRNTYPE>DEBIT</TRNTYPE>
+        <DTPOSTED>20240702</DTPOSTED>
+        <TRNAMT>-9.99</TRNAMT>
+        <FITID>X1</FITID>
+        <NAME>Streaming</NAME>
+      </STMTTRN>
+      <STMTTRN>
+        <TRNTYPE>DEBIT</TRNTYPE>
+        <DTPOSTED>2024</DTPOSTED>
+        <TRNAMT>-1.00</TRNAMT>
+      </STMTTRN>
+    </BANKTRANLIST>
+  </CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1>
+</OFX>
+"#;
+
+    #[test]
+    fn test_parse_sgml_statement() {
+        let rows = parse_statement(SGML).unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let debit = rows[0].as_ref().unwrap();
+        assert_eq!(debit.line, 14);
+        assert_eq!(debit.spent_on, NaiveDate::from_ymd_opt(2024, 6, 3).unwrap());
+        assert_eq!(debit.description, "Grocer & Sons");
+        assert_eq!(debit.amount, Money::from_minor(4250));
+        assert_eq!(debit.fitid.as_deref(), Some("T1"));
+        assert_eq!(debit.account.as_deref(), Some("0001234567"));
+
+        let credit = rows[1].as_ref().unwrap();
+        assert_eq!(credit.description, "Payroll");
+        assert_eq!(credit.amount, Money::from_minor(-150000));
+    }
+
+    #[test]
+    fn test_parse_xml_statement() {
+        let rows = parse_statement(XML).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].as_ref().unwrap().amount, Money::from_minor(999));
+        assert_eq!(rows[0].as_ref().unwrap().fitid.as_deref(), Some("X1"));
+        assert_eq!(rows[0].as_ref().unwrap().account, None);
+        assert!(rows[1].as_ref().unwrap_err().message.contains("DTPOSTED"));
+    }
+
+    #[test]
+    fn test_rejects_non_ofx() {
+        assert!(parse_statement("Date,Amount\n2024-01-01,1.00\n").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Seconds each code is valid for.
+pub const STEP_SECONDS: u64 = 30;
+
+/// Codes from one step either side of the current one are accepted, allowing
+/// for clocks that drift apart.
+const SKEW_STEPS: u64 = 1;
+
+const ISSUER: &str = "payme";
+
+/// A new 160-bit shared secret, base32 encoded as authenticator apps expect.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+/// The `otpauth://` URI an authenticator app reads, usually from a QR code.
+pub fn provisioning_uri(secret: &str, username: &str) -> Option<String> {
+    totp(secret, username).map(|totp| totp.get_url())
+}
+
+/// Finds the time step `code` was generated for, within the allowed drift of
+/// `now` (Unix seconds). Steps at or before `last_step` are refused so a code
+/// cannot be used twice.
+pub fn verify(secret: &str, code: &str, now: u64, last_step: Option<i64>) -> Option<i64> {
+    let totp = totp(secret, "")?;
+    let current = now / STEP_SECONDS;
+    (current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .map(|step| step as i64)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| totp.check(code, *step as u64 * STEP_SECONDS))
+}
+
+/// The time codes are checked against: the system clock, or a fixed time
+/// that tests set and move forward themselves.
+#[derive(Clone, Default)]
+pub struct Clock(Option<Arc<AtomicU64>>);
+
+impl Clock {
+    pub fn system() -> Self {
+        Self(None)
+    }
+
+    /// A clock stopped at `now` (Unix seconds) until [`Clock::set`] moves it.
+    pub fn fixed(now: u64) -> Self {
+        Self(Some(Arc::new(AtomicU64::new(now))))
+    }
+
+    /// Moves a fixed clock, and every clone of it, to `now`. Has no effect
+    /// on the system clock.
+    pub fn set(&self, now: u64) {
+        if let Some(fixed) = &self.0 {
+            fixed.store(now, Ordering::SeqCst);
+        }
+    }
+
+    /// The current Unix time in seconds.
+    pub fn now(&self) -> u64 {
+        match &self.0 {
+            Some(fixed) => fixed.load(Ordering::SeqCst),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+fn totp(secret: &str, username: &str) -> Option<TOTP> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    // Skew is applied by `verify` so it can tell which step matched.
+    Some(TOTP::new_unchecked(
+        Algorithm::SHA1,
+        6,
+        0,
+        STEP_SECONDS,
+        bytes,
+        Some(ISSUER.to_string()),
+        username.to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 key from RFC 6238 appendix B, "12345678901234567890".
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        // The RFC lists 8-digit codes; these are their last 6 digits.
+        for (time, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(
+                verify(RFC_SECRET, code, time, None),
+                Some((time / STEP_SECONDS) as i64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_accepts_one_step_of_drift() {
+        let now = 1111111109;
+        let step = (now / STEP_SECONDS) as i64;
+        assert_eq!(verify(RFC_SECRET, "081804", now + 30, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "081804", now - 30, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, "081804", now + 90, None), None);
+        assert_eq!(verify(RFC_SECRET, "000000", now, None), None);
+    }
+
+    #[test]
+    fn test_refuses_used_steps() {
+        let now = 1111111109;
+        let step = (now / STEP_SECONDS) as i64;
+        assert_eq!(
+            verify(RFC_SECRET, "081804", now, Some(step - 1)),
+            Some(step)
+        );
+        assert_eq!(verify(RFC_SECRET, "081804", now, Some(step)), None);
+    }
+
+    #[test]
+    fn test_fixed_clock() {
+        let clock = Clock::fixed(59);
+        let shared = clock.clone();
+        assert_eq!(verify(RFC_SECRET, "287082", clock.now(), None), Some(1));
+        shared.set(1111111109);
+        assert_eq!(clock.now(), 1111111109);
+        assert!(Clock::system().now() > 1111111109);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(
+            provisioning_uri(&secret, "jo doe").unwrap(),
+            format!("otpauth://totp/payme:jo%20doe?secret={secret}&issuer=payme")
+        );
+        assert_eq!(provisioning_uri("not base32!", "jo"), None);
+    }
+}
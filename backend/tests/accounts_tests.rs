@@ -0,0 +1,265 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_category, create_test_month, create_test_pool,
+    create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_account(
+    server: &axum_test::TestServer,
+    token: &str,
+    account: serde_json::Value,
+) -> i64 {
+    let response = server
+        .post("/api/accounts")
+        .add_header(auth_name(), auth_value(token))
+        .json(&account)
+        .await;
+    response.assert_status_ok();
+    let created: serde_json::Value = response.json();
+    created["id"].as_i64().unwrap()
+}
+
+async fn balances(server: &axum_test::TestServer, token: &str) -> Vec<(String, f64)> {
+    let response = server
+        .get("/api/accounts")
+        .add_header(auth_name(), auth_value(token))
+        .await;
+    response.assert_status_ok();
+    let accounts: Vec<serde_json::Value> = response.json();
+    accounts
+        .iter()
+        .map(|a| {
+            (
+                a["name"].as_str().unwrap().to_string(),
+                a["balance"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_balances_follow_linked_transactions() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let checking = create_account(
+        &server,
+        &token,
+        json!({ "name": "Checking", "kind": "checking", "opening_balance": 1000.0 }),
+    )
+    .await;
+    let card = create_account(
+        &server,
+        &token,
+        json!({ "name": "Visa", "kind": "credit_card", "opening_balance": -50.0 }),
+    )
+    .await;
+
+    server
+        .post(&format!("/api/months/{month_id}/income"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Salary", "amount": 2000.0, "account_id": checking }))
+        .await
+        .assert_status_ok();
+    server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "description": "Groceries", "amount": 120.25, "spent_on": "2024-06-03", "account_id": card }))
+        .await
+        .assert_status_ok();
+    server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "description": "Market", "amount": 30.0, "spent_on": "2024-06-04" }))
+        .await
+        .assert_status_ok();
+
+    assert_eq!(
+        balances(&server, &token).await,
+        vec![
+            ("Checking".to_string(), 3000.0),
+            ("Visa".to_string(), -170.25)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_update_item_account_and_unlink() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let cash = create_account(
+        &server,
+        &token,
+        json!({ "name": "Wallet", "kind": "cash", "opening_balance": 100.0 }),
+    )
+    .await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "description": "Lunch", "amount": 15.0, "spent_on": "2024-06-03" }))
+        .await;
+    let item: serde_json::Value = response.json();
+    let item_url = format!("/api/months/{month_id}/items/{}", item["id"]);
+
+    let response = server
+        .put(&item_url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "account_id": cash }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["account_id"], cash);
+    assert_eq!(balances(&server, &token).await[0].1, 85.0);
+
+    let response = server
+        .put(&item_url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 20.0 }))
+        .await;
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["account_id"], cash);
+
+    let response = server
+        .put(&item_url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "account_id": null }))
+        .await;
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["account_id"], serde_json::Value::Null);
+    assert_eq!(balances(&server, &token).await[0].1, 100.0);
+}
+
+#[tokio::test]
+async fn test_linking_another_users_account_fails() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs: i64 = sqlx::query_scalar(
+        "INSERT INTO accounts (user_id, name, kind) VALUES (?, 'Theirs', 'checking') RETURNING id",
+    )
+    .bind(other_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "description": "Lunch", "amount": 15.0, "spent_on": "2024-06-03", "account_id": theirs }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/income"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Gift", "amount": 15.0, "account_id": theirs }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server
+        .put(&format!("/api/accounts/{theirs}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Mine", "kind": "checking" }))
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_savings_endpoints_view_savings_accounts() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+    create_account(
+        &server,
+        &token,
+        json!({ "name": "Emergency", "kind": "savings", "opening_balance": 1000.0 }),
+    )
+    .await;
+    create_account(
+        &server,
+        &token,
+        json!({ "name": "Vacation", "kind": "savings", "opening_balance": 500.0 }),
+    )
+    .await;
+    create_account(
+        &server,
+        &token,
+        json!({ "name": "Checking", "kind": "checking", "opening_balance": 800.0 }),
+    )
+    .await;
+
+    let response = server
+        .get("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["savings"], 1500.0);
+
+    server
+        .put("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "savings": 2000.0 }))
+        .await
+        .assert_status_ok();
+    assert_eq!(
+        balances(&server, &token).await,
+        vec![
+            ("Emergency".to_string(), 1500.0),
+            ("Vacation".to_string(), 500.0),
+            ("Checking".to_string(), 800.0)
+        ]
+    );
+
+    server
+        .put("/api/retirement-savings")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "retirement_savings": 25000.0 }))
+        .await
+        .assert_status_ok();
+    let accounts = balances(&server, &token).await;
+    assert_eq!(accounts[3], ("Retirement".to_string(), 25000.0));
+}
+
+#[tokio::test]
+async fn test_delete_account_unlinks_transactions() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let checking = create_account(
+        &server,
+        &token,
+        json!({ "name": "Checking", "kind": "checking" }),
+    )
+    .await;
+    server
+        .post(&format!("/api/months/{month_id}/income"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Salary", "amount": 2000.0, "account_id": checking }))
+        .await
+        .assert_status_ok();
+
+    server
+        .delete(&format!("/api/accounts/{checking}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    assert!(balances(&server, &token).await.is_empty());
+    let linked: Option<i64> = sqlx::query_scalar("SELECT account_id FROM income_entries")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(linked, None);
+}
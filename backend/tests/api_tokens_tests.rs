@@ -0,0 +1,198 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_token(
+    server: &axum_test::TestServer,
+    session: &str,
+    body: serde_json::Value,
+) -> serde_json::Value {
+    let response = server
+        .post("/api/auth/tokens")
+        .add_header(auth_name(), auth_value(session))
+        .json(&body)
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+async fn status(
+    server: &axum_test::TestServer,
+    method: &str,
+    url: &str,
+    token: &serde_json::Value,
+) -> u16 {
+    let token = token["secret"].as_str().unwrap();
+    let request = match method {
+        "GET" => server.get(url),
+        "POST" => server.post(url).json(&json!({ "year": 2024, "month": 7 })),
+        _ => unreachable!(),
+    };
+    request
+        .add_header(auth_name(), auth_value(token))
+        .await
+        .status_code()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn test_create_list_and_revoke_tokens() {
+    let (server, pool, _user_id, session) = setup_with_user().await;
+    let token = create_token(
+        &server,
+        &session,
+        json!({ "name": "Backup script", "scopes": ["import_export", "read", "read"], "expires_in_days": 30 }),
+    )
+    .await;
+    let secret = token["secret"].as_str().unwrap();
+    assert!(secret.starts_with("pm_"));
+    assert!(secret.starts_with(token["prefix"].as_str().unwrap()));
+    assert_eq!(token["scopes"], json!(["read", "import_export"]));
+    assert!(token["expires_at"].is_string());
+
+    let stored: String = sqlx::query_scalar("SELECT token_hash FROM api_tokens")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(!stored.contains(&secret[3..]));
+
+    let tokens: Vec<serde_json::Value> = server
+        .get("/api/auth/tokens")
+        .add_header(auth_name(), auth_value(&session))
+        .await
+        .json();
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0]["name"], "Backup script");
+    assert!(tokens[0].get("secret").is_none());
+    assert!(tokens[0]["last_used_at"].is_null());
+
+    assert_eq!(status(&server, "GET", "/api/months", &token).await, 200);
+    let tokens: Vec<serde_json::Value> = server
+        .get("/api/auth/tokens")
+        .add_header(auth_name(), auth_value(&session))
+        .await
+        .json();
+    assert!(tokens[0]["last_used_at"].is_string());
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let url = format!("/api/auth/tokens/{}", token["id"]);
+    server
+        .delete(&url)
+        .add_header(
+            auth_name(),
+            auth_value(&generate_token(&pool, other_id, "otheruser").await),
+        )
+        .await
+        .assert_status_not_found();
+    server
+        .delete(&url)
+        .add_header(auth_name(), auth_value(&session))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(status(&server, "GET", "/api/months", &token).await, 401);
+}
+
+#[tokio::test]
+async fn test_scopes_are_enforced_per_route() {
+    let (server, _pool, _user_id, session) = setup_with_user().await;
+    let read = create_token(
+        &server,
+        &session,
+        json!({ "name": "Dashboard", "scopes": ["read"] }),
+    )
+    .await;
+    let write = create_token(
+        &server,
+        &session,
+        json!({ "name": "Sync", "scopes": ["write"] }),
+    )
+    .await;
+    let backup = create_token(
+        &server,
+        &session,
+        json!({ "name": "Backup", "scopes": ["import_export"] }),
+    )
+    .await;
+
+    assert_eq!(status(&server, "GET", "/api/months", &read).await, 200);
+    assert_eq!(status(&server, "GET", "/api/auth/me", &read).await, 200);
+    assert_eq!(status(&server, "POST", "/api/months", &read).await, 403);
+    assert_eq!(status(&server, "GET", "/api/export/json", &read).await, 403);
+
+    assert_eq!(status(&server, "POST", "/api/months", &write).await, 200);
+    assert_eq!(status(&server, "GET", "/api/months", &write).await, 200);
+    assert_eq!(
+        status(&server, "GET", "/api/export/json", &write).await,
+        403
+    );
+
+    assert_eq!(
+        status(&server, "GET", "/api/export/json", &backup).await,
+        200
+    );
+    assert_eq!(status(&server, "GET", "/api/months", &backup).await, 403);
+
+    for token in [&read, &write, &backup] {
+        assert_eq!(status(&server, "GET", "/api/auth/tokens", token).await, 403);
+        assert_eq!(
+            status(&server, "GET", "/api/auth/sessions", token).await,
+            403
+        );
+    }
+
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(read["secret"].as_str().unwrap()))
+        .json(&json!({ "year": 2024, "month": 8 }))
+        .await;
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "forbidden");
+    assert_eq!(body["message"], "This token lacks the write scope");
+}
+
+#[tokio::test]
+async fn test_expired_and_invalid_tokens() {
+    let (server, pool, _user_id, session) = setup_with_user().await;
+    let token = create_token(
+        &server,
+        &session,
+        json!({ "name": "Old", "scopes": ["read"], "expires_in_days": 1 }),
+    )
+    .await;
+    assert_eq!(status(&server, "GET", "/api/months", &token).await, 200);
+
+    sqlx::query("UPDATE api_tokens SET expires_at = datetime('now', '-1 minute')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status(&server, "GET", "/api/months", &token).await, 401);
+    let forged = json!({ "secret": "pm_0000" });
+    assert_eq!(status(&server, "GET", "/api/months", &forged).await, 401);
+
+    for body in [
+        json!({ "name": "None", "scopes": [] }),
+        json!({ "name": "", "scopes": ["read"] }),
+        json!({ "name": "Zero", "scopes": ["read"], "expires_in_days": 0 }),
+        json!({ "name": "Admin", "scopes": ["admin"] }),
+    ] {
+        let response = server
+            .post("/api/auth/tokens")
+            .add_header(auth_name(), auth_value(&session))
+            .json(&body)
+            .await;
+        assert!(response.status_code().is_client_error());
+    }
+}
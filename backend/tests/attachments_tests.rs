@@ -0,0 +1,229 @@
+mod common;
+
+use axum_test::multipart::{MultipartForm, Part};
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_item,
+    create_test_month, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+
+async fn setup_with_item() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let category = create_test_category(&pool, user_id, "Food", 400.0).await;
+    let item_id = create_test_item(&pool, month_id, category, "Dinner", 45.0, "2024-06-08").await;
+    let url = format!("/api/months/{month_id}/items/{item_id}/attachments");
+    (server, pool, month_id, token, url)
+}
+
+fn receipt(data: &[u8], file_name: &str, mime_type: &str) -> MultipartForm {
+    MultipartForm::new().add_part(
+        "file",
+        Part::bytes(data.to_vec())
+            .file_name(file_name)
+            .mime_type(mime_type),
+    )
+}
+
+async fn attachment_count(pool: &sqlx::SqlitePool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM attachments")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_upload_list_download_and_delete() {
+    let (server, _pool, _month_id, token, url) = setup_with_item().await;
+
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .multipart(receipt(
+            b"%PDF-1.4 receipt",
+            "dinner.pdf",
+            "application/pdf",
+        ))
+        .await;
+    response.assert_status_ok();
+    let attachment: serde_json::Value = response.json();
+    assert_eq!(attachment["filename"], "dinner.pdf");
+    assert_eq!(attachment["content_type"], "application/pdf");
+    assert_eq!(attachment["size"], 16);
+
+    let attachments: Vec<serde_json::Value> = server
+        .get(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(attachments.len(), 1);
+
+    let download = server
+        .get(&format!("{url}/{}", attachment["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    download.assert_status_ok();
+    assert_eq!(download.header("Content-Type"), "application/pdf");
+    assert_eq!(
+        download.header("Content-Disposition"),
+        "attachment; filename=\"dinner.pdf\""
+    );
+    assert_eq!(download.as_bytes().as_ref(), b"%PDF-1.4 receipt");
+
+    server
+        .delete(&format!("{url}/{}", attachment["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    let attachments: Vec<serde_json::Value> = server
+        .get(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert!(attachments.is_empty());
+}
+
+#[tokio::test]
+async fn test_upload_rejects_bad_files() {
+    let (server, pool, month_id, token, url) = setup_with_item().await;
+    let oversized = vec![0u8; 10 * 1024 * 1024 + 1];
+
+    for form in [
+        receipt(b"MZ", "setup.exe", "application/octet-stream"),
+        receipt(b"", "empty.png", "image/png"),
+        receipt(&oversized, "huge.jpg", "image/jpeg"),
+        receipt(b"<html><script>", "receipt.png", "image/png"),
+        receipt(b"\x89PNG\r\n\x1a\n", "receipt.pdf", "application/pdf"),
+        receipt(b"RIFF\0\0\0\0WAVE", "receipt.webp", "image/webp"),
+        MultipartForm::new().add_text("note", "no file"),
+    ] {
+        let response = server
+            .post(&url)
+            .add_header(auth_name(), auth_value(&token))
+            .multipart(form)
+            .await;
+        response.assert_status_bad_request();
+    }
+
+    close_test_month(&pool, month_id).await;
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .multipart(receipt(b"\x89PNG", "receipt.png", "image/png"))
+        .await;
+    response.assert_status_bad_request();
+    assert_eq!(attachment_count(&pool).await, 0);
+}
+
+#[tokio::test]
+async fn test_attachments_are_private() {
+    let (server, pool, _month_id, token, url) = setup_with_item().await;
+    let attachment: serde_json::Value = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .multipart(receipt(b"\xff\xd8\xff", "receipt.jpg", "image/jpeg"))
+        .await
+        .json();
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let other_token = generate_token(&pool, other_id, "otheruser").await;
+    server
+        .get(&url)
+        .add_header(auth_name(), auth_value(&other_token))
+        .await
+        .assert_status_not_found();
+    server
+        .get(&format!("{url}/{}", attachment["id"]))
+        .add_header(auth_name(), auth_value(&other_token))
+        .await
+        .assert_status_not_found();
+    server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&other_token))
+        .multipart(receipt(b"\xff\xd8\xff", "receipt.jpg", "image/jpeg"))
+        .await
+        .assert_status_not_found();
+    server
+        .delete(&format!("{url}/{}", attachment["id"]))
+        .add_header(auth_name(), auth_value(&other_token))
+        .await;
+    assert_eq!(attachment_count(&pool).await, 1);
+}
+
+#[tokio::test]
+async fn test_attachments_follow_item_through_delete_and_export() {
+    let (server, pool, month_id, token, url) = setup_with_item().await;
+    server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .multipart(receipt(b"%PDF-1.4", "dinner.pdf", "application/pdf"))
+        .await
+        .assert_status_ok();
+
+    let export = server
+        .get("/api/export")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    export.assert_status_ok();
+
+    let item_id: i64 = sqlx::query_scalar("SELECT id FROM items WHERE month_id = ?")
+        .bind(month_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    server
+        .delete(&format!("/api/months/{month_id}/items/{item_id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(attachment_count(&pool).await, 0);
+
+    server
+        .post("/api/import")
+        .add_header(auth_name(), auth_value(&token))
+        .bytes(export.into_bytes())
+        .await
+        .assert_status_ok();
+
+    let (month_id, item_id): (i64, i64) = sqlx::query_as("SELECT month_id, id FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let url = format!("/api/months/{month_id}/items/{item_id}/attachments");
+    let attachments: Vec<serde_json::Value> = server
+        .get(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(attachments.len(), 1);
+    let download = server
+        .get(&format!("{url}/{}", attachments[0]["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert_eq!(download.as_bytes().as_ref(), b"%PDF-1.4");
+}
+
+#[tokio::test]
+async fn test_upload_accepts_each_allowed_type() {
+    let (server, pool, _month_id, token, url) = setup_with_item().await;
+
+    for (data, mime_type) in [
+        (&b"\x89PNG\r\n\x1a\n"[..], "image/png"),
+        (b"RIFF\x10\0\0\0WEBPVP8 ", "image/webp"),
+        (b"\0\0\0\x18ftypheic\0\0\0\0", "image/heic"),
+    ] {
+        let response = server
+            .post(&url)
+            .add_header(auth_name(), auth_value(&token))
+            .multipart(receipt(data, "receipt", mime_type))
+            .await;
+        response.assert_status_ok();
+        let attachment: serde_json::Value = response.json();
+        assert_eq!(attachment["content_type"], mime_type);
+    }
+    assert_eq!(attachment_count(&pool).await, 3);
+}
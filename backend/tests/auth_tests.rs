@@ -15,7 +15,7 @@ async fn setup() -> axum_test::TestServer {
 async fn setup_with_user() -> (axum_test::TestServer, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool);
     let server = create_test_server(app);
     (server, user_id, token)
@@ -54,7 +54,9 @@ async fn test_register_duplicate_username() {
         }))
         .await;
 
-    response.assert_status_internal_server_error();
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "conflict");
 }
 
 #[tokio::test]
@@ -211,7 +213,7 @@ async fn test_change_username_duplicate() {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "user1", "password123").await;
     create_test_user(&pool, "user2", "password123").await;
-    let token = generate_token(user_id, "user1");
+    let token = generate_token(&pool, user_id, "user1").await;
     let app = create_app(pool);
     let server = create_test_server(app);
 
@@ -223,7 +225,41 @@ async fn test_change_username_duplicate() {
         }))
         .await;
 
-    response.assert_status_internal_server_error();
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_change_currency() {
+    let (server, _user_id, token) = setup_with_user().await;
+
+    let me: serde_json::Value = server
+        .get("/api/auth/me")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(me["currency"], "USD");
+
+    let response = server
+        .put("/api/auth/change-currency")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "currency": "EUR" }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.json::<serde_json::Value>()["currency"], "EUR");
+
+    let summary: serde_json::Value = server
+        .get("/api/months/current")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(summary["currency"], "EUR");
+
+    let response = server
+        .put("/api/auth/change-currency")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "currency": "XYZ" }))
+        .await;
+    assert!(response.status_code().is_client_error());
 }
 
 #[tokio::test]
@@ -277,7 +313,8 @@ async fn test_clear_all_data() {
         .add_header(auth_name(), auth_value(&token))
         .await;
 
-    me_response.assert_status_not_found();
+    // Deleting the account ends its sessions too.
+    me_response.assert_status_unauthorized();
 }
 
 #[tokio::test]
@@ -8,6 +8,7 @@ use axum::Router;
 use axum_test::TestServer;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{encode, EncodingKey, Header};
+use payme::db::run_migrations;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
@@ -18,6 +19,7 @@ pub struct Claims {
     pub sub: i64,
     pub username: String,
     pub exp: usize,
+    pub sid: i64,
 }
 
 /// Create an in-memory SQLite pool and run migrations
@@ -26,141 +28,15 @@ pub async fn create_test_pool() -> SqlitePool {
         .await
         .expect("Failed to create in-memory database");
 
-    run_migrations(&pool).await;
+    run_migrations(&pool)
+        .await
+        .expect("Failed to run migrations");
     pool
 }
 
-/// Run database migrations (copied from db/mod.rs to avoid circular deps)
-async fn run_migrations(pool: &SqlitePool) {
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            username TEXT NOT NULL UNIQUE,
-            password_hash TEXT NOT NULL,
-            savings REAL NOT NULL DEFAULT 0,
-            savings_goal REAL NOT NULL DEFAULT 0,
-            retirement_savings REAL NOT NULL DEFAULT 0,
-            created_at TEXT NOT NULL DEFAULT (datetime('now'))
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create users table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS fixed_expenses (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            amount REAL NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create fixed_expenses table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS budget_categories (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            default_amount REAL NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create budget_categories table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS months (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            user_id INTEGER NOT NULL,
-            year INTEGER NOT NULL,
-            month INTEGER NOT NULL,
-            is_closed INTEGER NOT NULL DEFAULT 0,
-            closed_at TEXT,
-            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-            UNIQUE(user_id, year, month)
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create months table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS income_entries (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            label TEXT NOT NULL,
-            amount REAL NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create income_entries table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS monthly_budgets (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            category_id INTEGER NOT NULL,
-            allocated_amount REAL NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
-            FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE,
-            UNIQUE(month_id, category_id)
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create monthly_budgets table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS items (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL,
-            category_id INTEGER NOT NULL,
-            description TEXT NOT NULL,
-            amount REAL NOT NULL,
-            spent_on TEXT NOT NULL,
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE,
-            FOREIGN KEY (category_id) REFERENCES budget_categories(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create items table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE IF NOT EXISTS monthly_snapshots (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            month_id INTEGER NOT NULL UNIQUE,
-            pdf_data BLOB NOT NULL,
-            created_at TEXT NOT NULL DEFAULT (datetime('now')),
-            FOREIGN KEY (month_id) REFERENCES months(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(pool)
-    .await
-    .expect("Failed to create monthly_snapshots table");
+/// Convert a dollar amount into the integer cents stored in money columns
+pub fn cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
 }
 
 /// Create a test user and return their ID
@@ -182,8 +58,20 @@ pub async fn create_test_user(pool: &SqlitePool, username: &str, password: &str)
     .expect("Failed to create test user")
 }
 
-/// Generate a JWT token for a user
-pub fn generate_token(user_id: i64, username: &str) -> String {
+/// Open a session for a user and return its id
+pub async fn create_test_session(pool: &SqlitePool, user_id: i64) -> i64 {
+    sqlx::query_scalar::<_, i64>(
+        "INSERT INTO sessions (user_id, token_hash, expires_at) VALUES (?, ?, datetime('now', '+30 days')) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(uuid::Uuid::new_v4().to_string())
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create test session")
+}
+
+/// Generate a JWT token for a user, backed by a new session
+pub async fn generate_token(pool: &SqlitePool, user_id: i64, username: &str) -> String {
     let secret = std::env::var("JWT_SECRET")
         .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string());
 
@@ -191,6 +79,7 @@ pub fn generate_token(user_id: i64, username: &str) -> String {
         sub: user_id,
         username: username.to_string(),
         exp: (Utc::now() + Duration::days(30)).timestamp() as usize,
+        sid: create_test_session(pool, user_id).await,
     };
 
     encode(
@@ -202,7 +91,7 @@ pub fn generate_token(user_id: i64, username: &str) -> String {
 }
 
 /// Generate an expired JWT token for testing
-pub fn generate_expired_token(user_id: i64, username: &str) -> String {
+pub async fn generate_expired_token(pool: &SqlitePool, user_id: i64, username: &str) -> String {
     let secret = std::env::var("JWT_SECRET")
         .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string());
 
@@ -210,6 +99,7 @@ pub fn generate_expired_token(user_id: i64, username: &str) -> String {
         sub: user_id,
         username: username.to_string(),
         exp: (Utc::now() - Duration::days(1)).timestamp() as usize,
+        sid: create_test_session(pool, user_id).await,
     };
 
     encode(
@@ -232,7 +122,7 @@ pub async fn create_test_category(
     )
     .bind(user_id)
     .bind(label)
-    .bind(default_amount)
+    .bind(cents(default_amount))
     .fetch_one(pool)
     .await
     .expect("Failed to create test category")
@@ -263,7 +153,7 @@ pub async fn create_test_fixed_expense(
     )
     .bind(user_id)
     .bind(label)
-    .bind(amount)
+    .bind(cents(amount))
     .fetch_one(pool)
     .await
     .expect("Failed to create test fixed expense")
@@ -276,7 +166,7 @@ pub async fn create_test_income(pool: &SqlitePool, month_id: i64, label: &str, a
     )
     .bind(month_id)
     .bind(label)
-    .bind(amount)
+    .bind(cents(amount))
     .fetch_one(pool)
     .await
     .expect("Failed to create test income")
@@ -297,7 +187,7 @@ pub async fn create_test_item(
     .bind(month_id)
     .bind(category_id)
     .bind(description)
-    .bind(amount)
+    .bind(cents(amount))
     .bind(spent_on)
     .fetch_one(pool)
     .await
@@ -316,7 +206,7 @@ pub async fn create_test_budget(
     )
     .bind(month_id)
     .bind(category_id)
-    .bind(allocated_amount)
+    .bind(cents(allocated_amount))
     .fetch_one(pool)
     .await
     .expect("Failed to create test budget")
@@ -0,0 +1,199 @@
+use payme::db::{latest_version, migration_status, run_migrations, MigrationError};
+use sqlx::SqlitePool;
+
+async fn create_legacy_pool() -> SqlitePool {
+    let pool = SqlitePool::connect(":memory:")
+        .await
+        .expect("Failed to create in-memory database");
+
+    sqlx::query(
+        r#"
+        CREATE TABLE users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            savings REAL NOT NULL DEFAULT 0,
+            savings_goal REAL NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        CREATE TABLE fixed_expenses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            label TEXT NOT NULL,
+            amount REAL NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        "INSERT INTO users (username, password_hash, savings, savings_goal) VALUES ('legacy', 'x', 1234.56, 0.1)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    for amount in [19.99, 0.07, 1500.0] {
+        sqlx::query("INSERT INTO fixed_expenses (user_id, label, amount) VALUES (1, 'Bill', ?)")
+            .bind(amount)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    pool
+}
+
+#[tokio::test]
+async fn test_migrations_convert_real_amounts_to_cents() {
+    let pool = create_legacy_pool().await;
+
+    run_migrations(&pool).await.expect("Migrations failed");
+
+    let (savings, savings_goal): (i64, i64) =
+        sqlx::query_as("SELECT savings, savings_goal FROM users WHERE id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(savings, 123456);
+    assert_eq!(savings_goal, 10);
+
+    let account: (String, String, i64) =
+        sqlx::query_as("SELECT name, kind, opening_balance FROM accounts WHERE user_id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        account,
+        ("Savings".to_string(), "savings".to_string(), 123456)
+    );
+
+    let amounts: Vec<i64> = sqlx::query_scalar("SELECT amount FROM fixed_expenses ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(amounts, vec![1999, 7, 150000]);
+
+    let column_type: String = sqlx::query_scalar(
+        "SELECT type FROM pragma_table_info('fixed_expenses') WHERE name = 'amount'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(column_type, "INTEGER");
+}
+
+#[tokio::test]
+async fn test_migrations_backfill_monthly_fixed_expenses() {
+    let pool = create_legacy_pool().await;
+    sqlx::query(
+        r#"
+        CREATE TABLE months (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            month INTEGER NOT NULL,
+            is_closed INTEGER NOT NULL DEFAULT 0,
+            closed_at TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+            UNIQUE(user_id, year, month)
+        );
+        INSERT INTO months (user_id, year, month, is_closed) VALUES (1, 2023, 11, 1), (1, 2023, 12, 0);
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    run_migrations(&pool).await.expect("Migrations failed");
+
+    let copies: Vec<(i64, Option<i64>, i64)> = sqlx::query_as(
+        "SELECT month_id, fixed_expense_id, amount FROM monthly_fixed_expenses ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        copies,
+        vec![
+            (1, Some(1), 1999),
+            (1, Some(2), 7),
+            (1, Some(3), 150000),
+            (2, Some(1), 1999),
+            (2, Some(2), 7),
+            (2, Some(3), 150000),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_migrations_are_idempotent() {
+    let pool = create_legacy_pool().await;
+
+    run_migrations(&pool).await.expect("First run failed");
+    run_migrations(&pool).await.expect("Second run failed");
+
+    let total: i64 = sqlx::query_scalar("SELECT SUM(amount) FROM fixed_expenses")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(total, 152006);
+}
+
+#[tokio::test]
+async fn test_migrations_record_versions() {
+    let pool = SqlitePool::connect(":memory:").await.unwrap();
+
+    run_migrations(&pool).await.expect("Migrations failed");
+
+    let versions: Vec<i64> =
+        sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(versions, (1..=latest_version()).collect::<Vec<_>>());
+
+    let status = migration_status(&pool).await.unwrap();
+    assert_eq!(status.len() as i64, latest_version());
+    assert!(status.iter().all(|m| m.is_applied()));
+}
+
+#[tokio::test]
+async fn test_migration_status_lists_pending() {
+    let pool = SqlitePool::connect(":memory:").await.unwrap();
+
+    let status = migration_status(&pool).await.unwrap();
+    assert_eq!(status.len() as i64, latest_version());
+    assert!(status.iter().all(|m| !m.is_applied()));
+    assert_eq!(status[0].name, "initial_schema");
+}
+
+#[tokio::test]
+async fn test_migrations_refuse_newer_database() {
+    let pool = SqlitePool::connect(":memory:").await.unwrap();
+
+    run_migrations(&pool).await.expect("Migrations failed");
+    sqlx::query("INSERT INTO schema_migrations (version, name) VALUES (?, 'from_the_future')")
+        .bind(latest_version() + 1)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let result = run_migrations(&pool).await;
+    assert!(matches!(
+        result,
+        Err(MigrationError::DatabaseNewer { database, binary })
+            if database == latest_version() + 1 && binary == latest_version()
+    ));
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_month,
+    create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use payme::openapi::ApiDoc;
+use serde_json::json;
+use utoipa::OpenApi;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+#[tokio::test]
+async fn test_domain_errors_have_distinct_codes() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let url = format!("/api/months/{month_id}/items");
+    let item = json!({ "category_id": 999, "description": "Lunch", "amount": 12.0, "spent_on": "2024-05-02" });
+
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&item)
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(
+        body,
+        json!({ "code": "invalid_reference", "message": "Invalid category" })
+    );
+
+    close_test_month(&pool, month_id).await;
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&item)
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(
+        body,
+        json!({ "code": "month_closed", "message": "Month is closed" })
+    );
+
+    let response = server
+        .get("/api/months/999")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_not_found();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "not_found");
+}
+
+#[tokio::test]
+async fn test_validation_errors_list_fields() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    let response = server
+        .post("/api/auth/register")
+        .json(&json!({ "username": "ab", "password": "short" }))
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "validation_failed");
+    assert_eq!(body["fields"]["username"][0]["code"], "length");
+    assert_eq!(body["fields"]["username"][0]["params"]["min"], 3);
+    assert_eq!(body["fields"]["password"][0]["code"], "length");
+    assert!(body["fields"]["password"][0]["params"]
+        .get("value")
+        .is_none());
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "description": "Costco", "amount": 30.0, "spent_on": "2024-05-20",
+            "splits": [{ "category_id": food, "amount": 40.0 }, { "category_id": food, "amount": -10.0 }]
+        }))
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "validation_failed");
+    assert!(body["fields"]["splits[1].amount"].is_array());
+}
+
+#[tokio::test]
+async fn test_out_of_range_amounts_are_rejected() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    for amount in [json!(1e14), json!(92233720368547758_u64)] {
+        let response = server
+            .post(&format!("/api/months/{month_id}/items"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({
+                "category_id": food, "description": "Yacht", "amount": amount, "spent_on": "2024-05-20"
+            }))
+            .await;
+        response.assert_status(axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "bad_request");
+        assert!(body["message"]
+            .as_str()
+            .unwrap()
+            .contains("amount out of range"));
+    }
+}
+
+#[tokio::test]
+async fn test_framework_rejections_use_envelope() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/categories")
+        .add_header(auth_name(), auth_value(&token))
+        .text("{ not json")
+        .content_type("application/json")
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "bad_request");
+    assert!(!body["message"].as_str().unwrap().is_empty());
+
+    let response = server
+        .get("/api/months?limit=many")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_bad_request();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "bad_request");
+
+    let response = server.get("/api/months").await;
+    response.assert_status_unauthorized();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "unauthorized");
+}
+
+#[tokio::test]
+async fn test_unique_violations_are_conflicts() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+    let profile = json!({
+        "name": "Bank", "date_column": 0, "description_column": 1, "amount_column": 2,
+        "sign_convention": "expenses_negative"
+    });
+
+    server
+        .post("/api/import/csv/profiles")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&profile)
+        .await
+        .assert_status_ok();
+    let response = server
+        .post("/api/import/csv/profiles")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&profile)
+        .await;
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "conflict");
+}
+
+#[test]
+fn test_openapi_documents_error_body() {
+    let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+    assert!(doc["components"]["schemas"]["ErrorBody"].is_object());
+    assert_eq!(
+        doc["paths"]["/api/months/{id}"]["get"]["responses"]["500"]["content"]["application/json"]
+            ["schema"]["$ref"],
+        "#/components/schemas/ErrorBody"
+    );
+}
@@ -11,7 +11,7 @@ use serde_json::json;
 async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool.clone());
     let server = create_test_server(app);
     (server, pool, user_id, token)
@@ -98,6 +98,72 @@ async fn test_import_json() {
     assert_eq!(exported["fixed_expenses"].as_array().unwrap().len(), 2);
     assert_eq!(exported["categories"].as_array().unwrap().len(), 2);
     assert_eq!(exported["months"].as_array().unwrap().len(), 1);
+    assert_eq!(exported["savings"], 10000.0);
+    assert_eq!(exported["retirement_savings"], 25000.0);
+    assert_eq!(exported["accounts"].as_array().unwrap().len(), 2);
+    let entries = &exported["accounts"][0]["ledger_entries"];
+    assert_eq!(entries[0]["kind"], "correction");
+    assert_eq!(entries[0]["amount"], 10000.0);
+
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+    let body: serde_json::Value = server
+        .get("/api/savings/entries")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["amount"], 10000.0);
+}
+
+#[tokio::test]
+async fn test_json_round_trip_keeps_account_links() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let account_id: i64 = sqlx::query_scalar(
+        "INSERT INTO accounts (user_id, name, kind, opening_balance) VALUES (?, 'Card', 'credit_card', 0) RETURNING id",
+    )
+    .bind(user_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO items (month_id, category_id, description, amount, spent_on, account_id) VALUES (?, ?, 'Groceries', 4000, '2024-06-15', ?)",
+    )
+    .bind(month_id)
+    .bind(cat_id)
+    .bind(account_id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let exported: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(exported["months"][0]["items"][0]["account"], "Card");
+
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+
+    let accounts: Vec<serde_json::Value> = server
+        .get("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0]["kind"], "credit_card");
+    assert_eq!(accounts[0]["balance"], -40.0);
 }
 
 #[tokio::test]
@@ -190,3 +256,239 @@ async fn test_import_json_replaces_existing() {
     assert_eq!(categories.len(), 1);
     assert_eq!(categories[0]["label"], "New Category");
 }
+
+async fn open_export(bytes: &[u8]) -> (sqlx::SqlitePool, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("payme-test-{}.db", uuid::Uuid::new_v4()));
+    std::fs::write(&path, bytes).unwrap();
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite:{}", path.display()))
+        .await
+        .unwrap();
+    (pool, path)
+}
+
+#[tokio::test]
+async fn test_export_sqlite_contains_only_caller_data() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    create_test_fixed_expense(&pool, user_id, "Rent", 1500.0).await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    create_test_item(&pool, month_id, cat_id, "Groceries", 150.0, "2024-06-15").await;
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    create_test_fixed_expense(&pool, other_id, "Secret", 999.0).await;
+    let other_cat = create_test_category(&pool, other_id, "Hidden", 100.0).await;
+    let other_month = create_test_month(&pool, other_id, 2024, 6).await;
+    create_test_income(&pool, other_month, "Other salary", 9000.0).await;
+    create_test_item(&pool, other_month, other_cat, "Private", 42.0, "2024-06-02").await;
+
+    let response = server
+        .get("/api/export")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+
+    response.assert_status_ok();
+    let (export, path) = open_export(response.as_bytes()).await;
+
+    let users: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT id, username, password_hash FROM users")
+            .fetch_all(&export)
+            .await
+            .unwrap();
+    assert_eq!(
+        users,
+        vec![(user_id, "testuser".to_string(), String::new())]
+    );
+
+    let labels: Vec<String> = sqlx::query_scalar("SELECT label FROM fixed_expenses")
+        .fetch_all(&export)
+        .await
+        .unwrap();
+    assert_eq!(labels, vec!["Rent"]);
+
+    let items: Vec<String> = sqlx::query_scalar("SELECT description FROM items")
+        .fetch_all(&export)
+        .await
+        .unwrap();
+    assert_eq!(items, vec!["Groceries"]);
+
+    let income: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM income_entries")
+        .fetch_one(&export)
+        .await
+        .unwrap();
+    assert_eq!(income, 0);
+
+    let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations")
+        .fetch_one(&export)
+        .await
+        .unwrap();
+    assert_eq!(version, payme::db::latest_version());
+
+    export.close().await;
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_import_sqlite_round_trip() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    create_test_fixed_expense(&pool, user_id, "Rent", 1500.0).await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    create_test_income(&pool, month_id, "Salary", 5000.0).await;
+    create_test_budget(&pool, month_id, cat_id, 500.0).await;
+    create_test_item(&pool, month_id, cat_id, "Groceries", 150.0, "2024-06-15").await;
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    create_test_category(&pool, other_id, "Hidden", 100.0).await;
+
+    let before: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+
+    let export = server
+        .get("/api/export")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    export.assert_status_ok();
+
+    create_test_fixed_expense(&pool, user_id, "Added later", 10.0).await;
+
+    let response = server
+        .post("/api/import")
+        .add_header(auth_name(), auth_value(&token))
+        .bytes(export.into_bytes())
+        .await;
+    response.assert_status_ok();
+
+    let after: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(before, after);
+
+    let other_categories: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM budget_categories WHERE user_id = ?")
+            .bind(other_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(other_categories, 1);
+}
+
+#[tokio::test]
+async fn test_import_sqlite_rejects_non_database() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/import")
+        .add_header(auth_name(), auth_value(&token))
+        .bytes("not a database".into())
+        .await;
+
+    response.assert_status_bad_request();
+}
+
+/// Exports the caller's database, lets `craft` tamper with it, and uploads
+/// the result.
+async fn import_crafted(
+    server: &axum_test::TestServer,
+    token: &str,
+    craft: &[&str],
+) -> axum_test::TestResponse {
+    let response = server
+        .get("/api/export")
+        .add_header(auth_name(), auth_value(token))
+        .await;
+    response.assert_status_ok();
+    let (export, path) = open_export(response.as_bytes()).await;
+    let mut conn = export.acquire().await.unwrap();
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    for statement in craft {
+        sqlx::query(statement).execute(&mut *conn).await.unwrap();
+    }
+    drop(conn);
+    export.close().await;
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    server
+        .post("/api/import")
+        .add_header(auth_name(), auth_value(token))
+        .bytes(bytes.into())
+        .await
+}
+
+#[tokio::test]
+async fn test_import_sqlite_ignores_crafted_column_names() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    create_test_category(&pool, user_id, "Food", 500.0).await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    create_test_category(&pool, other_id, "Hidden", 100.0).await;
+
+    let response = import_crafted(
+        &server,
+        &token,
+        &[r#"ALTER TABLE budget_categories ADD COLUMN "x) SELECT 1; DELETE FROM users; --" TEXT"#],
+    )
+    .await;
+    response.assert_status_ok();
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 2);
+    let labels: Vec<String> =
+        sqlx::query_scalar("SELECT label FROM budget_categories ORDER BY label")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(labels, vec!["Food", "Hidden"]);
+}
+
+#[tokio::test]
+async fn test_import_sqlite_rejects_references_outside_the_file() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    create_test_item(&pool, month_id, cat_id, "Groceries", 150.0, "2024-06-15").await;
+    create_test_income(&pool, month_id, "Salary", 5000.0).await;
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let other_cat = create_test_category(&pool, other_id, "Hidden", 100.0).await;
+    let other_month = create_test_month(&pool, other_id, 2024, 6).await;
+    create_test_item(&pool, other_month, other_cat, "Private", 42.0, "2024-06-02").await;
+
+    // Ids that, once shifted by the import, would point at the other user's rows.
+    for craft in [
+        "UPDATE items SET month_id = month_id - 1000",
+        "UPDATE items SET category_id = -5",
+        "UPDATE income_entries SET month_id = 0",
+    ] {
+        let response = import_crafted(&server, &token, &[craft]).await;
+        response.assert_status_bad_request();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["code"], "bad_request");
+    }
+
+    let owners: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT i.description, m.user_id FROM items i JOIN months m ON i.month_id = m.id ORDER BY i.description",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        owners,
+        vec![
+            ("Groceries".to_string(), user_id),
+            ("Private".to_string(), other_id)
+        ]
+    );
+}
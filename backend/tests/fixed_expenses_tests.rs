@@ -1,8 +1,8 @@
 mod common;
 
 use common::{
-    auth_name, auth_value, create_test_fixed_expense, create_test_pool, create_test_server,
-    create_test_user, generate_token,
+    auth_name, auth_value, close_test_month, create_test_fixed_expense, create_test_pool,
+    create_test_server, create_test_user, generate_token,
 };
 use payme::create_app;
 use serde_json::json;
@@ -10,7 +10,7 @@ use serde_json::json;
 async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool.clone());
     let server = create_test_server(app);
     (server, pool, user_id, token)
@@ -110,3 +110,155 @@ async fn test_delete_fixed_expense() {
     let body: Vec<serde_json::Value> = list_response.json();
     assert!(body.is_empty());
 }
+
+/// Creates a month through the API so it gets a copy of the fixed expenses.
+async fn create_month(server: &axum_test::TestServer, token: &str, year: i32, month: i32) -> i64 {
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(token))
+        .json(&json!({ "year": year, "month": month }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    body["id"].as_i64().unwrap()
+}
+
+async fn month_fixed(
+    server: &axum_test::TestServer,
+    token: &str,
+    month_id: i64,
+) -> Vec<(String, f64)> {
+    let body: serde_json::Value = server
+        .get(&format!("/api/months/{}", month_id))
+        .add_header(auth_name(), auth_value(token))
+        .await
+        .json();
+    body["fixed_expenses"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["label"].as_str().unwrap().to_string(),
+                e["amount"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_update_fixed_expense_leaves_closed_months_alone() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let expense_id = create_test_fixed_expense(&pool, user_id, "Rent", 1500.0).await;
+    let may = create_month(&server, &token, 2024, 5).await;
+    let june = create_month(&server, &token, 2024, 6).await;
+    close_test_month(&pool, may).await;
+
+    server
+        .put(&format!("/api/fixed-expenses/{}", expense_id))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 1600.0 }))
+        .await
+        .assert_status_ok();
+
+    assert_eq!(
+        month_fixed(&server, &token, may).await,
+        vec![("Rent".to_string(), 1500.0)]
+    );
+    assert_eq!(
+        month_fixed(&server, &token, june).await,
+        vec![("Rent".to_string(), 1600.0)]
+    );
+
+    let stats: serde_json::Value = server
+        .get("/api/stats")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let fixed: Vec<f64> = stats["monthly_trends"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["total_fixed"].as_f64().unwrap())
+        .collect();
+    assert!(fixed.contains(&1500.0));
+    assert!(fixed.contains(&1600.0));
+}
+
+#[tokio::test]
+async fn test_update_fixed_expense_within_range() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let expense_id = create_test_fixed_expense(&pool, user_id, "Rent", 1500.0).await;
+    let june = create_month(&server, &token, 2024, 6).await;
+    let july = create_month(&server, &token, 2024, 7).await;
+
+    server
+        .put(&format!("/api/fixed-expenses/{}?from=2024-07", expense_id))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 1600.0 }))
+        .await
+        .assert_status_ok();
+
+    assert_eq!(month_fixed(&server, &token, june).await[0].1, 1500.0);
+    assert_eq!(month_fixed(&server, &token, july).await[0].1, 1600.0);
+}
+
+#[tokio::test]
+async fn test_create_and_delete_fixed_expense_in_open_months() {
+    let (server, pool, _user_id, token) = setup_with_user().await;
+
+    let may = create_month(&server, &token, 2024, 5).await;
+    let june = create_month(&server, &token, 2024, 6).await;
+    close_test_month(&pool, may).await;
+
+    let response = server
+        .post("/api/fixed-expenses")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Gym", "amount": 30.0 }))
+        .await;
+    response.assert_status_ok();
+    let expense: serde_json::Value = response.json();
+
+    assert!(month_fixed(&server, &token, may).await.is_empty());
+    assert_eq!(
+        month_fixed(&server, &token, june).await,
+        vec![("Gym".to_string(), 30.0)]
+    );
+    let july = create_month(&server, &token, 2024, 7).await;
+
+    server
+        .delete(&format!("/api/fixed-expenses/{}?to=2024-06", expense["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    assert!(month_fixed(&server, &token, june).await.is_empty());
+    assert_eq!(
+        month_fixed(&server, &token, july).await,
+        vec![("Gym".to_string(), 30.0)]
+    );
+}
+
+#[tokio::test]
+async fn test_fixed_expense_invalid_range() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let expense_id = create_test_fixed_expense(&pool, user_id, "Rent", 1500.0).await;
+
+    for range in [
+        "from=2024",
+        "to=2024-13",
+        "from=June",
+        "from=2147483647-12",
+        "to=10000-01",
+    ] {
+        let response = server
+            .put(&format!("/api/fixed-expenses/{}?{}", expense_id, range))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "amount": 1600.0 }))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
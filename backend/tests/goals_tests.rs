@@ -0,0 +1,252 @@
+mod common;
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use common::{
+    auth_name, auth_value, create_test_month, create_test_pool, create_test_server,
+    create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_goal(
+    server: &axum_test::TestServer,
+    token: &str,
+    goal: serde_json::Value,
+) -> serde_json::Value {
+    let response = server
+        .post("/api/goals")
+        .add_header(auth_name(), auth_value(token))
+        .json(&goal)
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+/// A date in the month `months` after the current one.
+fn months_ahead(months: u32) -> NaiveDate {
+    Utc::now().date_naive().with_day(10).unwrap() + Months::new(months)
+}
+
+#[tokio::test]
+async fn test_allocations_track_goal_progress() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+    let goal = create_goal(
+        &server,
+        &token,
+        json!({ "name": "Vacation", "target_amount": 1200.0, "target_date": months_ahead(2) }),
+    )
+    .await;
+    assert_eq!(goal["saved"], 0.0);
+    assert_eq!(goal["required_monthly"], 400.0);
+
+    for amount in [500.0, -200.0] {
+        server
+            .post(&format!("/api/goals/{}/allocations", goal["id"]))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "amount": amount }))
+            .await
+            .assert_status_ok();
+    }
+
+    let goals: Vec<serde_json::Value> = server
+        .get("/api/goals")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(goals[0]["saved"], 300.0);
+    assert_eq!(goals[0]["remaining"], 900.0);
+    assert_eq!(goals[0]["required_monthly"], 300.0);
+
+    let allocations: Vec<serde_json::Value> = server
+        .get(&format!("/api/goals/{}/allocations", goal["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(allocations.len(), 2);
+    assert_eq!(
+        allocations[0]["allocated_on"],
+        Utc::now().date_naive().to_string()
+    );
+
+    server
+        .delete(&format!(
+            "/api/goals/{}/allocations/{}",
+            goal["id"], allocations[0]["id"]
+        ))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    let goals: Vec<serde_json::Value> = server
+        .get("/api/goals")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(goals[0]["saved"], 500.0);
+}
+
+#[tokio::test]
+async fn test_linked_account_goal_uses_balance() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+    let account: serde_json::Value = server
+        .post("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Emergency", "kind": "savings", "opening_balance": 2500.0 }))
+        .await
+        .json();
+    let goal = create_goal(
+        &server,
+        &token,
+        json!({ "name": "Emergency fund", "target_amount": 2000.0, "target_date": "2020-01-01", "account_id": account["id"] }),
+    )
+    .await;
+    assert_eq!(goal["saved"], 2500.0);
+    assert_eq!(goal["remaining"], 0.0);
+    assert_eq!(goal["required_monthly"], 0.0);
+
+    let response = server
+        .post(&format!("/api/goals/{}/allocations", goal["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 10.0 }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server
+        .put(&format!("/api/goals/{}", goal["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Emergency fund", "target_amount": 4000.0, "target_date": "2020-01-01", "account_id": account["id"] }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["required_monthly"], 1500.0);
+}
+
+#[tokio::test]
+async fn test_goal_validation_and_ordering() {
+    let (server, pool, _user_id, token) = setup_with_user().await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs: i64 = sqlx::query_scalar(
+        "INSERT INTO accounts (user_id, name, kind) VALUES (?, 'Theirs', 'savings') RETURNING id",
+    )
+    .bind(other_id)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    for goal in [
+        json!({ "name": "", "target_amount": 100.0, "target_date": "2030-01-01" }),
+        json!({ "name": "Car", "target_amount": -100.0, "target_date": "2030-01-01" }),
+        json!({ "name": "Car", "target_amount": 100.0, "target_date": "2030-01-01", "priority": -1 }),
+        json!({ "name": "Car", "target_amount": 100.0, "target_date": "2030-01-01", "account_id": theirs }),
+    ] {
+        let response = server
+            .post("/api/goals")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&goal)
+            .await;
+        response.assert_status_bad_request();
+    }
+
+    create_goal(
+        &server,
+        &token,
+        json!({ "name": "Car", "target_amount": 100.0, "target_date": "2030-01-01", "priority": 2 }),
+    )
+    .await;
+    create_goal(
+        &server,
+        &token,
+        json!({ "name": "House", "target_amount": 100.0, "target_date": "2035-01-01", "priority": 1 }),
+    )
+    .await;
+    let goals: Vec<serde_json::Value> = server
+        .get("/api/goals")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let names: Vec<&str> = goals.iter().map(|g| g["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["House", "Car"]);
+
+    let response = server
+        .put("/api/goals/999")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Car", "target_amount": 100.0, "target_date": "2030-01-01" }))
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_goals_in_month_summary_and_export() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    server
+        .post("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Emergency", "kind": "savings", "opening_balance": 800.0 }))
+        .await
+        .assert_status_ok();
+    let goal = create_goal(
+        &server,
+        &token,
+        json!({ "name": "Bike", "target_amount": 900.0, "target_date": "2030-01-01" }),
+    )
+    .await;
+    server
+        .post(&format!("/api/goals/{}/allocations", goal["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 150.0, "allocated_on": "2024-06-02" }))
+        .await
+        .assert_status_ok();
+    let accounts: Vec<serde_json::Value> = server
+        .get("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    create_goal(
+        &server,
+        &token,
+        json!({ "name": "Buffer", "target_amount": 1000.0, "target_date": "2030-01-01", "account_id": accounts[0]["id"] }),
+    )
+    .await;
+
+    let summary: serde_json::Value = server
+        .get(&format!("/api/months/{month_id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(summary["goals"][0]["name"], "Bike");
+    assert_eq!(summary["goals"][0]["saved"], 150.0);
+    assert_eq!(summary["goals"][1]["saved"], 800.0);
+
+    let exported: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(exported["goals"][1]["account"], "Emergency");
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+
+    let goals: Vec<serde_json::Value> = server
+        .get("/api/goals")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let progress: Vec<(&str, f64)> = goals
+        .iter()
+        .map(|g| (g["name"].as_str().unwrap(), g["saved"].as_f64().unwrap()))
+        .collect();
+    assert_eq!(progress, vec![("Bike", 150.0), ("Buffer", 800.0)]);
+}
@@ -0,0 +1,425 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_month,
+    create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+const STATEMENT: &str = "Date,Description,Amount\n\
+    2024-05-30,Bakery,-4.20\n\
+    2024-06-01,Supermarket,-82.15\n\
+    2024-06-02,Salary,2500.00\n\
+    2024-06-03,Pharmacy,-12.00\n\
+    not-a-date,Broken,-1.00\n";
+
+fn mapping() -> serde_json::Value {
+    json!({
+        "date_column": 0,
+        "description_column": 1,
+        "amount_column": 2,
+        "sign_convention": "expenses_negative"
+    })
+}
+
+#[tokio::test]
+async fn test_preview_csv_import() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    create_test_month(&pool, user_id, 2024, 6).await;
+
+    let response = server
+        .post("/api/import/csv/preview")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "csv": STATEMENT, "mapping": mapping(), "category_id": food }))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+
+    let rows = body["rows"].as_array().unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[1]["description"], "Supermarket");
+    assert_eq!(rows[1]["amount"], 82.15);
+    assert_eq!(rows[1]["line"], 3);
+
+    let skipped = body["skipped"].as_array().unwrap();
+    assert_eq!(skipped.len(), 2);
+    assert_eq!(skipped[0]["line"], 4);
+    assert_eq!(skipped[1]["line"], 6);
+
+    assert_eq!(body["new_months"], json!([{ "year": 2024, "month": 5 }]));
+
+    let items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(items, 0);
+}
+
+#[tokio::test]
+async fn test_import_csv_creates_items_and_months() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let health = create_test_category(&pool, user_id, "Health", 50.0).await;
+
+    let response = server
+        .post("/api/import/csv")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "csv": STATEMENT,
+            "mapping": mapping(),
+            "category_id": food,
+            "category_overrides": [{ "line": 5, "category_id": health }]
+        }))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["imported"], 3);
+    assert_eq!(body["months_created"], 2);
+
+    let items: Vec<(String, i64, i64)> =
+        sqlx::query_as("SELECT description, amount, category_id FROM items ORDER BY spent_on")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        items,
+        vec![
+            ("Bakery".to_string(), 420, food),
+            ("Supermarket".to_string(), 8215, food),
+            ("Pharmacy".to_string(), 1200, health),
+        ]
+    );
+
+    let budgets: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM monthly_budgets")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(budgets, 4);
+}
+
+#[tokio::test]
+async fn test_import_csv_skips_closed_months() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let may = create_test_month(&pool, user_id, 2024, 5).await;
+    close_test_month(&pool, may).await;
+
+    let response = server
+        .post("/api/import/csv")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "csv": STATEMENT, "mapping": mapping(), "category_id": food }))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["imported"], 2);
+    assert!(body["skipped"][0]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("closed"));
+}
+
+#[tokio::test]
+async fn test_import_csv_with_profile() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    let response = server
+        .post("/api/import/csv/profiles")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "name": "Sparkasse",
+            "delimiter": ";",
+            "date_column": 1,
+            "description_column": 0,
+            "amount_column": 2,
+            "sign_convention": "expenses_negative",
+            "decimal_separator": "comma",
+            "date_format": "%d.%m.%Y",
+            "category_id": food
+        }))
+        .await;
+    response.assert_status_ok();
+    let profile: serde_json::Value = response.json();
+    assert_eq!(profile["name"], "Sparkasse");
+    assert_eq!(profile["has_header"], true);
+
+    let response = server
+        .get("/api/import/csv/profiles")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    let profiles: Vec<serde_json::Value> = response.json();
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0]["decimal_separator"], "comma");
+
+    let response = server
+        .post("/api/import/csv")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "csv": "Text;Datum;Betrag\nMiete;01.06.2024;-1.250,00\n",
+            "profile_id": profile["id"]
+        }))
+        .await;
+    response.assert_status_ok();
+
+    let amount: i64 = sqlx::query_scalar("SELECT amount FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(amount, 125000);
+}
+
+#[tokio::test]
+async fn test_update_and_delete_csv_profile() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let mut profile = mapping();
+    profile["name"] = json!("Bank");
+    let created: serde_json::Value = server
+        .post("/api/import/csv/profiles")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&profile)
+        .await
+        .json();
+    let id = created["id"].as_i64().unwrap();
+
+    profile["name"] = json!("Card");
+    profile["sign_convention"] = json!("expenses_positive");
+    let response = server
+        .put(&format!("/api/import/csv/profiles/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&profile)
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["name"], "Card");
+    assert_eq!(updated["sign_convention"], "expenses_positive");
+
+    let response = server
+        .delete(&format!("/api/import/csv/profiles/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let response = server
+        .put(&format!("/api/import/csv/profiles/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&profile)
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_import_csv_rejects_foreign_category() {
+    let (server, pool, _user_id, token) = setup_with_user().await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let other_category = create_test_category(&pool, other_id, "Theirs", 10.0).await;
+
+    let response = server
+        .post("/api/import/csv")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "csv": STATEMENT, "mapping": mapping(), "category_id": other_category }))
+        .await;
+
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_import_csv_requires_mapping() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/import/csv/preview")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "csv": STATEMENT }))
+        .await;
+
+    response.assert_status_bad_request();
+}
+
+const OFX: &str = "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\n\n<OFX>\n<BANKMSGSRSV1><STMTTRNRS><STMTRS>\n<BANKTRANLIST>\n\
+    <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20240603\n<TRNAMT>-42.50\n<FITID>T1\n<NAME>Grocer\n</STMTTRN>\n\
+    <STMTTRN>\n<TRNTYPE>CREDIT\n<DTPOSTED>20240615\n<TRNAMT>1500.00\n<FITID>T2\n<NAME>Payroll\n</STMTTRN>\n\
+    <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20240701\n<TRNAMT>-9.99\n<FITID>T3\n<NAME>Streaming\n</STMTTRN>\n\
+    </BANKTRANLIST>\n</STMTRS></STMTTRNRS></BANKMSGSRSV1>\n</OFX>\n";
+
+#[tokio::test]
+async fn test_import_ofx_creates_items_and_income() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    let response = server
+        .post("/api/import/ofx")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "ofx": OFX, "category_id": food }))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["imported"], 2);
+    assert_eq!(body["income_imported"], 1);
+    assert_eq!(body["months_created"], 2);
+
+    let items: Vec<(String, i64, String)> =
+        sqlx::query_as("SELECT description, amount, fitid FROM items ORDER BY spent_on")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        items,
+        vec![
+            ("Grocer".to_string(), 4250, "T1".to_string()),
+            ("Streaming".to_string(), 999, "T3".to_string()),
+        ]
+    );
+
+    let income: (String, i64, i32) = sqlx::query_as(
+        "SELECT e.label, e.amount, m.month FROM income_entries e JOIN months m ON e.month_id = m.id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(income, ("Payroll".to_string(), 150000, 6));
+}
+
+#[tokio::test]
+async fn test_import_ofx_twice_skips_duplicates() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let request = json!({ "ofx": OFX, "category_id": food });
+
+    server
+        .post("/api/import/ofx")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&request)
+        .await
+        .assert_status_ok();
+
+    let preview: serde_json::Value = server
+        .post("/api/import/ofx/preview")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&request)
+        .await
+        .json();
+    assert_eq!(preview["rows"].as_array().unwrap().len(), 0);
+    assert_eq!(preview["income"].as_array().unwrap().len(), 0);
+    assert_eq!(preview["skipped"][0]["reason"], "Already imported");
+
+    let response = server
+        .post("/api/import/ofx")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&request)
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["imported"], 0);
+    assert_eq!(body["skipped"].as_array().unwrap().len(), 3);
+
+    let items: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(items, 2);
+}
+
+/// A one-transaction statement for the account `acctid`, posted on `posted`.
+fn statement_for(acctid: &str, posted: &str) -> String {
+    format!(
+        "<OFX>\n<BANKMSGSRSV1><STMTTRNRS><STMTRS>\n<BANKACCTFROM>\n<ACCTID>{acctid}\n</BANKACCTFROM>\n<BANKTRANLIST>\n\
+        <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>{posted}\n<TRNAMT>-42.50\n<FITID>1\n<NAME>Grocer\n</STMTTRN>\n\
+        </BANKTRANLIST>\n</STMTRS></STMTTRNRS></BANKMSGSRSV1>\n</OFX>\n"
+    )
+}
+
+#[tokio::test]
+async fn test_import_ofx_dedupes_per_account() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    for (acctid, imported) in [("111", 1), ("222", 1), ("111", 0)] {
+        let response = server
+            .post("/api/import/ofx")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "ofx": statement_for(acctid, "20240603"), "category_id": food }))
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["imported"], imported, "account {acctid}");
+    }
+
+    let accounts: Vec<String> =
+        sqlx::query_scalar("SELECT fitid_account FROM items ORDER BY fitid_account")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(accounts, vec!["111", "222"]);
+
+    // The database refuses a second copy even if the checks are raced past.
+    let copy = sqlx::query(
+        "INSERT INTO items (month_id, category_id, description, amount, spent_on, fitid, fitid_account) SELECT month_id, category_id, description, amount, spent_on, fitid, fitid_account FROM items LIMIT 1",
+    )
+    .execute(&pool)
+    .await;
+    assert!(copy
+        .unwrap_err()
+        .as_database_error()
+        .unwrap()
+        .is_unique_violation());
+}
+
+#[tokio::test]
+async fn test_import_ofx_dedupes_per_month() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+
+    // Checked in the same scope as the unique index: the month of the date.
+    for (posted, imported) in [("20240603", 1), ("20240703", 1), ("20240610", 0)] {
+        let request = json!({ "ofx": statement_for("111", posted), "category_id": food });
+        let preview: serde_json::Value = server
+            .post("/api/import/ofx/preview")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&request)
+            .await
+            .json();
+        assert_eq!(
+            preview["rows"].as_array().unwrap().len(),
+            imported,
+            "{posted}"
+        );
+
+        let response = server
+            .post("/api/import/ofx")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&request)
+            .await;
+        response.assert_status_ok();
+        let body: serde_json::Value = response.json();
+        assert_eq!(body["imported"], imported, "{posted}");
+    }
+}
+
+#[tokio::test]
+async fn test_import_ofx_rejects_other_formats() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/import/ofx")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "ofx": STATEMENT }))
+        .await;
+
+    response.assert_status_bad_request();
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_template(
+    server: &axum_test::TestServer,
+    token: &str,
+    template: serde_json::Value,
+) -> serde_json::Value {
+    let response = server
+        .post("/api/income-templates")
+        .add_header(auth_name(), auth_value(token))
+        .json(&template)
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+async fn month_income(
+    server: &axum_test::TestServer,
+    token: &str,
+    year: i32,
+    month: i32,
+) -> Vec<(String, f64)> {
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(token))
+        .json(&json!({ "year": year, "month": month }))
+        .await;
+    response.assert_status_ok();
+    let created: serde_json::Value = response.json();
+
+    let entries: Vec<serde_json::Value> = server
+        .get(&format!("/api/months/{}/income", created["id"]))
+        .add_header(auth_name(), auth_value(token))
+        .await
+        .json();
+    entries
+        .iter()
+        .map(|e| {
+            (
+                e["label"].as_str().unwrap().to_string(),
+                e["amount"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_create_and_list_income_templates() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let template = create_template(
+        &server,
+        &token,
+        json!({ "label": "Paycheck", "amount": 1250.5, "schedule": "biweekly", "anchor_date": "2024-01-05" }),
+    )
+    .await;
+    assert_eq!(template["schedule"], "biweekly");
+    assert_eq!(template["anchor_date"], "2024-01-05");
+
+    let response = server
+        .get("/api/income-templates")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let templates: Vec<serde_json::Value> = response.json();
+    assert_eq!(templates.len(), 1);
+    assert_eq!(templates[0]["amount"], 1250.5);
+}
+
+#[tokio::test]
+async fn test_income_template_requires_anchor_date() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    for schedule in ["biweekly", "annual"] {
+        let response = server
+            .post("/api/income-templates")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "label": "Pay", "amount": 100.0, "schedule": schedule }))
+            .await;
+        response.assert_status_bad_request();
+    }
+
+    let response = server
+        .post("/api/income-templates")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "", "amount": 100.0, "schedule": "monthly" }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_new_months_get_scheduled_income() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    create_template(
+        &server,
+        &token,
+        json!({ "label": "Paycheck", "amount": 1000.0, "schedule": "biweekly", "anchor_date": "2024-01-05" }),
+    )
+    .await;
+    create_template(
+        &server,
+        &token,
+        json!({ "label": "Rent share", "amount": 300.0, "schedule": "monthly" }),
+    )
+    .await;
+    create_template(
+        &server,
+        &token,
+        json!({ "label": "Bonus", "amount": 2000.0, "schedule": "annual", "anchor_date": "2023-12-15" }),
+    )
+    .await;
+
+    let march = month_income(&server, &token, 2024, 3).await;
+    assert_eq!(
+        march,
+        vec![
+            ("Paycheck".to_string(), 1000.0),
+            ("Paycheck".to_string(), 1000.0),
+            ("Paycheck".to_string(), 1000.0),
+            ("Rent share".to_string(), 300.0),
+        ]
+    );
+
+    let december = month_income(&server, &token, 2024, 12).await;
+    assert_eq!(december.iter().filter(|(l, _)| l == "Paycheck").count(), 2);
+    assert!(december.contains(&("Bonus".to_string(), 2000.0)));
+
+    let before_anchor = month_income(&server, &token, 2023, 11).await;
+    assert_eq!(before_anchor, vec![("Rent share".to_string(), 300.0)]);
+}
+
+#[tokio::test]
+async fn test_update_and_delete_income_template() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    let template = create_template(
+        &server,
+        &token,
+        json!({ "label": "Salary", "amount": 3000.0, "schedule": "monthly" }),
+    )
+    .await;
+    let id = template["id"].as_i64().unwrap();
+
+    let response = server
+        .put(&format!("/api/income-templates/{}", id))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Salary", "amount": 1500.0, "schedule": "semi_monthly" }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(
+        month_income(&server, &token, 2024, 5).await,
+        vec![
+            ("Salary".to_string(), 1500.0),
+            ("Salary".to_string(), 1500.0)
+        ]
+    );
+
+    server
+        .delete(&format!("/api/income-templates/{}", id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert!(month_income(&server, &token, 2024, 6).await.is_empty());
+
+    let response = server
+        .put(&format!("/api/income-templates/{}", id))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "label": "Salary", "amount": 1500.0, "schedule": "monthly" }))
+        .await;
+    response.assert_status_not_found();
+}
@@ -0,0 +1,224 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_budget, create_test_category, create_test_month,
+    create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+fn spent_by_category(summary: &serde_json::Value) -> Vec<(String, f64)> {
+    summary["budgets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| {
+            (
+                b["category_label"].as_str().unwrap().to_string(),
+                b["spent_amount"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_split_item_counts_towards_each_category() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let groceries = create_test_category(&pool, user_id, "Groceries", 400.0).await;
+    let household = create_test_category(&pool, user_id, "Household", 100.0).await;
+    let clothing = create_test_category(&pool, user_id, "Clothing", 100.0).await;
+    for category in [groceries, household, clothing] {
+        create_test_budget(&pool, month_id, category, 100.0).await;
+    }
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "description": "Costco", "amount": 180.0, "spent_on": "2024-06-08",
+            "splits": [
+                { "category_id": groceries, "amount": 120.0 },
+                { "category_id": household, "amount": 35.5 },
+                { "category_id": clothing, "amount": 24.5 }
+            ]
+        }))
+        .await;
+    response.assert_status_ok();
+    let item: serde_json::Value = response.json();
+    assert_eq!(item["category_id"], groceries);
+    assert_eq!(item["splits"].as_array().unwrap().len(), 3);
+    assert_eq!(item["splits"][1]["category_label"], "Household");
+
+    let summary: serde_json::Value = server
+        .get(&format!("/api/months/{month_id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(summary["total_spent"], 180.0);
+    assert_eq!(
+        spent_by_category(&summary),
+        vec![
+            ("Groceries".to_string(), 120.0),
+            ("Household".to_string(), 35.5),
+            ("Clothing".to_string(), 24.5)
+        ]
+    );
+
+    let stats: serde_json::Value = server
+        .get("/api/stats")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let household_stats = stats["category_comparisons"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["category_label"] == "Household")
+        .unwrap();
+    assert_eq!(household_stats["current_month_spent"], 35.5);
+}
+
+#[tokio::test]
+async fn test_splits_must_add_up_and_use_own_categories() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let groceries = create_test_category(&pool, user_id, "Groceries", 400.0).await;
+    let household = create_test_category(&pool, user_id, "Household", 100.0).await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs = create_test_category(&pool, other_id, "Theirs", 10.0).await;
+
+    for splits in [
+        json!([{ "category_id": groceries, "amount": 50.0 }, { "category_id": household, "amount": 40.0 }]),
+        json!([{ "category_id": groceries, "amount": 50.0 }, { "category_id": theirs, "amount": 50.0 }]),
+        json!([{ "category_id": groceries, "amount": 110.0 }, { "category_id": household, "amount": -10.0 }]),
+    ] {
+        let response = server
+            .post(&format!("/api/months/{month_id}/items"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "description": "Costco", "amount": 100.0, "spent_on": "2024-06-08", "splits": splits }))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_update_item_splits() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let groceries = create_test_category(&pool, user_id, "Groceries", 400.0).await;
+    let household = create_test_category(&pool, user_id, "Household", 100.0).await;
+    create_test_budget(&pool, month_id, groceries, 400.0).await;
+    create_test_budget(&pool, month_id, household, 100.0).await;
+
+    let item: serde_json::Value = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": groceries, "description": "Costco", "amount": 100.0, "spent_on": "2024-06-08" }))
+        .await
+        .json();
+    let url = format!("/api/months/{month_id}/items/{}", item["id"]);
+
+    let response = server
+        .put(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "splits": [{ "category_id": groceries, "amount": 70.0 }, { "category_id": household, "amount": 30.0 }] }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["splits"].as_array().unwrap().len(), 2);
+
+    let response = server
+        .put(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 120.0 }))
+        .await;
+    response.assert_status_bad_request();
+
+    let response = server
+        .put(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 120.0, "splits": [] }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["splits"], json!([]));
+
+    let summary: serde_json::Value = server
+        .get(&format!("/api/months/{month_id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(
+        spent_by_category(&summary),
+        vec![
+            ("Groceries".to_string(), 120.0),
+            ("Household".to_string(), 0.0)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_splits_survive_json_round_trip() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let groceries = create_test_category(&pool, user_id, "Groceries", 400.0).await;
+    let household = create_test_category(&pool, user_id, "Household", 100.0).await;
+    server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "description": "Costco", "amount": 100.0, "spent_on": "2024-06-08",
+            "splits": [{ "category_id": household, "amount": 60.0 }, { "category_id": groceries, "amount": 40.0 }]
+        }))
+        .await
+        .assert_status_ok();
+
+    let exported: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let item = &exported["months"][0]["items"][0];
+    assert_eq!(item["category_label"], "Household");
+    assert_eq!(item["splits"][1]["category_label"], "Groceries");
+
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+
+    let months: Vec<serde_json::Value> = server
+        .get("/api/months")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let items: Vec<serde_json::Value> = server
+        .get(&format!("/api/months/{}/items", months[0]["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let splits: Vec<(&str, f64)> = items[0]["splits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            (
+                s["category_label"].as_str().unwrap(),
+                s["amount"].as_f64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(splits, vec![("Household", 60.0), ("Groceries", 40.0)]);
+}
@@ -10,7 +10,7 @@ use serde_json::json;
 async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool.clone());
     let server = create_test_server(app);
     (server, pool, user_id, token)
@@ -15,7 +15,7 @@ async fn setup() -> axum_test::TestServer {
 async fn setup_with_user() -> (axum_test::TestServer, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool);
     let server = create_test_server(app);
     (server, user_id, token)
@@ -68,7 +68,7 @@ async fn test_auth_from_bearer() {
 async fn test_auth_expired_token() {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let expired_token = generate_expired_token(user_id, "testuser");
+    let expired_token = generate_expired_token(&pool, user_id, "testuser").await;
     let app = create_app(pool);
     let server = create_test_server(app);
 
@@ -1,15 +1,18 @@
 mod common;
 
+use chrono::{Datelike, Utc};
 use common::{
-    auth_name, auth_value, close_test_month, create_test_category, create_test_month,
-    create_test_pool, create_test_server, create_test_user, generate_token,
+    auth_name, auth_value, close_test_month, create_test_budget, create_test_category,
+    create_test_income, create_test_item, create_test_month, create_test_pool, create_test_server,
+    create_test_user, generate_token,
 };
 use payme::create_app;
+use serde_json::json;
 
 async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool.clone());
     let server = create_test_server(app);
     (server, pool, user_id, token)
@@ -74,6 +77,105 @@ async fn test_get_or_create_current_month_creates() {
     assert_eq!(budgets[0]["allocated_amount"], 500.0);
 }
 
+/// The calendar month before today's, as (year, month).
+fn previous_month() -> (i32, i32) {
+    let now = Utc::now();
+    match now.month() {
+        1 => (now.year() - 1, 12),
+        month => (now.year(), month as i32 - 1),
+    }
+}
+
+#[tokio::test]
+async fn test_current_month_rolls_over_budgets() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let none = create_test_category(&pool, user_id, "Fun", 100.0).await;
+    let surplus = create_test_category(&pool, user_id, "Food", 500.0).await;
+    let capped = create_test_category(&pool, user_id, "Clothes", 50.0).await;
+    let deficit = create_test_category(&pool, user_id, "Transport", 200.0).await;
+    for (id, mode) in [
+        (surplus, "carry_surplus"),
+        (capped, "carry_surplus"),
+        (deficit, "carry_surplus_and_deficit"),
+    ] {
+        server
+            .put(&format!("/api/categories/{id}"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "rollover_mode": mode }))
+            .await
+            .assert_status_ok();
+    }
+
+    let (year, month) = previous_month();
+    let spent_on = format!("{year}-{month:02}-10");
+    let previous = create_test_month(&pool, user_id, year, month).await;
+    for (id, allocated, spent) in [
+        (none, 100.0, 10.0),
+        (surplus, 500.0, 420.0),
+        (capped, 50.0, 80.0),
+        (deficit, 200.0, 300.0),
+    ] {
+        create_test_budget(&pool, previous, id, allocated).await;
+        create_test_item(&pool, previous, id, "Spending", spent, &spent_on).await;
+    }
+
+    let response = server
+        .get("/api/months/current")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    let budget = |id: i64| {
+        body["budgets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["category_id"] == id)
+            .map(|b| {
+                (
+                    b["allocated_amount"].as_f64().unwrap(),
+                    b["carried_amount"].as_f64().unwrap(),
+                )
+            })
+            .unwrap()
+    };
+    assert_eq!(budget(none), (100.0, 0.0));
+    assert_eq!(budget(surplus), (580.0, 80.0));
+    assert_eq!(budget(capped), (50.0, 0.0));
+    assert_eq!(budget(deficit), (100.0, -100.0));
+}
+
+#[tokio::test]
+async fn test_current_month_without_previous_month_carries_nothing() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/categories")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "label": "Food",
+            "default_amount": 300.0,
+            "rollover_mode": "carry_surplus_and_deficit"
+        }))
+        .await;
+    response.assert_status_ok();
+    let category: serde_json::Value = response.json();
+    assert_eq!(category["rollover_mode"], "carry_surplus_and_deficit");
+
+    let (year, _) = previous_month();
+    create_test_month(&pool, user_id, year - 1, 1).await;
+
+    let body: serde_json::Value = server
+        .get("/api/months/current")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(body["budgets"][0]["allocated_amount"], 300.0);
+    assert_eq!(body["budgets"][0]["carried_amount"], 0.0);
+}
+
 #[tokio::test]
 async fn test_get_or_create_current_month_returns_existing() {
     let (server, _pool, _user_id, token) = setup_with_user().await;
@@ -97,6 +199,109 @@ async fn test_get_or_create_current_month_returns_existing() {
     assert_eq!(month_id1, month_id2);
 }
 
+#[tokio::test]
+async fn test_create_month_seeds_budgets() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    create_test_category(&pool, user_id, "Food", 500.0).await;
+
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "year": 2019, "month": 3 }))
+        .await;
+
+    response.assert_status_ok();
+    let month: serde_json::Value = response.json();
+    assert_eq!(month["year"], 2019);
+    assert_eq!(month["month"], 3);
+    assert_eq!(month["is_closed"], false);
+
+    let body: serde_json::Value = server
+        .get(&format!("/api/months/{}", month["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(body["budgets"][0]["allocated_amount"], 500.0);
+}
+
+#[tokio::test]
+async fn test_create_month_conflict() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    create_test_month(&pool, user_id, 2030, 1).await;
+
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "year": 2030, "month": 1 }))
+        .await;
+
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_create_month_validation() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    for (year, month) in [(2024, 0), (2024, 13), (10000, 1)] {
+        let response = server
+            .post("/api/months")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "year": year, "month": month }))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_create_month_range() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    create_test_month(&pool, user_id, 2025, 1).await;
+
+    let response = server
+        .post("/api/months/bulk")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "from": { "year": 2024, "month": 11 },
+            "to": { "year": 2025, "month": 2 }
+        }))
+        .await;
+
+    response.assert_status_ok();
+    let created: Vec<serde_json::Value> = response.json();
+    let created: Vec<(i64, i64)> = created
+        .iter()
+        .map(|m| (m["year"].as_i64().unwrap(), m["month"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(created, vec![(2024, 11), (2024, 12), (2025, 2)]);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM months WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 4);
+}
+
+#[tokio::test]
+async fn test_create_month_range_rejects_bad_ranges() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+
+    for (from, to) in [((2025, 3), (2025, 1)), ((2000, 1), (2010, 1))] {
+        let response = server
+            .post("/api/months/bulk")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({
+                "from": { "year": from.0, "month": from.1 },
+                "to": { "year": to.0, "month": to.1 }
+            }))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
 #[tokio::test]
 async fn test_get_month_success() {
     let (server, pool, user_id, token) = setup_with_user().await;
@@ -136,7 +341,7 @@ async fn test_get_month_wrong_user() {
 
     let month_id = create_test_month(&pool, user1_id, 2024, 6).await;
 
-    let token2 = generate_token(user2_id, "user2");
+    let token2 = generate_token(&pool, user2_id, "user2").await;
     let app = create_app(pool);
     let server = create_test_server(app);
 
@@ -180,6 +385,38 @@ async fn test_close_month_already_closed() {
     response.assert_status_bad_request();
 }
 
+#[tokio::test]
+async fn test_concurrent_closes_take_one_snapshot() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+
+    let url = format!("/api/months/{}/close", month_id);
+    let close = || async {
+        server
+            .post(&url)
+            .add_header(auth_name(), auth_value(&token))
+            .await
+            .status_code()
+            .as_u16()
+    };
+    let statuses = tokio::join!(close(), close(), close());
+    assert_eq!(
+        [statuses.0, statuses.1, statuses.2]
+            .iter()
+            .filter(|s| **s == 200)
+            .count(),
+        1
+    );
+
+    let snapshots: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM monthly_snapshots WHERE month_id = ?")
+            .bind(month_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(snapshots, 1);
+}
+
 #[tokio::test]
 async fn test_get_month_pdf_success() {
     let (server, pool, user_id, token) = setup_with_user().await;
@@ -215,3 +452,132 @@ async fn test_get_month_pdf_not_closed() {
 
     response.assert_status_not_found();
 }
+
+#[tokio::test]
+async fn test_reopen_and_close_keeps_snapshot_versions() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+
+    server
+        .post(&format!("/api/months/{}/close", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .post(&format!("/api/months/{}/reopen", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["is_closed"], false);
+    assert!(body["closed_at"].is_null());
+
+    server
+        .post(&format!("/api/months/{}/items", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "category_id": cat_id,
+            "description": "Late refund correction",
+            "amount": 12.0,
+            "spent_on": "2024-06-28"
+        }))
+        .await
+        .assert_status_ok();
+
+    server
+        .post(&format!("/api/months/{}/close", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .get(&format!("/api/months/{}/snapshots", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let snapshots: Vec<serde_json::Value> = response.json();
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[0]["version"], 1);
+    assert_eq!(snapshots[1]["version"], 2);
+
+    let first = server
+        .get(&format!("/api/months/{}/snapshots/1", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    first.assert_status_ok();
+    let latest = server
+        .get(&format!("/api/months/{}/pdf", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    let second = server
+        .get(&format!("/api/months/{}/snapshots/2", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert_eq!(latest.as_bytes(), second.as_bytes());
+    assert_ne!(first.as_bytes(), second.as_bytes());
+
+    server
+        .get(&format!("/api/months/{}/snapshots/3", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_reopen_open_month_fails() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+
+    let response = server
+        .post(&format!("/api/months/{}/reopen", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_snapshots_of_other_users_month_not_found() {
+    let (server, pool, _user_id, token) = setup_with_user().await;
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let month_id = create_test_month(&pool, other_id, 2024, 6).await;
+
+    server
+        .get(&format!("/api/months/{}/snapshots", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status_not_found();
+
+    server
+        .post(&format!("/api/months/{}/reopen", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_month_summary_totals_are_exact() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let cat_id = create_test_category(&pool, user_id, "Food", 500.0).await;
+    for _ in 0..300 {
+        create_test_item(&pool, month_id, cat_id, "Gum", 0.1, "2024-06-15").await;
+    }
+    create_test_income(&pool, month_id, "Salary", 100.3).await;
+
+    let response = server
+        .get(&format!("/api/months/{}", month_id))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["total_spent"], 30.0);
+    assert_eq!(body["remaining"], 70.3);
+}
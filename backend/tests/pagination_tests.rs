@@ -0,0 +1,222 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_category, create_test_fixed_expense, create_test_income,
+    create_test_item, create_test_month, create_test_pool, create_test_server, create_test_user,
+    generate_token,
+};
+use payme::create_app;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn list(server: &axum_test::TestServer, token: &str, url: &str) -> Vec<serde_json::Value> {
+    let response = server
+        .get(url)
+        .add_header(auth_name(), auth_value(token))
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+fn labels(rows: &[serde_json::Value]) -> Vec<&str> {
+    rows.iter().map(|r| r["label"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_months_page_and_filter_by_date() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    for (year, month) in [(2023, 11), (2023, 12), (2024, 1), (2024, 2), (2024, 3)] {
+        create_test_month(&pool, user_id, year, month).await;
+    }
+
+    let response = server
+        .get("/api/months?limit=2")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header("X-Total-Count"), "5");
+    assert_eq!(
+        response.header("Link"),
+        "</api/months?limit=2&offset=2>; rel=\"next\""
+    );
+    let months: Vec<serde_json::Value> = response.json();
+    let dates: Vec<(i64, i64)> = months
+        .iter()
+        .map(|m| (m["year"].as_i64().unwrap(), m["month"].as_i64().unwrap()))
+        .collect();
+    assert_eq!(dates, vec![(2024, 3), (2024, 2)]);
+
+    let months = list(
+        &server,
+        &token,
+        "/api/months?direction=asc&limit=2&offset=2",
+    )
+    .await;
+    assert_eq!(months[0]["year"], 2024);
+    assert_eq!(months[0]["month"], 1);
+
+    let response = server
+        .get("/api/months?from=2023-12-31&to=2024-02-01")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert_eq!(response.header("X-Total-Count"), "3");
+    assert!(response.maybe_header("Link").is_none());
+    let months: Vec<serde_json::Value> = response.json();
+    let months: Vec<i64> = months
+        .iter()
+        .map(|m| m["month"].as_i64().unwrap())
+        .collect();
+    assert_eq!(months, vec![2, 1, 12]);
+}
+
+#[tokio::test]
+async fn test_configuration_lists_sort_and_filter_by_amount() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    for (label, amount) in [("Rent", 1200.0), ("gym", 40.0), ("Internet", 60.0)] {
+        create_test_fixed_expense(&pool, user_id, label, amount).await;
+        create_test_category(&pool, user_id, label, amount).await;
+    }
+
+    let expenses = list(&server, &token, "/api/fixed-expenses").await;
+    assert_eq!(labels(&expenses), vec!["Rent", "gym", "Internet"]);
+    let expenses = list(&server, &token, "/api/fixed-expenses?sort=label").await;
+    assert_eq!(labels(&expenses), vec!["gym", "Internet", "Rent"]);
+    let expenses = list(
+        &server,
+        &token,
+        "/api/fixed-expenses?sort=amount&direction=desc&max_amount=100",
+    )
+    .await;
+    assert_eq!(labels(&expenses), vec!["Internet", "gym"]);
+
+    let categories = list(
+        &server,
+        &token,
+        "/api/categories?sort=default_amount&min_amount=50",
+    )
+    .await;
+    assert_eq!(labels(&categories), vec!["Internet", "Rent"]);
+    let response = server
+        .get("/api/categories?sort=label&limit=1&offset=1")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert_eq!(response.header("X-Total-Count"), "3");
+    assert_eq!(
+        response.header("Link"),
+        "</api/categories?sort=label&limit=1&offset=2>; rel=\"next\""
+    );
+    let categories: Vec<serde_json::Value> = response.json();
+    assert_eq!(labels(&categories), vec!["Internet"]);
+}
+
+#[tokio::test]
+async fn test_income_and_items_paginate() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    create_test_income(&pool, month_id, "Salary", 3000.0).await;
+    create_test_income(&pool, month_id, "Bonus", 500.0).await;
+    create_test_income(&pool, month_id, "Gift", 50.0).await;
+
+    let income = list(&server, &token, &format!("/api/months/{month_id}/income")).await;
+    assert_eq!(labels(&income), vec!["Salary", "Bonus", "Gift"]);
+    let income = list(
+        &server,
+        &token,
+        &format!("/api/months/{month_id}/income?sort=amount&min_amount=100"),
+    )
+    .await;
+    assert_eq!(labels(&income), vec!["Bonus", "Salary"]);
+
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    for (description, amount, day) in [
+        ("Bakery", 5.0, "2024-05-01"),
+        ("Groceries", 80.0, "2024-05-02"),
+        ("Takeaway", 25.0, "2024-05-03"),
+    ] {
+        create_test_item(&pool, month_id, food, description, amount, day).await;
+    }
+    let url = format!("/api/months/{month_id}/items");
+    let response = server
+        .get(&format!("{url}?limit=2"))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert_eq!(response.header("X-Total-Count"), "3");
+    let items: Vec<serde_json::Value> = response.json();
+    let descriptions: Vec<&str> = items
+        .iter()
+        .map(|i| i["description"].as_str().unwrap())
+        .collect();
+    assert_eq!(descriptions, vec!["Takeaway", "Groceries"]);
+
+    let items = list(
+        &server,
+        &token,
+        &format!("{url}?sort=amount&direction=asc&from=2024-05-02"),
+    )
+    .await;
+    let descriptions: Vec<&str> = items
+        .iter()
+        .map(|i| i["description"].as_str().unwrap())
+        .collect();
+    assert_eq!(descriptions, vec!["Takeaway", "Groceries"]);
+}
+
+#[tokio::test]
+async fn test_month_lists_default_to_one_page() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    for index in 0..55 {
+        create_test_month(&pool, user_id, 2020 + index / 12, index % 12 + 1).await;
+    }
+    let month_id = create_test_month(&pool, user_id, 2030, 1).await;
+    for n in 0..55 {
+        create_test_income(&pool, month_id, &format!("Payment {n}"), 10.0).await;
+    }
+
+    for url in [
+        "/api/months".to_string(),
+        format!("/api/months/{month_id}/income"),
+    ] {
+        let response = server
+            .get(&url)
+            .add_header(auth_name(), auth_value(&token))
+            .await;
+        assert_eq!(
+            response.header("X-Total-Count"),
+            if url == "/api/months" { "56" } else { "55" }
+        );
+        assert_eq!(response.json::<Vec<serde_json::Value>>().len(), 50);
+        assert_eq!(
+            response.header("Link"),
+            format!("<{url}?limit=50&offset=50>; rel=\"next\"").as_str()
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_list_parameters() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+
+    for url in [
+        "/api/months?sort=label".to_string(),
+        "/api/months?direction=sideways".to_string(),
+        "/api/categories?limit=0".to_string(),
+        "/api/fixed-expenses?offset=-5".to_string(),
+        "/api/fixed-expenses?min_amount=lots".to_string(),
+        format!("/api/months/{month_id}/income?sort=date"),
+        format!("/api/months/{month_id}/items?limit=1000"),
+    ] {
+        let response = server
+            .get(&url)
+            .add_header(auth_name(), auth_value(&token))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
@@ -0,0 +1,339 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_pool,
+    create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_recurring(
+    server: &axum_test::TestServer,
+    token: &str,
+    recurring: serde_json::Value,
+) -> i64 {
+    let response = server
+        .post("/api/recurring")
+        .add_header(auth_name(), auth_value(token))
+        .json(&recurring)
+        .await;
+    response.assert_status_ok();
+    let created: serde_json::Value = response.json();
+    created["id"].as_i64().unwrap()
+}
+
+async fn create_month(server: &axum_test::TestServer, token: &str, year: i32, month: i32) -> i64 {
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(token))
+        .json(&json!({ "year": year, "month": month }))
+        .await;
+    response.assert_status_ok();
+    let created: serde_json::Value = response.json();
+    created["id"].as_i64().unwrap()
+}
+
+async fn month_items(pool: &sqlx::SqlitePool, month_id: i64) -> Vec<(String, i64, String)> {
+    sqlx::query_as(
+        "SELECT description, amount, spent_on FROM items WHERE month_id = ? ORDER BY spent_on, id",
+    )
+    .bind(month_id)
+    .fetch_all(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_create_and_list_recurring() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+
+    create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Gym", "amount": 25.0, "frequency": "monthly", "start_date": "2024-01-15" }),
+    )
+    .await;
+
+    let response = server
+        .get("/api/recurring")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let recurring: Vec<serde_json::Value> = response.json();
+    assert_eq!(recurring.len(), 1);
+    assert_eq!(recurring[0]["frequency"], "monthly");
+    assert_eq!(recurring[0]["interval"], 1);
+    assert_eq!(recurring[0]["day_of_month"], serde_json::Value::Null);
+}
+
+#[tokio::test]
+async fn test_create_recurring_rejects_invalid_input() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs = create_test_category(&pool, other_id, "Theirs", 10.0).await;
+
+    let base = |overrides: serde_json::Value| {
+        let mut recurring = json!({
+            "category_id": bills, "description": "Gym", "amount": 25.0,
+            "frequency": "monthly", "start_date": "2024-01-15"
+        });
+        for (key, value) in overrides.as_object().unwrap() {
+            recurring[key] = value.clone();
+        }
+        recurring
+    };
+    for recurring in [
+        base(json!({ "interval": 0 })),
+        base(json!({ "day_of_month": 32 })),
+        base(json!({ "frequency": "weekly", "day_of_month": 3 })),
+        base(json!({ "end_date": "2023-12-31" })),
+        base(json!({ "category_id": theirs })),
+    ] {
+        let response = server
+            .post("/api/recurring")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&recurring)
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_new_months_get_recurring_items() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+
+    create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Cleaner", "amount": 40.0, "frequency": "weekly", "interval": 2, "start_date": "2024-01-03", "end_date": "2024-02-20" }),
+    )
+    .await;
+    create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Insurance", "amount": 120.0, "frequency": "monthly", "interval": 3, "start_date": "2024-01-10", "day_of_month": 31 }),
+    )
+    .await;
+
+    let february = create_month(&server, &token, 2024, 2).await;
+    assert_eq!(
+        month_items(&pool, february).await,
+        vec![("Cleaner".to_string(), 4000, "2024-02-14".to_string())]
+    );
+
+    let april = create_month(&server, &token, 2024, 4).await;
+    assert_eq!(
+        month_items(&pool, april).await,
+        vec![("Insurance".to_string(), 12000, "2024-04-30".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn test_skip_and_edit_occurrences() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+    let id = create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Phone", "amount": 30.0, "frequency": "monthly", "start_date": "2024-01-05" }),
+    )
+    .await;
+
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-03-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "skipped": true }))
+        .await
+        .assert_status_ok();
+    let march = create_month(&server, &token, 2024, 3).await;
+    assert!(month_items(&pool, march).await.is_empty());
+
+    let april = create_month(&server, &token, 2024, 4).await;
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 45.5, "description": "Phone + roaming" }))
+        .await
+        .assert_status_ok();
+    assert_eq!(
+        month_items(&pool, april).await,
+        vec![(
+            "Phone + roaming".to_string(),
+            4550,
+            "2024-04-05".to_string()
+        )]
+    );
+
+    server
+        .delete(&format!("/api/recurring/{id}/occurrences/2024-03-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(
+        month_items(&pool, march).await,
+        vec![("Phone".to_string(), 3000, "2024-03-05".to_string())]
+    );
+
+    let response = server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-06"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "skipped": true }))
+        .await;
+    response.assert_status_bad_request();
+
+    close_test_month(&pool, april).await;
+    let response = server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "skipped": true }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_editing_an_occurrence_keeps_its_item() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+    let id = create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Phone", "amount": 30.0, "frequency": "monthly", "start_date": "2024-01-05" }),
+    )
+    .await;
+    let april = create_month(&server, &token, 2024, 4).await;
+    let item_id: i64 = sqlx::query_scalar("SELECT id FROM items WHERE month_id = ?")
+        .bind(april)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let tag_id: i64 =
+        sqlx::query_scalar("INSERT INTO tags (user_id, name) VALUES (?, 'work') RETURNING id")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    for sql in [
+        "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+        "INSERT INTO item_splits (item_id, category_id, amount) VALUES (?1, ?3, 3000)",
+        "INSERT INTO attachments (item_id, filename, content_type, size, data) VALUES (?1, 'bill.pdf', 'application/pdf', 5, x'255044462d')",
+    ] {
+        sqlx::query(sql)
+            .bind(item_id)
+            .bind(tag_id)
+            .bind(bills)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+    let count = |table: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table} WHERE item_id = ?"))
+                .bind(item_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "description": "Phone bill" }))
+        .await
+        .assert_status_ok();
+    let kept: i64 = sqlx::query_scalar("SELECT id FROM items WHERE description = 'Phone bill'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(kept, item_id);
+    assert_eq!(count("item_splits").await, 1);
+
+    // A new amount leaves the splits adding up to the old one, so they go.
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "amount": 35.0 }))
+        .await
+        .assert_status_ok();
+    assert_eq!(
+        month_items(&pool, april).await,
+        vec![("Phone".to_string(), 3500, "2024-04-05".to_string())]
+    );
+    assert_eq!(count("item_splits").await, 0);
+    assert_eq!(count("item_tags").await, 1);
+    assert_eq!(count("attachments").await, 1);
+
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/2024-04-05"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "skipped": true }))
+        .await
+        .assert_status_ok();
+    assert!(month_items(&pool, april).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_upcoming_lists_due_occurrences() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let bills = create_test_category(&pool, user_id, "Bills", 500.0).await;
+    let today = Utc::now().date_naive();
+    let id = create_recurring(
+        &server,
+        &token,
+        json!({ "category_id": bills, "description": "Allowance", "amount": 10.0, "frequency": "weekly", "start_date": today }),
+    )
+    .await;
+
+    let upcoming = |days: i64| {
+        let request = server
+            .get(&format!("/api/recurring/upcoming?days={days}"))
+            .add_header(auth_name(), auth_value(&token));
+        async move {
+            let response = request.await;
+            response.assert_status_ok();
+            let due: Vec<serde_json::Value> = response.json();
+            due.iter()
+                .map(|d| d["due_on"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    let day = |n: i64| (today + Duration::days(n)).to_string();
+    assert_eq!(upcoming(14).await, vec![day(0), day(7), day(14)]);
+
+    server
+        .put(&format!("/api/recurring/{id}/occurrences/{}", day(7)))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "skipped": true }))
+        .await
+        .assert_status_ok();
+    assert_eq!(upcoming(14).await, vec![day(0), day(14)]);
+
+    let response = server
+        .get("/api/recurring/upcoming")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let due: Vec<serde_json::Value> = response.json();
+    assert_eq!(due.len(), 4);
+    assert_eq!(due[0]["description"], "Allowance");
+
+    let response = server
+        .get("/api/recurring/upcoming?days=0")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_bad_request();
+}
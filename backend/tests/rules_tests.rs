@@ -0,0 +1,238 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_item,
+    create_test_month, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_rule(
+    server: &axum_test::TestServer,
+    token: &str,
+    rule: serde_json::Value,
+) -> serde_json::Value {
+    let response = server
+        .post("/api/rules")
+        .add_header(auth_name(), auth_value(token))
+        .json(&rule)
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+#[tokio::test]
+async fn test_create_and_list_rules_in_priority_order() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let fun = create_test_category(&pool, user_id, "Fun", 100.0).await;
+
+    create_rule(
+        &server,
+        &token,
+        json!({ "category_id": fun, "priority": 5, "description_regex": "(?i)cinema" }),
+    )
+    .await;
+    let rule = create_rule(
+        &server,
+        &token,
+        json!({ "category_id": food, "priority": 1, "description_contains": "market", "max_amount": 200.0 }),
+    )
+    .await;
+    assert_eq!(rule["max_amount"], 200.0);
+
+    let response = server
+        .get("/api/rules")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let rules: Vec<serde_json::Value> = response.json();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["category_id"], food);
+    assert_eq!(rules[1]["category_id"], fun);
+}
+
+#[tokio::test]
+async fn test_create_rule_rejects_invalid_input() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs = create_test_category(&pool, other_id, "Theirs", 10.0).await;
+
+    for rule in [
+        json!({ "category_id": food }),
+        json!({ "category_id": food, "description_regex": "(" }),
+        json!({ "category_id": food, "min_amount": 50.0, "max_amount": 10.0 }),
+        json!({ "category_id": theirs, "description_contains": "x" }),
+    ] {
+        let response = server
+            .post("/api/rules")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&rule)
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_create_item_without_category_uses_rules() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    create_rule(
+        &server,
+        &token,
+        json!({ "category_id": food, "description_contains": "grocer" }),
+    )
+    .await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "description": "Corner Grocer", "amount": 12.5, "spent_on": "2024-06-10" }))
+        .await;
+    response.assert_status_ok();
+    let item: serde_json::Value = response.json();
+    assert_eq!(item["category_id"], food);
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "description": "Hardware", "amount": 3.0, "spent_on": "2024-06-10" }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_update_and_delete_rule() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let rule = create_rule(
+        &server,
+        &token,
+        json!({ "category_id": food, "description_contains": "a" }),
+    )
+    .await;
+    let id = rule["id"].as_i64().unwrap();
+
+    let response = server
+        .put(&format!("/api/rules/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "start_date": "2024-01-01", "end_date": "2024-12-31" }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(updated["description_contains"], serde_json::Value::Null);
+    assert_eq!(updated["end_date"], "2024-12-31");
+
+    server
+        .delete(&format!("/api/rules/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+
+    let response = server
+        .put(&format!("/api/rules/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": food, "description_contains": "a" }))
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_apply_rules_to_month() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let misc = create_test_category(&pool, user_id, "Misc", 100.0).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let bread = create_test_item(&pool, month_id, misc, "Bakery", 4.0, "2024-06-01").await;
+    let tools = create_test_item(&pool, month_id, misc, "Tools", 40.0, "2024-06-02").await;
+    create_rule(
+        &server,
+        &token,
+        json!({ "category_id": food, "description_regex": "^Bak" }),
+    )
+    .await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/rules/apply"))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["updated"], 1);
+
+    let category = |id: i64| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT category_id FROM items WHERE id = ?")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    assert_eq!(category(bread).await, food);
+    assert_eq!(category(tools).await, misc);
+}
+
+#[tokio::test]
+async fn test_apply_rules_to_closed_month_fails() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    close_test_month(&pool, month_id).await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/rules/apply"))
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_rules_categorize_imports() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let misc = create_test_category(&pool, user_id, "Misc", 100.0).await;
+    let rent = create_test_category(&pool, user_id, "Rent", 1000.0).await;
+    create_rule(
+        &server,
+        &token,
+        json!({ "category_id": rent, "min_amount": 900.0 }),
+    )
+    .await;
+
+    let response = server
+        .post("/api/import/csv")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "csv": "Date,Description,Amount\n2024-06-01,Landlord,-950.00\n2024-06-02,Kiosk,-2.00\n",
+            "mapping": {
+                "date_column": 0,
+                "description_column": 1,
+                "amount_column": 2,
+                "sign_convention": "expenses_negative"
+            },
+            "category_id": misc
+        }))
+        .await;
+    response.assert_status_ok();
+
+    let items: Vec<(String, i64)> =
+        sqlx::query_as("SELECT description, category_id FROM items ORDER BY spent_on")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        items,
+        vec![("Landlord".to_string(), rent), ("Kiosk".to_string(), misc)]
+    );
+}
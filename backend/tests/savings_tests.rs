@@ -1,5 +1,6 @@
 mod common;
 
+use chrono::{Datelike, Months, NaiveDate, Utc};
 use common::{
     auth_name, auth_value, create_test_pool, create_test_server, create_test_user, generate_token,
 };
@@ -9,7 +10,7 @@ use serde_json::json;
 async fn setup_with_user() -> (axum_test::TestServer, i64, String) {
     let pool = create_test_pool().await;
     let user_id = create_test_user(&pool, "testuser", "password123").await;
-    let token = generate_token(user_id, "testuser");
+    let token = generate_token(&pool, user_id, "testuser").await;
     let app = create_app(pool);
     let server = create_test_server(app);
     (server, user_id, token)
@@ -109,3 +110,214 @@ async fn test_savings_validation() {
 
     response.assert_status_bad_request();
 }
+
+fn month_start(months_ago: u32) -> NaiveDate {
+    Utc::now().date_naive().with_day(1).unwrap() - Months::new(months_ago)
+}
+
+#[tokio::test]
+async fn test_ledger_entries_change_savings() {
+    let (server, _user_id, token) = setup_with_user().await;
+
+    let response = server
+        .post("/api/savings/entries")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "kind": "deposit", "amount": 500.0, "entry_date": "2024-03-01", "note": "Bonus" }))
+        .await;
+    response.assert_status_ok();
+    let deposit: serde_json::Value = response.json();
+    assert_eq!(deposit["amount"], 500.0);
+    server
+        .post("/api/savings/entries")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "kind": "withdrawal", "amount": 120.0, "entry_date": "2024-03-10" }))
+        .await
+        .assert_status_ok();
+    server
+        .put("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "savings": 400.0 }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .get("/api/savings/entries")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let entries: Vec<serde_json::Value> = response.json();
+    let summary: Vec<(&str, f64)> = entries
+        .iter()
+        .map(|e| (e["kind"].as_str().unwrap(), e["amount"].as_f64().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("correction", 20.0),
+            ("withdrawal", -120.0),
+            ("deposit", 500.0)
+        ]
+    );
+
+    server
+        .delete(&format!("/api/savings/entries/{}", deposit["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    let body: serde_json::Value = server
+        .get("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(body["savings"], -100.0);
+}
+
+#[tokio::test]
+async fn test_create_ledger_entry_validation() {
+    let (server, _user_id, token) = setup_with_user().await;
+    let response = server
+        .post("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "Checking", "kind": "checking" }))
+        .await;
+    let checking: serde_json::Value = response.json();
+
+    for entry in [
+        json!({ "kind": "deposit", "amount": -5.0 }),
+        json!({ "kind": "withdrawal", "amount": 0.0 }),
+        json!({ "kind": "deposit", "amount": 5.0, "account_id": checking["id"] }),
+    ] {
+        let response = server
+            .post("/api/savings/entries")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&entry)
+            .await;
+        response.assert_status_bad_request();
+    }
+
+    let response = server
+        .post("/api/savings/entries")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "kind": "correction", "amount": -5.0 }))
+        .await;
+    response.assert_status_ok();
+    let entry: serde_json::Value = response.json();
+    assert_eq!(entry["entry_date"], Utc::now().date_naive().to_string());
+}
+
+#[tokio::test]
+async fn test_savings_history_and_projection() {
+    let (server, _user_id, token) = setup_with_user().await;
+    let account = |body: serde_json::Value| {
+        let request = server
+            .post("/api/accounts")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&body);
+        async move {
+            let created: serde_json::Value = request.await.json();
+            created["id"].as_i64().unwrap()
+        }
+    };
+    let checking = account(json!({ "name": "Checking", "kind": "checking" })).await;
+    account(json!({ "name": "Savings", "kind": "savings", "opening_balance": 1000.0 })).await;
+    let entry = |kind: &str, amount: f64, date: NaiveDate| {
+        server
+            .post("/api/savings/entries")
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "kind": kind, "amount": amount, "entry_date": date }))
+    };
+
+    entry("deposit", 300.0, month_start(3))
+        .await
+        .assert_status_ok();
+    entry("deposit", 200.0, month_start(2))
+        .await
+        .assert_status_ok();
+    entry("withdrawal", 50.0, month_start(2))
+        .await
+        .assert_status_ok();
+    let last_month = month_start(1);
+    let response = server
+        .post("/api/months")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "year": last_month.year(), "month": last_month.month() }))
+        .await;
+    let month: serde_json::Value = response.json();
+    let savings_id = server
+        .get("/api/accounts")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json::<Vec<serde_json::Value>>()[1]["id"]
+        .clone();
+    server
+        .post(&format!("/api/months/{}/transfers", month["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": checking, "to_account_id": savings_id, "amount": 150.0, "transferred_on": last_month }))
+        .await
+        .assert_status_ok();
+    server
+        .put("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "savings": 1700.0 }))
+        .await
+        .assert_status_ok();
+    server
+        .put("/api/savings/goal")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "savings_goal": 2000.0 }))
+        .await
+        .assert_status_ok();
+
+    let response = server
+        .get("/api/savings/history")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let history: serde_json::Value = response.json();
+    assert_eq!(history["opening_balance"], 1000.0);
+    let balances: Vec<f64> = history["balances"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["balance"].as_f64().unwrap())
+        .collect();
+    assert_eq!(balances, vec![1300.0, 1450.0, 1600.0, 1700.0]);
+    let contributions: Vec<f64> = history["contributions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["amount"].as_f64().unwrap())
+        .collect();
+    assert_eq!(contributions, vec![300.0, 150.0, 150.0]);
+
+    let projection = &history["projection"];
+    assert_eq!(projection["monthly_contribution"], 200.0);
+    assert_eq!(projection["months_to_goal"], 2);
+    assert_eq!(
+        projection["projected_date"],
+        (Utc::now().date_naive() + Months::new(2)).to_string()
+    );
+}
+
+#[tokio::test]
+async fn test_savings_projection_without_growth() {
+    let (server, _user_id, token) = setup_with_user().await;
+    server
+        .put("/api/savings/goal")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "savings_goal": 2000.0 }))
+        .await
+        .assert_status_ok();
+
+    let history: serde_json::Value = server
+        .get("/api/savings/history")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(history["balances"], json!([]));
+    assert_eq!(history["projection"]["monthly_contribution"], 0.0);
+    assert_eq!(
+        history["projection"]["months_to_goal"],
+        serde_json::Value::Null
+    );
+}
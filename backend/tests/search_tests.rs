@@ -0,0 +1,227 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_category, create_test_item, create_test_month,
+    create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn search(
+    server: &axum_test::TestServer,
+    token: &str,
+    query: &str,
+) -> Vec<serde_json::Value> {
+    let response = server
+        .get(&format!("/api/items/search?{query}"))
+        .add_header(auth_name(), auth_value(token))
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+fn descriptions(results: &[serde_json::Value]) -> Vec<&str> {
+    results
+        .iter()
+        .map(|r| r["description"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_text_search_across_months() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let march = create_test_month(&pool, user_id, 2024, 3).await;
+    let april = create_test_month(&pool, user_id, 2024, 4).await;
+    let shopping = create_test_category(&pool, user_id, "Shopping", 300.0).await;
+    create_test_item(
+        &pool,
+        march,
+        shopping,
+        "Amazon Marketplace",
+        42.0,
+        "2024-03-12",
+    )
+    .await;
+    create_test_item(
+        &pool,
+        april,
+        shopping,
+        "AMAZON.com order",
+        18.0,
+        "2024-04-02",
+    )
+    .await;
+    let bakery = create_test_item(&pool, april, shopping, "Corner bakery", 6.5, "2024-04-03").await;
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let other_month = create_test_month(&pool, other_id, 2024, 4).await;
+    let theirs = create_test_category(&pool, other_id, "Theirs", 100.0).await;
+    create_test_item(
+        &pool,
+        other_month,
+        theirs,
+        "Amazon gift",
+        25.0,
+        "2024-04-05",
+    )
+    .await;
+
+    let results = search(&server, &token, "q=amaz").await;
+    assert_eq!(
+        descriptions(&results),
+        vec!["AMAZON.com order", "Amazon Marketplace"]
+    );
+    assert_eq!(results[1]["year"], 2024);
+    assert_eq!(results[1]["month"], 3);
+    assert_eq!(results[1]["category_label"], "Shopping");
+
+    server
+        .put(&format!("/api/months/{april}/items/{bakery}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "description": "Amazon Fresh" }))
+        .await
+        .assert_status_ok();
+    let results = search(&server, &token, "q=amazon+fresh").await;
+    assert_eq!(descriptions(&results), vec!["Amazon Fresh"]);
+
+    server
+        .delete(&format!("/api/months/{april}/items/{bakery}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert!(search(&server, &token, "q=fresh").await.is_empty());
+    assert!(search(&server, &token, "q=bakery").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_filters() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let household = create_test_category(&pool, user_id, "Household", 100.0).await;
+    create_test_item(&pool, month_id, food, "Groceries", 80.0, "2024-05-03").await;
+    create_test_item(
+        &pool,
+        month_id,
+        household,
+        "Light bulbs",
+        12.0,
+        "2024-05-10",
+    )
+    .await;
+    let tag: serde_json::Value = server
+        .post("/api/tags")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "party" }))
+        .await
+        .json();
+    server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({
+            "description": "Costco", "amount": 150.0, "spent_on": "2024-05-20", "tag_ids": [tag["id"]],
+            "splits": [{ "category_id": food, "amount": 100.0 }, { "category_id": household, "amount": 50.0 }]
+        }))
+        .await
+        .assert_status_ok();
+
+    let results = search(&server, &token, &format!("category_id={household}")).await;
+    assert_eq!(descriptions(&results), vec!["Costco", "Light bulbs"]);
+    let results = search(&server, &token, "min_amount=50&max_amount=100").await;
+    assert_eq!(descriptions(&results), vec!["Groceries"]);
+    let results = search(&server, &token, "from=2024-05-05&to=2024-05-19").await;
+    assert_eq!(descriptions(&results), vec!["Light bulbs"]);
+    let results = search(&server, &token, &format!("tag={}", tag["id"])).await;
+    assert_eq!(descriptions(&results), vec!["Costco"]);
+    assert_eq!(results[0]["tags"][0]["name"], "party");
+    assert_eq!(results[0]["splits"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_search_sorts_and_paginates() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    for (description, amount, day) in [
+        ("Cafe", 4.5, "2024-05-01"),
+        ("Bistro", 30.0, "2024-05-02"),
+        ("Diner", 12.0, "2024-05-03"),
+        ("Alehouse", 22.0, "2024-05-04"),
+    ] {
+        create_test_item(&pool, month_id, food, description, amount, day).await;
+    }
+
+    let results = search(&server, &token, "sort=amount&direction=asc").await;
+    assert_eq!(
+        descriptions(&results),
+        vec!["Cafe", "Diner", "Alehouse", "Bistro"]
+    );
+
+    let response = server
+        .get("/api/items/search?sort=description&direction=asc&limit=3")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(response.header("X-Total-Count"), "4");
+    assert_eq!(
+        response.header("Link"),
+        "</api/items/search?sort=description&direction=asc&limit=3&offset=3>; rel=\"next\""
+    );
+    let results: Vec<serde_json::Value> = response.json();
+    assert_eq!(descriptions(&results), vec!["Alehouse", "Bistro", "Cafe"]);
+
+    let response = server
+        .get("/api/items/search?sort=description&direction=asc&limit=3&offset=3")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    assert!(response.maybe_header("Link").is_none());
+    let results: Vec<serde_json::Value> = response.json();
+    assert_eq!(descriptions(&results), vec!["Diner"]);
+
+    for query in [
+        "limit=0",
+        "limit=501",
+        "offset=-1",
+        "sort=price",
+        "q=%22%3A-",
+    ] {
+        let response = server
+            .get(&format!("/api/items/search?{query}"))
+            .add_header(auth_name(), auth_value(&token))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_search_treats_query_syntax_literally() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 5).await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    create_test_item(&pool, month_id, food, "Fish AND chips", 9.0, "2024-05-01").await;
+    create_test_item(
+        &pool,
+        month_id,
+        food,
+        "Rock \"n\" roll cafe",
+        7.0,
+        "2024-05-02",
+    )
+    .await;
+
+    let results = search(&server, &token, "q=chips+OR+NEAR(").await;
+    assert!(results.is_empty());
+    let results = search(&server, &token, "q=AND+chips").await;
+    assert_eq!(descriptions(&results), vec!["Fish AND chips"]);
+    let results = search(&server, &token, "q=%22n%22+roll").await;
+    assert_eq!(descriptions(&results), vec!["Rock \"n\" roll cafe"]);
+}
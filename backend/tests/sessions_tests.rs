@@ -0,0 +1,271 @@
+mod common;
+
+use std::net::SocketAddr;
+
+use axum::extract::connect_info::MockConnectInfo;
+use axum_test::TestServerConfig;
+use chrono::{Duration, Utc};
+use common::{
+    auth_name, auth_value, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use payme::create_app;
+use serde_json::json;
+
+/// The reverse proxy every test server sits behind.
+const PROXY: [u8; 4] = [10, 0, 0, 1];
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64) {
+    setup_from(PROXY).await
+}
+
+/// A server whose requests all arrive from `peer`.
+async fn setup_from(peer: [u8; 4]) -> (axum_test::TestServer, sqlx::SqlitePool, i64) {
+    std::env::set_var("TRUSTED_PROXIES", "10.0.0.1");
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let app = create_app(pool.clone()).layer(MockConnectInfo(SocketAddr::from((peer, 4000))));
+    let server = create_test_server(app);
+    (server, pool, user_id)
+}
+
+async fn login(server: &axum_test::TestServer, user_agent: &str) -> serde_json::Value {
+    let response = server
+        .post("/api/auth/login")
+        .add_header("User-Agent", user_agent)
+        .add_header("X-Forwarded-For", "203.0.113.7, 10.0.0.1")
+        .json(&json!({ "username": "testuser", "password": "password123", "include_refresh_token": true }))
+        .await;
+    response.assert_status_ok();
+    response.json()
+}
+
+async fn refresh(server: &axum_test::TestServer, refresh_token: &serde_json::Value) -> u16 {
+    server
+        .post("/api/auth/refresh")
+        .json(&json!({ "refresh_token": refresh_token }))
+        .await
+        .status_code()
+        .as_u16()
+}
+
+async fn me(server: &axum_test::TestServer, access_token: &serde_json::Value) -> u16 {
+    server
+        .get("/api/auth/me")
+        .add_header(auth_name(), auth_value(access_token.as_str().unwrap()))
+        .await
+        .status_code()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn test_refresh_rotates_tokens() {
+    let (server, pool, _user_id) = setup_with_user().await;
+    let tokens = login(&server, "Firefox").await;
+    assert_eq!(tokens["expires_in"], 900);
+    assert_eq!(me(&server, &tokens["access_token"]).await, 200);
+
+    let response = server
+        .post("/api/auth/refresh")
+        .json(&json!({ "refresh_token": tokens["refresh_token"] }))
+        .await;
+    response.assert_status_ok();
+    let rotated: serde_json::Value = response.json();
+    assert_ne!(rotated["refresh_token"], tokens["refresh_token"]);
+    assert_eq!(me(&server, &rotated["access_token"]).await, 200);
+
+    // A second use right away is treated as a race and only refused.
+    assert_eq!(refresh(&server, &tokens["refresh_token"]).await, 401);
+    assert_eq!(me(&server, &rotated["access_token"]).await, 200);
+
+    // Later reuse means the old token leaked, so the session is revoked.
+    sqlx::query("UPDATE sessions SET rotated_at = datetime('now', '-1 hour')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(refresh(&server, &tokens["refresh_token"]).await, 401);
+    assert_eq!(me(&server, &rotated["access_token"]).await, 401);
+    assert_eq!(refresh(&server, &rotated["refresh_token"]).await, 401);
+
+    assert_eq!(refresh(&server, &json!("not-a-token")).await, 401);
+}
+
+#[tokio::test]
+async fn test_refresh_from_cookie() {
+    let pool = create_test_pool().await;
+    create_test_user(&pool, "testuser", "password123").await;
+    let mut config = TestServerConfig::new();
+    config.save_cookies = true;
+    let server = config.build(create_app(pool)).unwrap();
+
+    // Browsers do not opt in, so script never sees the refresh token.
+    let response = server
+        .post("/api/auth/login")
+        .json(&json!({ "username": "testuser", "password": "password123" }))
+        .await;
+    response.assert_status_ok();
+    let tokens: serde_json::Value = response.json();
+    assert!(tokens.get("refresh_token").is_none());
+    let cookie = response.cookie("refresh_token");
+    assert_eq!(cookie.http_only(), Some(true));
+    assert_eq!(cookie.path(), Some("/api/auth"));
+
+    let response = server.post("/api/auth/refresh").await;
+    response.assert_status_ok();
+    let rotated: serde_json::Value = response.json();
+    assert!(rotated.get("refresh_token").is_none());
+    assert_ne!(response.cookie("refresh_token").value(), cookie.value());
+    server.get("/api/auth/me").await.assert_status_ok();
+
+    server.post("/api/auth/logout").await.assert_status_ok();
+    assert_eq!(me(&server, &rotated["access_token"]).await, 401);
+    assert_eq!(refresh(&server, &json!(cookie.value())).await, 401);
+}
+
+#[tokio::test]
+async fn test_list_and_revoke_sessions() {
+    let (server, pool, _user_id) = setup_with_user().await;
+    let laptop = login(&server, "Firefox on Linux").await;
+    let phone = login(&server, "Safari on iOS").await;
+
+    let response = server
+        .get("/api/auth/sessions")
+        .add_header(
+            auth_name(),
+            auth_value(laptop["access_token"].as_str().unwrap()),
+        )
+        .await;
+    response.assert_status_ok();
+    let sessions: Vec<serde_json::Value> = response.json();
+    assert_eq!(sessions.len(), 2);
+    let current: Vec<&serde_json::Value> =
+        sessions.iter().filter(|s| s["current"] == true).collect();
+    assert_eq!(current.len(), 1);
+    assert_eq!(current[0]["user_agent"], "Firefox on Linux");
+    assert_eq!(current[0]["ip"], "203.0.113.7");
+    let phone_id = sessions
+        .iter()
+        .find(|s| s["user_agent"] == "Safari on iOS")
+        .unwrap()["id"]
+        .clone();
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    server
+        .delete(&format!("/api/auth/sessions/{phone_id}"))
+        .add_header(
+            auth_name(),
+            auth_value(&generate_token(&pool, other_id, "otheruser").await),
+        )
+        .await
+        .assert_status_not_found();
+
+    let url = format!("/api/auth/sessions/{phone_id}");
+    server
+        .delete(&url)
+        .add_header(
+            auth_name(),
+            auth_value(laptop["access_token"].as_str().unwrap()),
+        )
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(me(&server, &phone["access_token"]).await, 401);
+    assert_eq!(refresh(&server, &phone["refresh_token"]).await, 401);
+    assert_eq!(me(&server, &laptop["access_token"]).await, 200);
+    server
+        .delete(&url)
+        .add_header(
+            auth_name(),
+            auth_value(laptop["access_token"].as_str().unwrap()),
+        )
+        .await
+        .assert_status_not_found();
+
+    server
+        .delete("/api/auth/sessions")
+        .add_header(
+            auth_name(),
+            auth_value(laptop["access_token"].as_str().unwrap()),
+        )
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(me(&server, &laptop["access_token"]).await, 401);
+}
+
+#[tokio::test]
+async fn test_password_change_revokes_sessions() {
+    let (server, _pool, _user_id) = setup_with_user().await;
+    let laptop = login(&server, "Firefox").await;
+    let phone = login(&server, "Safari").await;
+
+    let response = server
+        .put("/api/auth/change-password")
+        .add_header(
+            auth_name(),
+            auth_value(laptop["access_token"].as_str().unwrap()),
+        )
+        .json(&json!({ "current_password": "password123", "new_password": "newpassword456" }))
+        .await;
+    response.assert_status_ok();
+    let fresh = response.cookie("token");
+
+    assert_eq!(me(&server, &laptop["access_token"]).await, 401);
+    assert_eq!(me(&server, &phone["access_token"]).await, 401);
+    assert_eq!(refresh(&server, &phone["refresh_token"]).await, 401);
+    assert_eq!(me(&server, &json!(fresh.value())).await, 200);
+}
+
+#[tokio::test]
+async fn test_forwarded_ip_only_trusted_from_proxies() {
+    let (server, _pool, _user_id) = setup_from([198, 51, 100, 9]).await;
+    let tokens = login(&server, "Firefox").await;
+    let sessions: Vec<serde_json::Value> = server
+        .get("/api/auth/sessions")
+        .add_header(
+            auth_name(),
+            auth_value(tokens["access_token"].as_str().unwrap()),
+        )
+        .add_header("X-Real-IP", "203.0.113.8")
+        .await
+        .json();
+    assert_eq!(sessions[0]["ip"], "198.51.100.9");
+
+    // Behind the proxy, the address it reports for the client is used.
+    let (server, _pool, _user_id) = setup_with_user().await;
+    let response = server
+        .post("/api/auth/login")
+        .add_header("X-Forwarded-For", "192.0.2.1, 203.0.113.9")
+        .json(&json!({ "username": "testuser", "password": "password123" }))
+        .await;
+    let tokens: serde_json::Value = response.json();
+    let sessions: Vec<serde_json::Value> = server
+        .get("/api/auth/sessions")
+        .add_header(
+            auth_name(),
+            auth_value(tokens["access_token"].as_str().unwrap()),
+        )
+        .await
+        .json();
+    assert_eq!(sessions[0]["ip"], "203.0.113.9");
+}
+
+#[tokio::test]
+async fn test_tokens_without_session_are_rejected() {
+    let (server, pool, user_id) = setup_with_user().await;
+    let secret = std::env::var("JWT_SECRET")
+        .unwrap_or_else(|_| "payme-secret-key-change-in-production".to_string());
+    let claims = json!({
+        "sub": user_id,
+        "username": "testuser",
+        "exp": (Utc::now() + Duration::days(30)).timestamp(),
+    });
+    let legacy = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap();
+    assert_eq!(me(&server, &json!(legacy)).await, 401);
+
+    let current = generate_token(&pool, user_id, "testuser").await;
+    assert_eq!(me(&server, &json!(current)).await, 200);
+}
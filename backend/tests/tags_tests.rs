@@ -0,0 +1,256 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_category, create_test_month, create_test_pool,
+    create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_tag(server: &axum_test::TestServer, token: &str, name: &str) -> i64 {
+    let response = server
+        .post("/api/tags")
+        .add_header(auth_name(), auth_value(token))
+        .json(&json!({ "name": name }))
+        .await;
+    response.assert_status_ok();
+    let tag: serde_json::Value = response.json();
+    tag["id"].as_i64().unwrap()
+}
+
+fn tag_names(item: &serde_json::Value) -> Vec<&str> {
+    item["tags"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_tag_crud() {
+    let (server, _pool, _user_id, token) = setup_with_user().await;
+    let id = create_tag(&server, &token, "vacation").await;
+    create_tag(&server, &token, "business").await;
+
+    let response = server
+        .post("/api/tags")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "vacation" }))
+        .await;
+    response.assert_status(axum::http::StatusCode::CONFLICT);
+
+    let response = server
+        .put(&format!("/api/tags/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "travel" }))
+        .await;
+    response.assert_status_ok();
+
+    let response = server
+        .put("/api/tags/999")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "name": "missing" }))
+        .await;
+    response.assert_status_not_found();
+
+    let tags: Vec<serde_json::Value> = server
+        .get("/api/tags")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let names: Vec<&str> = tags.iter().map(|t| t["name"].as_str().unwrap()).collect();
+    assert_eq!(names, vec!["business", "travel"]);
+
+    server
+        .delete(&format!("/api/tags/{id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    let tags: Vec<serde_json::Value> = server
+        .get("/api/tags")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(tags.len(), 1);
+}
+
+#[tokio::test]
+async fn test_tag_items_and_filter() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let category = create_test_category(&pool, user_id, "Food", 400.0).await;
+    let vacation = create_tag(&server, &token, "vacation").await;
+    let business = create_tag(&server, &token, "business").await;
+    let url = format!("/api/months/{month_id}/items");
+
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": category, "description": "Hotel dinner", "amount": 80.0, "spent_on": "2024-06-08", "tag_ids": [vacation, business] }))
+        .await;
+    response.assert_status_ok();
+    let item: serde_json::Value = response.json();
+    assert_eq!(tag_names(&item), vec!["business", "vacation"]);
+
+    server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": category, "description": "Groceries", "amount": 40.0, "spent_on": "2024-06-09" }))
+        .await
+        .assert_status_ok();
+
+    let items: Vec<serde_json::Value> = server
+        .get(&format!("{url}?tag={vacation}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["description"], "Hotel dinner");
+    assert_eq!(tag_names(&items[0]), vec!["business", "vacation"]);
+
+    let response = server
+        .put(&format!("{url}/{}", item["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "tag_ids": [business] }))
+        .await;
+    response.assert_status_ok();
+    let updated: serde_json::Value = response.json();
+    assert_eq!(tag_names(&updated), vec!["business"]);
+
+    let items: Vec<serde_json::Value> = server
+        .get(&format!("{url}?tag={vacation}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert!(items.is_empty());
+
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs: i64 =
+        sqlx::query_scalar("INSERT INTO tags (user_id, name) VALUES (?, 'theirs') RETURNING id")
+            .bind(other_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    let response = server
+        .post(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": category, "description": "Taxi", "amount": 20.0, "spent_on": "2024-06-10", "tag_ids": [theirs] }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_tag_report_spans_months() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let june = create_test_month(&pool, user_id, 2024, 6).await;
+    let july = create_test_month(&pool, user_id, 2024, 7).await;
+    let category = create_test_category(&pool, user_id, "Travel", 400.0).await;
+    let vacation = create_tag(&server, &token, "vacation").await;
+    let business = create_tag(&server, &token, "business").await;
+    create_tag(&server, &token, "unused").await;
+
+    for (month_id, amount, spent_on, tags) in [
+        (june, 300.0, "2024-06-28", vec![vacation]),
+        (july, 150.5, "2024-07-02", vec![vacation, business]),
+        (july, 99.0, "2024-07-20", vec![vacation]),
+    ] {
+        server
+            .post(&format!("/api/months/{month_id}/items"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "category_id": category, "description": "Trip", "amount": amount, "spent_on": spent_on, "tag_ids": tags }))
+            .await
+            .assert_status_ok();
+    }
+
+    let response = server
+        .get("/api/tags/report?from=2024-06-15&to=2024-07-10")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let report: Vec<serde_json::Value> = response.json();
+    let totals: Vec<(&str, f64, i64)> = report
+        .iter()
+        .map(|r| {
+            (
+                r["name"].as_str().unwrap(),
+                r["total"].as_f64().unwrap(),
+                r["item_count"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            ("vacation", 450.5, 2),
+            ("business", 150.5, 1),
+            ("unused", 0.0, 0)
+        ]
+    );
+
+    let response = server
+        .get("/api/tags/report?from=2024-07-10&to=2024-06-15")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_tags_survive_json_round_trip() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    let category = create_test_category(&pool, user_id, "Food", 400.0).await;
+    let vacation = create_tag(&server, &token, "vacation").await;
+    create_tag(&server, &token, "unused").await;
+    server
+        .post(&format!("/api/months/{month_id}/items"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "category_id": category, "description": "Dinner", "amount": 50.0, "spent_on": "2024-06-08", "tag_ids": [vacation] }))
+        .await
+        .assert_status_ok();
+
+    let exported: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(exported["tags"], json!(["unused", "vacation"]));
+    assert_eq!(
+        exported["months"][0]["items"][0]["tags"],
+        json!(["vacation"])
+    );
+
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+
+    let tags: Vec<serde_json::Value> = server
+        .get("/api/tags")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(tags.len(), 2);
+    let months: Vec<serde_json::Value> = server
+        .get("/api/months")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    let items: Vec<serde_json::Value> = server
+        .get(&format!("/api/months/{}/items", months[0]["id"]))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(tag_names(&items[0]), vec!["vacation"]);
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, close_test_month, create_test_category, create_test_item,
+    create_test_month, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::create_app;
+use serde_json::json;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, i64, String) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let app = create_app(pool.clone());
+    let server = create_test_server(app);
+    (server, pool, user_id, token)
+}
+
+async fn create_account(pool: &sqlx::SqlitePool, user_id: i64, name: &str, kind: &str) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO accounts (user_id, name, kind, opening_balance) VALUES (?, ?, ?, 100000) RETURNING id",
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(kind)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+async fn balances(server: &axum_test::TestServer, token: &str) -> Vec<f64> {
+    let accounts: Vec<serde_json::Value> = server
+        .get("/api/accounts")
+        .add_header(auth_name(), auth_value(token))
+        .await
+        .json();
+    accounts
+        .iter()
+        .map(|a| a["balance"].as_f64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_transfer_moves_money_without_counting_as_spending() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let savings = create_account(&pool, user_id, "Savings", "savings").await;
+    let food = create_test_category(&pool, user_id, "Food", 300.0).await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    create_test_item(&pool, month_id, food, "Groceries", 80.0, "2024-06-02").await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/transfers"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": checking, "to_account_id": savings, "amount": 250.0, "transferred_on": "2024-06-20" }))
+        .await;
+    response.assert_status_ok();
+
+    assert_eq!(balances(&server, &token).await, vec![750.0, 1250.0]);
+
+    let summary: serde_json::Value = server
+        .get(&format!("/api/months/{month_id}"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(summary["total_spent"], 80.0);
+    assert_eq!(summary["total_transferred"], 250.0);
+    assert_eq!(summary["transfers"][0]["from_account_name"], "Checking");
+    assert_eq!(summary["transfers"][0]["to_account_name"], "Savings");
+
+    let stats: serde_json::Value = server
+        .get("/api/stats")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(stats["average_monthly_spending"], 80.0);
+
+    let savings_view: serde_json::Value = server
+        .get("/api/savings")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(savings_view["savings"], 1250.0);
+}
+
+#[tokio::test]
+async fn test_create_transfer_rejects_invalid_accounts() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let other_id = create_test_user(&pool, "otheruser", "password456").await;
+    let theirs = create_account(&pool, other_id, "Theirs", "savings").await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+
+    for (from, to) in [(checking, checking), (checking, theirs), (theirs, checking)] {
+        let response = server
+            .post(&format!("/api/months/{month_id}/transfers"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "from_account_id": from, "to_account_id": to, "amount": 10.0, "transferred_on": "2024-06-20" }))
+            .await;
+        response.assert_status_bad_request();
+    }
+}
+
+#[tokio::test]
+async fn test_create_transfer_rejects_invalid_amount_and_date() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let savings = create_account(&pool, user_id, "Savings", "savings").await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+
+    for (amount, transferred_on) in [
+        (0.0, "2024-06-20"),
+        (-5.0, "2024-06-20"),
+        (10.0, "2024-05-31"),
+        (10.0, "2025-06-20"),
+    ] {
+        let response = server
+            .post(&format!("/api/months/{month_id}/transfers"))
+            .add_header(auth_name(), auth_value(&token))
+            .json(&json!({ "from_account_id": checking, "to_account_id": savings, "amount": amount, "transferred_on": transferred_on }))
+            .await;
+        response.assert_status_bad_request();
+    }
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transfers")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn test_transfer_in_closed_month_fails() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let savings = create_account(&pool, user_id, "Savings", "savings").await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    close_test_month(&pool, month_id).await;
+
+    let response = server
+        .post(&format!("/api/months/{month_id}/transfers"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": checking, "to_account_id": savings, "amount": 10.0, "transferred_on": "2024-06-20" }))
+        .await;
+    response.assert_status_bad_request();
+}
+
+#[tokio::test]
+async fn test_update_and_delete_transfer() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let savings = create_account(&pool, user_id, "Savings", "savings").await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+
+    let created: serde_json::Value = server
+        .post(&format!("/api/months/{month_id}/transfers"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": checking, "to_account_id": savings, "amount": 100.0, "transferred_on": "2024-06-20" }))
+        .await
+        .json();
+    let url = format!("/api/months/{month_id}/transfers/{}", created["id"]);
+
+    let response = server
+        .put(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": savings, "to_account_id": checking, "amount": 40.0, "transferred_on": "2024-06-21" }))
+        .await;
+    response.assert_status_ok();
+    assert_eq!(balances(&server, &token).await, vec![1040.0, 960.0]);
+
+    let listed: Vec<serde_json::Value> = server
+        .get(&format!("/api/months/{month_id}/transfers"))
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["transferred_on"], "2024-06-21");
+
+    server
+        .delete(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    assert_eq!(balances(&server, &token).await, vec![1000.0, 1000.0]);
+
+    let response = server
+        .put(&url)
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": savings, "to_account_id": checking, "amount": 40.0, "transferred_on": "2024-06-21" }))
+        .await;
+    response.assert_status_not_found();
+}
+
+#[tokio::test]
+async fn test_transfers_survive_json_round_trip() {
+    let (server, pool, user_id, token) = setup_with_user().await;
+    let checking = create_account(&pool, user_id, "Checking", "checking").await;
+    let savings = create_account(&pool, user_id, "Savings", "savings").await;
+    let month_id = create_test_month(&pool, user_id, 2024, 6).await;
+    server
+        .post(&format!("/api/months/{month_id}/transfers"))
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "from_account_id": checking, "to_account_id": savings, "amount": 300.0, "transferred_on": "2024-06-20" }))
+        .await
+        .assert_status_ok();
+
+    let exported: serde_json::Value = server
+        .get("/api/export/json")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(
+        exported["months"][0]["transfers"][0]["to_account"],
+        "Savings"
+    );
+
+    server
+        .post("/api/import/json")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&exported)
+        .await
+        .assert_status_ok();
+    assert_eq!(balances(&server, &token).await, vec![700.0, 1300.0]);
+}
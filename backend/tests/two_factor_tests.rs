@@ -0,0 +1,321 @@
+mod common;
+
+use common::{
+    auth_name, auth_value, create_test_pool, create_test_server, create_test_user, generate_token,
+};
+use payme::{create_app_with_clock, totp::Clock};
+use serde_json::json;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+/// Where every test's clock starts, at the beginning of a 30-second step.
+const NOW: u64 = 1_700_000_010;
+
+async fn setup_with_user() -> (axum_test::TestServer, sqlx::SqlitePool, String, Clock) {
+    let pool = create_test_pool().await;
+    let user_id = create_test_user(&pool, "testuser", "password123").await;
+    let token = generate_token(&pool, user_id, "testuser").await;
+    let clock = Clock::fixed(NOW);
+    let app = create_app_with_clock(pool.clone(), clock.clone());
+    let server = create_test_server(app);
+    (server, pool, token, clock)
+}
+
+/// The code an authenticator app shows for `secret` at `time`.
+fn code(secret: &str, time: u64) -> String {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+    TOTP::new_unchecked(Algorithm::SHA1, 6, 0, 30, bytes, None, String::new()).generate(time)
+}
+
+/// Turns on two-factor authentication at `NOW`, returning the secret and
+/// recovery codes.
+async fn enroll(server: &axum_test::TestServer, token: &str) -> (String, Vec<String>) {
+    let setup: serde_json::Value = server
+        .post("/api/auth/2fa/setup")
+        .add_header(auth_name(), auth_value(token))
+        .await
+        .json();
+    let secret = setup["secret"].as_str().unwrap().to_string();
+
+    let response = server
+        .post("/api/auth/2fa/confirm")
+        .add_header(auth_name(), auth_value(token))
+        .json(&json!({ "code": code(&secret, NOW) }))
+        .await;
+    response.assert_status_ok();
+    let body: serde_json::Value = response.json();
+    let codes = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c.as_str().unwrap().to_string())
+        .collect();
+    (secret, codes)
+}
+
+async fn login(server: &axum_test::TestServer) -> axum_test::TestResponse {
+    server
+        .post("/api/auth/login")
+        .json(&json!({ "username": "testuser", "password": "password123" }))
+        .await
+}
+
+async fn challenge(server: &axum_test::TestServer) -> serde_json::Value {
+    let response = login(server).await;
+    response.assert_status(axum::http::StatusCode::ACCEPTED);
+    let body: serde_json::Value = response.json();
+    body["challenge_token"].clone()
+}
+
+async fn verify(server: &axum_test::TestServer, challenge: &serde_json::Value, code: &str) -> u16 {
+    server
+        .post("/api/auth/login/verify")
+        .json(&json!({ "challenge_token": challenge, "code": code }))
+        .await
+        .status_code()
+        .as_u16()
+}
+
+#[tokio::test]
+async fn test_enroll_and_log_in_with_code() {
+    let (server, _pool, token, clock) = setup_with_user().await;
+
+    server
+        .post("/api/auth/2fa/confirm")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "code": "123456" }))
+        .await
+        .assert_status_bad_request();
+
+    let response = server
+        .post("/api/auth/2fa/setup")
+        .add_header(auth_name(), auth_value(&token))
+        .await;
+    response.assert_status_ok();
+    let setup: serde_json::Value = response.json();
+    let secret = setup["secret"].as_str().unwrap();
+    assert_eq!(
+        setup["provisioning_uri"],
+        format!("otpauth://totp/payme:testuser?secret={secret}&issuer=payme")
+    );
+    let near = [
+        code(secret, NOW - 30),
+        code(secret, NOW),
+        code(secret, NOW + 30),
+    ];
+    let wrong = ["000000", "111111", "222222", "333333"]
+        .into_iter()
+        .find(|c| !near.iter().any(|n| n == c))
+        .unwrap();
+    server
+        .post("/api/auth/2fa/confirm")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "code": wrong }))
+        .await
+        .assert_status_bad_request();
+    login(&server).await.assert_status_ok();
+
+    let (secret, recovery_codes) = enroll(&server, &token).await;
+    assert_eq!(recovery_codes.len(), 10);
+    let status: serde_json::Value = server
+        .get("/api/auth/2fa")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(
+        status,
+        json!({ "enabled": true, "recovery_codes_remaining": 10 })
+    );
+    server
+        .post("/api/auth/2fa/setup")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .assert_status(axum::http::StatusCode::CONFLICT);
+
+    let response = login(&server).await;
+    response.assert_status(axum::http::StatusCode::ACCEPTED);
+    assert!(response.maybe_cookie("token").is_none());
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["expires_in"], 300);
+    assert!(body.get("access_token").is_none());
+    let first = body["challenge_token"].clone();
+
+    // The enrollment code's step is used up until the next step begins.
+    assert_eq!(verify(&server, &first, &code(&secret, NOW)).await, 401);
+    clock.set(NOW + 30);
+    let response = server
+        .post("/api/auth/login/verify")
+        .json(&json!({ "challenge_token": first, "code": code(&secret, NOW + 30) }))
+        .await;
+    response.assert_status_ok();
+    let tokens: serde_json::Value = response.json();
+    assert_eq!(tokens["username"], "testuser");
+    server
+        .get("/api/auth/me")
+        .add_header(
+            auth_name(),
+            auth_value(tokens["access_token"].as_str().unwrap()),
+        )
+        .await
+        .assert_status_ok();
+
+    // Challenges and codes work once; codes one step ahead are still accepted.
+    assert_eq!(verify(&server, &first, &code(&secret, NOW + 60)).await, 401);
+    let second = challenge(&server).await;
+    assert_eq!(
+        verify(&server, &second, &code(&secret, NOW + 30)).await,
+        401
+    );
+    assert_eq!(
+        verify(&server, &second, &code(&secret, NOW + 120)).await,
+        401
+    );
+    assert_eq!(
+        verify(&server, &second, &code(&secret, NOW + 60)).await,
+        200
+    );
+}
+
+#[tokio::test]
+async fn test_recovery_codes_work_once() {
+    let (server, _pool, token, _clock) = setup_with_user().await;
+    let (_secret, recovery_codes) = enroll(&server, &token).await;
+
+    let first = challenge(&server).await;
+    let typed = recovery_codes[0].to_uppercase().replace('-', " ");
+    assert_eq!(verify(&server, &first, &typed).await, 200);
+
+    let second = challenge(&server).await;
+    assert_eq!(verify(&server, &second, &recovery_codes[0]).await, 401);
+    assert_eq!(verify(&server, &second, &recovery_codes[1]).await, 200);
+
+    let status: serde_json::Value = server
+        .get("/api/auth/2fa")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(status["recovery_codes_remaining"], 8);
+}
+
+#[tokio::test]
+async fn test_challenges_expire_and_limit_attempts() {
+    let (server, pool, token, _clock) = setup_with_user().await;
+    let (secret, recovery_codes) = enroll(&server, &token).await;
+
+    let limited = challenge(&server).await;
+    for _ in 0..5 {
+        assert_eq!(verify(&server, &limited, "not-a-recovery-code").await, 401);
+    }
+    assert_eq!(verify(&server, &limited, &recovery_codes[0]).await, 401);
+
+    let expired = challenge(&server).await;
+    sqlx::query("UPDATE login_challenges SET expires_at = datetime('now', '-1 minute')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        verify(&server, &expired, &code(&secret, NOW + 30)).await,
+        401
+    );
+    assert_eq!(
+        verify(&server, &json!("forged"), &recovery_codes[0]).await,
+        401
+    );
+}
+
+#[tokio::test]
+async fn test_disable_requires_password() {
+    let (server, _pool, token, _clock) = setup_with_user().await;
+    enroll(&server, &token).await;
+
+    server
+        .delete("/api/auth/2fa")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "password": "wrongpassword" }))
+        .await
+        .assert_status_unauthorized();
+    login(&server)
+        .await
+        .assert_status(axum::http::StatusCode::ACCEPTED);
+
+    server
+        .delete("/api/auth/2fa")
+        .add_header(auth_name(), auth_value(&token))
+        .json(&json!({ "password": "password123" }))
+        .await
+        .assert_status(axum::http::StatusCode::NO_CONTENT);
+    login(&server).await.assert_status_ok();
+    let status: serde_json::Value = server
+        .get("/api/auth/2fa")
+        .add_header(auth_name(), auth_value(&token))
+        .await
+        .json();
+    assert_eq!(
+        status,
+        json!({ "enabled": false, "recovery_codes_remaining": 0 })
+    );
+}
+
+#[tokio::test]
+async fn test_wrong_codes_lock_out_across_challenges() {
+    let (server, pool, token, _clock) = setup_with_user().await;
+    let (_secret, recovery_codes) = enroll(&server, &token).await;
+
+    for _ in 0..2 {
+        let fresh = challenge(&server).await;
+        assert_eq!(verify(&server, &fresh, "not-a-recovery-code").await, 401);
+        assert_eq!(verify(&server, &fresh, "not-a-recovery-code").await, 401);
+    }
+    let last = challenge(&server).await;
+    assert_eq!(verify(&server, &last, "not-a-recovery-code").await, 401);
+
+    let locked = challenge(&server).await;
+    let response = server
+        .post("/api/auth/login/verify")
+        .json(&json!({ "challenge_token": locked, "code": recovery_codes[0] }))
+        .await;
+    response.assert_status(axum::http::StatusCode::TOO_MANY_REQUESTS);
+    let body: serde_json::Value = response.json();
+    assert_eq!(body["code"], "too_many_requests");
+
+    sqlx::query("UPDATE users SET totp_locked_until = datetime('now', '-1 second')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(verify(&server, &locked, &recovery_codes[0]).await, 200);
+    let failures: i64 = sqlx::query_scalar("SELECT totp_failures FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(failures, 0);
+}
+
+#[tokio::test]
+async fn test_parallel_attempts_share_the_limit() {
+    let (server, pool, token, _clock) = setup_with_user().await;
+    enroll(&server, &token).await;
+
+    let target = challenge(&server).await;
+    let guess = || verify(&server, &target, "not-a-recovery-code");
+    let results = tokio::join!(
+        guess(),
+        guess(),
+        guess(),
+        guess(),
+        guess(),
+        guess(),
+        guess(),
+        guess()
+    );
+    assert_eq!(
+        [results.0, results.1, results.2, results.3, results.4, results.5, results.6, results.7],
+        [401; 8]
+    );
+
+    let (attempts, failures): (i64, i64) = sqlx::query_as(
+        "SELECT c.attempts, u.totp_failures FROM login_challenges c JOIN users u ON c.user_id = u.id",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((attempts, failures), (5, 5));
+}
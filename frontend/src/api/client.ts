@@ -1,20 +1,66 @@
 const BASE_URL = "/api";
 
+export class ApiError extends Error {
+  constructor(
+    public status: number,
+    public code: ErrorCode,
+    message: string,
+    public fields?: Record<string, FieldError[]>
+  ) {
+    super(message);
+  }
+}
+
+async function apiError(response: Response): Promise<ApiError> {
+  const body: Partial<ErrorBody> = await response.json().catch(() => ({}));
+  return new ApiError(
+    response.status,
+    body.code ?? "internal_error",
+    body.message ?? `HTTP ${response.status}`,
+    body.fields
+  );
+}
+
+let refreshing: Promise<boolean> | null = null;
+
+/** Renews the access token cookie, sharing one attempt between callers. */
+function refreshSession(): Promise<boolean> {
+  refreshing ??= fetch(`${BASE_URL}/auth/refresh`, {
+    method: "POST",
+    credentials: "include",
+  })
+    .then((response) => response.ok)
+    .catch(() => false)
+    .finally(() => {
+      refreshing = null;
+    });
+  return refreshing;
+}
+
+/** Fetches an API endpoint, refreshing an expired access token once. */
+async function send(endpoint: string, init: RequestInit = {}): Promise<Response> {
+  const attempt = () => fetch(`${BASE_URL}${endpoint}`, { ...init, credentials: "include" });
+  const response = await attempt();
+  if (response.status !== 401 || endpoint.startsWith("/auth/") || !(await refreshSession())) {
+    return response;
+  }
+  return attempt();
+}
+
 async function request<T>(
   endpoint: string,
   options: RequestInit = {}
 ): Promise<T> {
-  const response = await fetch(`${BASE_URL}${endpoint}`, {
+  const response = await send(endpoint, {
     ...options,
     headers: {
       "Content-Type": "application/json",
       ...options.headers,
     },
-    credentials: "include",
   });
 
   if (!response.ok) {
-    throw new Error(`HTTP ${response.status}`);
+    throw await apiError(response);
   }
 
   if (response.status === 204) {
@@ -24,6 +70,38 @@ async function request<T>(
   return response.json();
 }
 
+/**
+ * Fetches a paginated list. Without an explicit `limit`, follows the `Link`
+ * headers until every page has been read.
+ */
+async function requestList<T>(endpoint: string, params: { limit?: number } = {}): Promise<T[]> {
+  let next: string | null = withQuery(endpoint, params);
+  if (params.limit !== undefined) {
+    return request<T[]>(next);
+  }
+
+  const rows: T[] = [];
+  while (next) {
+    const response = await send(next);
+    if (!response.ok) {
+      throw await apiError(response);
+    }
+    rows.push(...((await response.json()) as T[]));
+    const link = response.headers.get("Link")?.match(/<([^>]+)>;\s*rel="next"/)?.[1];
+    next = link?.startsWith(BASE_URL) ? link.slice(BASE_URL.length) : null;
+  }
+  return rows;
+}
+
+function withQuery(endpoint: string, params: object = {}): string {
+  const query = new URLSearchParams(
+    Object.entries(params)
+      .filter(([, value]) => value !== undefined && value !== "")
+      .map(([key, value]) => [key, String(value)])
+  ).toString();
+  return query ? `${endpoint}?${query}` : endpoint;
+}
+
 export const api = {
   auth: {
     register: (username: string, password: string) =>
@@ -32,17 +110,50 @@ export const api = {
         body: JSON.stringify({ username, password }),
       }),
     login: (username: string, password: string) =>
-      request<{ id: number; username: string }>("/auth/login", {
+      request<{ id: number; username: string } | LoginChallenge>("/auth/login", {
         method: "POST",
         body: JSON.stringify({ username, password }),
       }),
+    verifyLogin: (challengeToken: string, code: string) =>
+      request<{ id: number; username: string }>("/auth/login/verify", {
+        method: "POST",
+        body: JSON.stringify({ challenge_token: challengeToken, code }),
+      }),
+    refresh: () => refreshSession(),
     logout: () => request<void>("/auth/logout", { method: "POST" }),
-    me: () => request<{ id: number; username: string }>("/auth/me"),
+    sessions: () => request<Session[]>("/auth/sessions"),
+    revokeSession: (id: number) => request<void>(`/auth/sessions/${id}`, { method: "DELETE" }),
+    revokeAllSessions: () => request<void>("/auth/sessions", { method: "DELETE" }),
+    tokens: () => request<ApiToken[]>("/auth/tokens"),
+    createToken: (name: string, scopes: TokenScope[], expiresInDays?: number) =>
+      request<CreatedApiToken>("/auth/tokens", {
+        method: "POST",
+        body: JSON.stringify({ name, scopes, expires_in_days: expiresInDays }),
+      }),
+    revokeToken: (id: number) => request<void>(`/auth/tokens/${id}`, { method: "DELETE" }),
+    twoFactor: () => request<TwoFactorStatus>("/auth/2fa"),
+    setupTwoFactor: () => request<TwoFactorSetup>("/auth/2fa/setup", { method: "POST" }),
+    confirmTwoFactor: (code: string) =>
+      request<{ recovery_codes: string[] }>("/auth/2fa/confirm", {
+        method: "POST",
+        body: JSON.stringify({ code }),
+      }),
+    disableTwoFactor: (password: string) =>
+      request<void>("/auth/2fa", {
+        method: "DELETE",
+        body: JSON.stringify({ password }),
+      }),
+    me: () => request<User>("/auth/me"),
     changeUsername: (newUsername: string) =>
-      request<{ id: number; username: string }>("/auth/change-username", {
+      request<User>("/auth/change-username", {
         method: "PUT",
         body: JSON.stringify({ new_username: newUsername }),
       }),
+    changeCurrency: (currency: Currency) =>
+      request<User>("/auth/change-currency", {
+        method: "PUT",
+        body: JSON.stringify({ currency }),
+      }),
     changePassword: (currentPassword: string, newPassword: string) =>
       request<{ message: string }>("/auth/change-password", {
         method: "PUT",
@@ -56,20 +167,36 @@ export const api = {
   },
 
   months: {
-    list: () => request<Month[]>("/months"),
+    list: (params: ListParams<"date"> & DateRange = {}) =>
+      requestList<Month>("/months", params),
     current: () => request<MonthSummary>("/months/current"),
+    create: (data: YearMonth) =>
+      request<Month>("/months", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    createRange: (data: { from: YearMonth; to: YearMonth }) =>
+      request<Month[]>("/months/bulk", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
     get: (id: number) => request<MonthSummary>(`/months/${id}`),
     close: (id: number) => request<Month>(`/months/${id}/close`, { method: "POST" }),
+    reopen: (id: number) => request<Month>(`/months/${id}/reopen`, { method: "POST" }),
+    snapshots: (id: number) => request<MonthSnapshot[]>(`/months/${id}/snapshots`),
+    downloadSnapshot: async (id: number, version: number) => {
+      const response = await send(`/months/${id}/snapshots/${version}`);
+      return response.blob();
+    },
     downloadPdf: async (id: number) => {
-      const response = await fetch(`${BASE_URL}/months/${id}/pdf`, {
-        credentials: "include",
-      });
+      const response = await send(`/months/${id}/pdf`);
       return response.blob();
     },
   },
 
   fixedExpenses: {
-    list: () => request<FixedExpense[]>("/fixed-expenses"),
+    list: (params: ListParams<"id" | "label" | "amount"> & AmountRange = {}) =>
+      request<FixedExpense[]>(withQuery("/fixed-expenses", params)),
     create: (data: { label: string; amount: number }) =>
       request<FixedExpense>("/fixed-expenses", {
         method: "POST",
@@ -84,14 +211,61 @@ export const api = {
       request<void>(`/fixed-expenses/${id}`, { method: "DELETE" }),
   },
 
+  incomeTemplates: {
+    list: () => request<IncomeTemplate[]>("/income-templates"),
+    create: (data: SaveIncomeTemplate) =>
+      request<IncomeTemplate>("/income-templates", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    update: (id: number, data: SaveIncomeTemplate) =>
+      request<IncomeTemplate>(`/income-templates/${id}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    delete: (id: number) =>
+      request<void>(`/income-templates/${id}`, { method: "DELETE" }),
+  },
+
+  recurring: {
+    list: () => request<RecurringTransaction[]>("/recurring"),
+    create: (data: SaveRecurring) =>
+      request<RecurringTransaction>("/recurring", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    update: (id: number, data: SaveRecurring) =>
+      request<RecurringTransaction>(`/recurring/${id}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    delete: (id: number) =>
+      request<void>(`/recurring/${id}`, { method: "DELETE" }),
+    updateOccurrence: (id: number, date: string, data: SaveOccurrence) =>
+      request<RecurringOverride>(`/recurring/${id}/occurrences/${date}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    restoreOccurrence: (id: number, date: string) =>
+      request<void>(`/recurring/${id}/occurrences/${date}`, { method: "DELETE" }),
+    upcoming: (days?: number) =>
+      request<UpcomingTransaction[]>(
+        days === undefined ? "/recurring/upcoming" : `/recurring/upcoming?days=${days}`
+      ),
+  },
+
   categories: {
-    list: () => request<BudgetCategory[]>("/categories"),
-    create: (data: { label: string; default_amount: number }) =>
+    list: (params: ListParams<"id" | "label" | "default_amount"> & AmountRange = {}) =>
+      request<BudgetCategory[]>(withQuery("/categories", params)),
+    create: (data: { label: string; default_amount: number; rollover_mode?: RolloverMode }) =>
       request<BudgetCategory>("/categories", {
         method: "POST",
         body: JSON.stringify(data),
       }),
-    update: (id: number, data: { label?: string; default_amount?: number }) =>
+    update: (
+      id: number,
+      data: { label?: string; default_amount?: number; rollover_mode?: RolloverMode }
+    ) =>
       request<BudgetCategory>(`/categories/${id}`, {
         method: "PUT",
         body: JSON.stringify(data),
@@ -110,8 +284,9 @@ export const api = {
   },
 
   income: {
-    list: (monthId: number) => request<IncomeEntry[]>(`/months/${monthId}/income`),
-    create: (monthId: number, data: { label: string; amount: number }) =>
+    list: (monthId: number, params: ListParams<"id" | "label" | "amount"> & AmountRange = {}) =>
+      requestList<IncomeEntry>(`/months/${monthId}/income`, params),
+    create: (monthId: number, data: { label: string; amount: number; account_id?: number | null }) =>
       request<IncomeEntry>(`/months/${monthId}/income`, {
         method: "POST",
         body: JSON.stringify(data),
@@ -119,7 +294,7 @@ export const api = {
     update: (
       monthId: number,
       incomeId: number,
-      data: { label?: string; amount?: number }
+      data: { label?: string; amount?: number; account_id?: number | null }
     ) =>
       request<IncomeEntry>(`/months/${monthId}/income/${incomeId}`, {
         method: "PUT",
@@ -129,11 +304,51 @@ export const api = {
       request<void>(`/months/${monthId}/income/${incomeId}`, { method: "DELETE" }),
   },
 
+  transfers: {
+    list: (monthId: number) =>
+      request<TransferWithAccounts[]>(`/months/${monthId}/transfers`),
+    create: (monthId: number, data: SaveTransfer) =>
+      request<Transfer>(`/months/${monthId}/transfers`, {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    update: (monthId: number, transferId: number, data: SaveTransfer) =>
+      request<Transfer>(`/months/${monthId}/transfers/${transferId}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    delete: (monthId: number, transferId: number) =>
+      request<void>(`/months/${monthId}/transfers/${transferId}`, { method: "DELETE" }),
+  },
+
   items: {
-    list: (monthId: number) => request<ItemWithCategory[]>(`/months/${monthId}/items`),
+    search: async (params: ItemSearchParams = {}) => {
+      const response = await send(withQuery("/items/search", params));
+      if (!response.ok) {
+        throw await apiError(response);
+      }
+      return {
+        items: (await response.json()) as ItemSearchResult[],
+        total: Number(response.headers.get("X-Total-Count") ?? 0),
+      };
+    },
+    list: (
+      monthId: number,
+      tagId?: number,
+      params: ListParams<"spent_on" | "amount" | "description"> & AmountRange & DateRange = {}
+    ) =>
+      requestList<ItemWithCategory>(`/months/${monthId}/items`, { tag: tagId, ...params }),
     create: (
       monthId: number,
-      data: { category_id: number; description: string; amount: number; spent_on: string }
+      data: {
+        category_id?: number;
+        description: string;
+        amount: number;
+        spent_on: string;
+        account_id?: number | null;
+        splits?: SaveSplit[];
+        tag_ids?: number[];
+      }
     ) =>
       request<Item>(`/months/${monthId}/items`, {
         method: "POST",
@@ -147,6 +362,9 @@ export const api = {
         description?: string;
         amount?: number;
         spent_on?: string;
+        account_id?: number | null;
+        splits?: SaveSplit[];
+        tag_ids?: number[];
       }
     ) =>
       request<Item>(`/months/${monthId}/items/${itemId}`, {
@@ -155,6 +373,47 @@ export const api = {
       }),
     delete: (monthId: number, itemId: number) =>
       request<void>(`/months/${monthId}/items/${itemId}`, { method: "DELETE" }),
+    attachments: (monthId: number, itemId: number) =>
+      request<Attachment[]>(`/months/${monthId}/items/${itemId}/attachments`),
+    attach: async (monthId: number, itemId: number, file: File) => {
+      const form = new FormData();
+      form.append("file", file);
+      const response = await send(`/months/${monthId}/items/${itemId}/attachments`, {
+        method: "POST",
+        body: form,
+      });
+      if (!response.ok) {
+        throw await apiError(response);
+      }
+      return response.json() as Promise<Attachment>;
+    },
+    downloadAttachment: async (monthId: number, itemId: number, attachmentId: number) => {
+      const response = await send(
+        `/months/${monthId}/items/${itemId}/attachments/${attachmentId}`
+      );
+      return response.blob();
+    },
+    deleteAttachment: (monthId: number, itemId: number, attachmentId: number) =>
+      request<void>(`/months/${monthId}/items/${itemId}/attachments/${attachmentId}`, {
+        method: "DELETE",
+      }),
+  },
+
+  tags: {
+    list: () => request<Tag[]>("/tags"),
+    create: (name: string) =>
+      request<Tag>("/tags", {
+        method: "POST",
+        body: JSON.stringify({ name }),
+      }),
+    update: (id: number, name: string) =>
+      request<Tag>(`/tags/${id}`, {
+        method: "PUT",
+        body: JSON.stringify({ name }),
+      }),
+    delete: (id: number) => request<void>(`/tags/${id}`, { method: "DELETE" }),
+    report: (from: string, to: string) =>
+      request<TagSpending[]>(`/tags/report?from=${from}&to=${to}`),
   },
 
   stats: {
@@ -162,12 +421,21 @@ export const api = {
   },
 
   exportDb: async () => {
-    const response = await fetch(`${BASE_URL}/export`, {
-      credentials: "include",
-    });
+    const response = await send("/export");
     return response.blob();
   },
 
+  importDb: async (file: Blob) => {
+    const response = await send("/import", {
+      method: "POST",
+      headers: { "Content-Type": "application/octet-stream" },
+      body: file,
+    });
+    if (!response.ok) {
+      throw await apiError(response);
+    }
+  },
+
   exportJson: async () => {
     return request<UserExport>("/export/json");
   },
@@ -179,6 +447,21 @@ export const api = {
     });
   },
 
+  accounts: {
+    list: () => request<Account[]>("/accounts"),
+    create: (data: SaveAccount) =>
+      request<Account>("/accounts", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    update: (id: number, data: SaveAccount) =>
+      request<Account>(`/accounts/${id}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    delete: (id: number) => request<void>(`/accounts/${id}`, { method: "DELETE" }),
+  },
+
   savings: {
     get: () => request<{ savings: number; savings_goal: number }>("/savings"),
     update: (savings: number) =>
@@ -191,6 +474,38 @@ export const api = {
         method: "PUT",
         body: JSON.stringify({ savings_goal }),
       }),
+    entries: () => request<LedgerEntry[]>("/savings/entries"),
+    createEntry: (data: CreateLedgerEntry) =>
+      request<LedgerEntry>("/savings/entries", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    deleteEntry: (id: number) =>
+      request<void>(`/savings/entries/${id}`, { method: "DELETE" }),
+    history: () => request<SavingsHistory>("/savings/history"),
+  },
+
+  goals: {
+    list: () => request<SavingsGoal[]>("/goals"),
+    create: (data: SaveGoal) =>
+      request<SavingsGoal>("/goals", {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    update: (id: number, data: SaveGoal) =>
+      request<SavingsGoal>(`/goals/${id}`, {
+        method: "PUT",
+        body: JSON.stringify(data),
+      }),
+    delete: (id: number) => request<void>(`/goals/${id}`, { method: "DELETE" }),
+    allocations: (id: number) => request<GoalAllocation[]>(`/goals/${id}/allocations`),
+    allocate: (id: number, data: { amount: number; allocated_on?: string }) =>
+      request<GoalAllocation>(`/goals/${id}/allocations`, {
+        method: "POST",
+        body: JSON.stringify(data),
+      }),
+    deleteAllocation: (goalId: number, allocationId: number) =>
+      request<void>(`/goals/${goalId}/allocations/${allocationId}`, { method: "DELETE" }),
   },
 
   retirementSavings: {
@@ -207,18 +522,64 @@ export interface UserExport {
   version: number;
   savings?: number;
   retirement_savings?: number;
+  accounts?: {
+    name: string;
+    kind: AccountKind;
+    opening_balance: number;
+    ledger_entries?: {
+      kind: LedgerEntryKind;
+      amount: number;
+      entry_date: string;
+      note: string | null;
+    }[];
+  }[];
+  goals?: {
+    name: string;
+    target_amount: number;
+    target_date: string;
+    priority: number;
+    account: string | null;
+    allocations?: { amount: number; allocated_on: string }[];
+  }[];
+  tags?: string[];
   fixed_expenses: { label: string; amount: number }[];
   categories: { label: string; default_amount: number }[];
   months: {
     year: number;
     month: number;
     is_closed: boolean;
-    income_entries: { label: string; amount: number }[];
+    fixed_expenses?: { label: string; amount: number }[];
+    income_entries: { label: string; amount: number; account?: string | null }[];
     budgets: { category_label: string; allocated_amount: number }[];
-    items: { category_label: string; description: string; amount: number; spent_on: string }[];
+    items: {
+      category_label: string;
+      description: string;
+      amount: number;
+      spent_on: string;
+      account?: string | null;
+      splits?: { category_label: string; amount: number }[];
+      tags?: string[];
+    }[];
+    transfers?: {
+      from_account: string;
+      to_account: string;
+      amount: number;
+      transferred_on: string;
+    }[];
   }[];
 }
 
+export interface YearMonth {
+  year: number;
+  month: number;
+}
+
+export interface MonthSnapshot {
+  month_id: number;
+  version: number;
+  created_at: string;
+}
+
 export interface Month {
   id: number;
   user_id: number;
@@ -235,11 +596,22 @@ export interface FixedExpense {
   amount: number;
 }
 
+export type RolloverMode = "none" | "carry_surplus" | "carry_surplus_and_deficit";
+
+export interface MonthlyFixedExpense {
+  id: number;
+  month_id: number;
+  fixed_expense_id: number | null;
+  label: string;
+  amount: number;
+}
+
 export interface BudgetCategory {
   id: number;
   user_id: number;
   label: string;
   default_amount: number;
+  rollover_mode: RolloverMode;
 }
 
 export interface MonthlyBudget {
@@ -255,14 +627,168 @@ export interface MonthlyBudgetWithCategory {
   category_id: number;
   category_label: string;
   allocated_amount: number;
+  carried_amount: number;
   spent_amount: number;
 }
 
+export type Schedule = "monthly" | "biweekly" | "semi_monthly" | "annual";
+
+export interface SaveIncomeTemplate {
+  label: string;
+  amount: number;
+  schedule: Schedule;
+  anchor_date?: string | null;
+}
+
+export interface IncomeTemplate extends SaveIncomeTemplate {
+  id: number;
+  user_id: number;
+  anchor_date: string | null;
+}
+
+export type Frequency = "weekly" | "monthly";
+
+export interface SaveRecurring {
+  category_id: number;
+  description: string;
+  amount: number;
+  frequency: Frequency;
+  interval?: number;
+  start_date: string;
+  day_of_month?: number | null;
+  end_date?: string | null;
+}
+
+export interface RecurringTransaction extends SaveRecurring {
+  id: number;
+  user_id: number;
+  interval: number;
+  day_of_month: number | null;
+  end_date: string | null;
+}
+
+export interface SaveOccurrence {
+  skipped?: boolean;
+  amount?: number | null;
+  description?: string | null;
+}
+
+export interface RecurringOverride {
+  recurring_id: number;
+  occurs_on: string;
+  skipped: boolean;
+  amount: number | null;
+  description: string | null;
+}
+
+export interface UpcomingTransaction {
+  recurring_id: number;
+  category_id: number;
+  description: string;
+  amount: number;
+  due_on: string;
+}
+
+export type AccountKind =
+  | "checking"
+  | "savings"
+  | "credit_card"
+  | "cash"
+  | "retirement"
+  | "brokerage";
+
+export interface SaveAccount {
+  name: string;
+  kind: AccountKind;
+  opening_balance?: number;
+}
+
+export interface Account extends SaveAccount {
+  id: number;
+  user_id: number;
+  opening_balance: number;
+  balance: number;
+}
+
+export type LedgerEntryKind = "deposit" | "withdrawal" | "correction";
+
+export interface LedgerEntry {
+  id: number;
+  account_id: number;
+  kind: LedgerEntryKind;
+  amount: number;
+  entry_date: string;
+  note: string | null;
+}
+
+export interface CreateLedgerEntry {
+  account_id?: number;
+  kind: LedgerEntryKind;
+  amount: number;
+  entry_date?: string;
+  note?: string;
+}
+
+export interface SavingsHistory {
+  opening_balance: number;
+  balances: { date: string; balance: number }[];
+  contributions: { year: number; month: number; amount: number }[];
+  projection: {
+    savings_goal: number;
+    monthly_contribution: number;
+    months_to_goal: number | null;
+    projected_date: string | null;
+  };
+}
+
+export interface SaveGoal {
+  name: string;
+  target_amount: number;
+  target_date: string;
+  priority?: number;
+  account_id?: number | null;
+}
+
+export interface SavingsGoal extends SaveGoal {
+  id: number;
+  user_id: number;
+  priority: number;
+  account_id: number | null;
+  saved: number;
+  remaining: number;
+  required_monthly: number;
+}
+
+export interface GoalAllocation {
+  id: number;
+  goal_id: number;
+  amount: number;
+  allocated_on: string;
+}
+
+export interface SaveTransfer {
+  from_account_id: number;
+  to_account_id: number;
+  amount: number;
+  transferred_on: string;
+}
+
+export interface Transfer extends SaveTransfer {
+  id: number;
+  month_id: number;
+}
+
+export interface TransferWithAccounts extends Transfer {
+  from_account_name: string;
+  to_account_name: string;
+}
+
 export interface IncomeEntry {
   id: number;
   month_id: number;
   label: string;
   amount: number;
+  account_id: number | null;
 }
 
 export interface Item {
@@ -272,6 +798,146 @@ export interface Item {
   description: string;
   amount: number;
   spent_on: string;
+  account_id: number | null;
+  splits: ItemSplit[];
+  tags: Tag[];
+}
+
+export interface SaveSplit {
+  category_id: number;
+  amount: number;
+}
+
+export interface ItemSplit extends SaveSplit {
+  id: number;
+  item_id: number;
+  category_label: string;
+}
+
+export interface Session {
+  id: number;
+  user_agent: string | null;
+  ip: string | null;
+  created_at: string;
+  last_seen_at: string;
+  expires_at: string;
+  current: boolean;
+}
+
+export type TokenScope = "read" | "write" | "import_export";
+
+export type Currency = "USD" | "EUR" | "GBP" | "CAD" | "AUD";
+
+export interface User {
+  id: number;
+  username: string;
+  currency: Currency;
+}
+
+export interface ApiToken {
+  id: number;
+  name: string;
+  prefix: string;
+  scopes: TokenScope[];
+  created_at: string;
+  expires_at: string | null;
+  last_used_at: string | null;
+}
+
+export interface CreatedApiToken extends ApiToken {
+  secret: string;
+}
+
+export interface LoginChallenge {
+  challenge_token: string;
+  expires_in: number;
+}
+
+export interface TwoFactorStatus {
+  enabled: boolean;
+  recovery_codes_remaining: number;
+}
+
+export interface TwoFactorSetup {
+  secret: string;
+  provisioning_uri: string;
+}
+
+export type ErrorCode =
+  | "validation_failed"
+  | "bad_request"
+  | "month_closed"
+  | "invalid_reference"
+  | "unauthorized"
+  | "forbidden"
+  | "not_found"
+  | "conflict"
+  | "too_many_requests"
+  | "internal_error";
+
+export interface FieldError {
+  code: string;
+  message: string | null;
+  params: Record<string, unknown>;
+}
+
+export interface ErrorBody {
+  code: ErrorCode;
+  message: string;
+  fields?: Record<string, FieldError[]>;
+}
+
+export interface ListParams<Sort extends string> {
+  sort?: Sort;
+  direction?: "asc" | "desc";
+  limit?: number;
+  offset?: number;
+}
+
+export interface AmountRange {
+  min_amount?: number;
+  max_amount?: number;
+}
+
+export interface DateRange {
+  from?: string;
+  to?: string;
+}
+
+export interface ItemSearchParams
+  extends ListParams<"spent_on" | "amount" | "description">,
+    AmountRange,
+    DateRange {
+  q?: string;
+  category_id?: number;
+  tag?: number;
+}
+
+export interface ItemSearchResult extends ItemWithCategory {
+  year: number;
+  month: number;
+}
+
+export interface Attachment {
+  id: number;
+  item_id: number;
+  filename: string;
+  content_type: string;
+  size: number;
+  created_at: string;
+}
+
+export interface Tag {
+  id: number;
+  user_id: number;
+  name: string;
+}
+
+export interface TagSpending {
+  tag_id: number;
+  name: string;
+  total: number;
+  item_count: number;
 }
 
 export interface ItemWithCategory extends Item {
@@ -280,14 +946,18 @@ export interface ItemWithCategory extends Item {
 
 export interface MonthSummary {
   month: Month;
+  currency: Currency;
   income_entries: IncomeEntry[];
-  fixed_expenses: FixedExpense[];
+  fixed_expenses: MonthlyFixedExpense[];
   budgets: MonthlyBudgetWithCategory[];
   items: ItemWithCategory[];
+  transfers: TransferWithAccounts[];
+  goals: SavingsGoal[];
   total_income: number;
   total_fixed: number;
   total_budgeted: number;
   total_spent: number;
+  total_transferred: number;
   remaining: number;
 }
 
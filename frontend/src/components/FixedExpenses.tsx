@@ -1,29 +1,40 @@
 import { useState } from "react";
 import { Plus, Trash2, Edit2, Check, X, Settings } from "lucide-react";
-import { FixedExpense, api } from "../api/client";
+import { FixedExpense, MonthlyFixedExpense, api } from "../api/client";
 import { Card } from "./ui/Card";
 import { Input } from "./ui/Input";
 import { Button } from "./ui/Button";
 import { Modal } from "./ui/Modal";
 
 interface FixedExpensesProps {
-  expenses: FixedExpense[];
+  expenses: MonthlyFixedExpense[];
   onUpdate: () => void;
 }
 
 export function FixedExpenses({ expenses, onUpdate }: FixedExpensesProps) {
   const [isManaging, setIsManaging] = useState(false);
+  const [templates, setTemplates] = useState<FixedExpense[]>([]);
   const [isAdding, setIsAdding] = useState(false);
   const [editingId, setEditingId] = useState<number | null>(null);
   const [label, setLabel] = useState("");
   const [amount, setAmount] = useState("");
 
+  const refreshTemplates = async () => {
+    setTemplates(await api.fixedExpenses.list());
+  };
+
+  const openManager = async () => {
+    await refreshTemplates();
+    setIsManaging(true);
+  };
+
   const handleAdd = async () => {
     if (!label || !amount) return;
     await api.fixedExpenses.create({ label, amount: parseFloat(amount) });
     setLabel("");
     setAmount("");
     setIsAdding(false);
+    await refreshTemplates();
     await onUpdate();
   };
 
@@ -33,11 +44,13 @@ export function FixedExpenses({ expenses, onUpdate }: FixedExpensesProps) {
     setEditingId(null);
     setLabel("");
     setAmount("");
+    await refreshTemplates();
     await onUpdate();
   };
 
   const handleDelete = async (id: number) => {
     await api.fixedExpenses.delete(id);
+    await refreshTemplates();
     await onUpdate();
   };
 
@@ -64,7 +77,7 @@ export function FixedExpenses({ expenses, onUpdate }: FixedExpensesProps) {
             Fixed Expenses
           </h3>
           <button
-            onClick={() => setIsManaging(true)}
+            onClick={openManager}
             className="p-1 hover:bg-sand-200 dark:hover:bg-charcoal-800 transition-colors"
           >
             <Settings size={16} />
@@ -106,7 +119,7 @@ export function FixedExpenses({ expenses, onUpdate }: FixedExpensesProps) {
 
       <Modal isOpen={isManaging} onClose={() => setIsManaging(false)} title="Manage Fixed Expenses">
         <div className="space-y-3">
-          {expenses.map((expense) => (
+          {templates.map((expense) => (
             <div key={expense.id}>
               {editingId === expense.id ? (
                 <div className="flex items-end gap-2">
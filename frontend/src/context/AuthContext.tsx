@@ -1,5 +1,5 @@
 import { createContext, useContext, useEffect, useState, ReactNode } from "react";
-import { api } from "../api/client";
+import { api, LoginChallenge } from "../api/client";
 
 interface User {
   id: number;
@@ -9,7 +9,9 @@ interface User {
 interface AuthContextType {
   user: User | null;
   loading: boolean;
-  login: (username: string, password: string) => Promise<void>;
+  /** Resolves to a challenge when the account needs a second factor. */
+  login: (username: string, password: string) => Promise<LoginChallenge | null>;
+  verifyLogin: (challengeToken: string, code: string) => Promise<void>;
   register: (username: string, password: string) => Promise<void>;
   logout: () => Promise<void>;
   updateUsername: (username: string) => void;
@@ -30,7 +32,16 @@ export function AuthProvider({ children }: { children: ReactNode }) {
   }, []);
 
   const login = async (username: string, password: string) => {
-    const user = await api.auth.login(username, password);
+    const result = await api.auth.login(username, password);
+    if ("challenge_token" in result) {
+      return result;
+    }
+    setUser(result);
+    return null;
+  };
+
+  const verifyLogin = async (challengeToken: string, code: string) => {
+    const user = await api.auth.verifyLogin(challengeToken, code);
     setUser(user);
   };
 
@@ -54,7 +65,7 @@ export function AuthProvider({ children }: { children: ReactNode }) {
   };
 
   return (
-    <AuthContext.Provider value={{ user, loading, login, register, logout, updateUsername }}>
+    <AuthContext.Provider value={{ user, loading, login, verifyLogin, register, logout, updateUsername }}>
       {children}
     </AuthContext.Provider>
   );
@@ -9,9 +9,11 @@ interface LoginProps {
 }
 
 export function Login({ onSwitchToRegister }: LoginProps) {
-  const { login } = useAuth();
+  const { login, verifyLogin } = useAuth();
   const [username, setUsername] = useState("");
   const [password, setPassword] = useState("");
+  const [challenge, setChallenge] = useState<string | null>(null);
+  const [code, setCode] = useState("");
   const [error, setError] = useState("");
   const [loading, setLoading] = useState(false);
 
@@ -21,9 +23,14 @@ export function Login({ onSwitchToRegister }: LoginProps) {
     setLoading(true);
 
     try {
-      await login(username, password);
+      if (challenge) {
+        await verifyLogin(challenge, code);
+      } else {
+        const result = await login(username, password);
+        setChallenge(result?.challenge_token ?? null);
+      }
     } catch {
-      setError("Invalid credentials");
+      setError(challenge ? "Invalid code" : "Invalid credentials");
     } finally {
       setLoading(false);
     }
@@ -37,20 +44,34 @@ export function Login({ onSwitchToRegister }: LoginProps) {
         </h1>
 
         <form onSubmit={handleSubmit} className="space-y-4">
-          <Input
-            type="text"
-            placeholder="Username"
-            value={username}
-            onChange={(e) => setUsername(e.target.value)}
-            required
-          />
-          <Input
-            type="password"
-            placeholder="Password"
-            value={password}
-            onChange={(e) => setPassword(e.target.value)}
-            required
-          />
+          {challenge ? (
+            <Input
+              type="text"
+              inputMode="numeric"
+              autoComplete="one-time-code"
+              placeholder="Authentication or recovery code"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+              required
+            />
+          ) : (
+            <>
+              <Input
+                type="text"
+                placeholder="Username"
+                value={username}
+                onChange={(e) => setUsername(e.target.value)}
+                required
+              />
+              <Input
+                type="password"
+                placeholder="Password"
+                value={password}
+                onChange={(e) => setPassword(e.target.value)}
+                required
+              />
+            </>
+          )}
 
           {error && (
             <div className="text-sm text-terracotta-600 dark:text-terracotta-400">
@@ -59,7 +80,7 @@ export function Login({ onSwitchToRegister }: LoginProps) {
           )}
 
           <Button type="submit" className="w-full" disabled={loading}>
-            {loading ? "..." : "Sign In"}
+            {loading ? "..." : challenge ? "Verify" : "Sign In"}
           </Button>
         </form>
 